@@ -1,8 +1,10 @@
 extern crate ordcode;
+use ordcode::primitives::SerializableValue;
+
 fn main() {
-    let mut buf = [0u8; 8];
+    let mut buf = [0u8; 32];
     let mut w = ordcode::DeBytesWriter::new(&mut buf);
-    ordcode::primitives::serialize_u32(&mut w, 1234, ordcode::params::DescendingOrder).unwrap();
+    (1234_u32, -1_i64, Some([1_u8; 4]), "key".to_string()).to_writer(&mut w, ordcode::params::DescendingOrder).unwrap();
 
     println!("Hello, {:#?}!", buf);
 }
@@ -0,0 +1,64 @@
+//! Derive macros for [`ordcode`](https://docs.rs/ordcode) crate
+//!
+//! `#[derive(FixedSize)]` implements `ordcode::FixedSize` for structs and enums, from encoded sizes
+//! of their fields. Type parameters are required to implement `FixedSize`. Please see
+//! `ordcode::fixed_size` module documentation for details.
+
+#![deny(clippy::all, clippy::pedantic)]
+
+use std::convert::TryFrom;
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{ parse_macro_input, parse_quote, Data, DeriveInput, Fields };
+
+/// Derive `ordcode::FixedSize` for struct or enum
+#[proc_macro_derive(FixedSize)]
+pub fn derive_fixed_size(input: TokenStream) -> TokenStream {
+    let mut input = parse_macro_input!(input as DeriveInput);
+
+    let (min_size, max_size) = match &input.data {
+        Data::Struct(data) => fields_size(&data.fields),
+        Data::Enum(data) => {
+            if u32::try_from(data.variants.len()).is_err() {
+                return syn::Error::new_spanned(&input.ident, "too many enum variants")
+                    .to_compile_error().into();
+            }
+            let sizes = data.variants.iter().zip(0_u32..).map(|(variant, index)| {
+                let (min_size, max_size) = fields_size(&variant.fields);
+                let discr_size = quote!(::ordcode::fixed_size::discriminant_size(#index));
+                (quote!(#discr_size + #min_size), quote!(#discr_size + #max_size))
+            });
+            sizes.reduce(|(min_acc, max_acc), (min_size, max_size)| {
+                (quote!(::ordcode::fixed_size::min(#min_acc, #min_size)),
+                 quote!(::ordcode::fixed_size::max(#max_acc, #max_size)))
+            }).unwrap_or((quote!(0), quote!(0)))
+        },
+        Data::Union(_) => {
+            return syn::Error::new_spanned(&input.ident, "FixedSize can't be derived for unions")
+                .to_compile_error().into();
+        },
+    };
+
+    let type_params: Vec<_> = input.generics.type_params().map(|p| p.ident.clone()).collect();
+    let where_clause = input.generics.make_where_clause();
+    for param in type_params {
+        where_clause.predicates.push(parse_quote!(#param: ::ordcode::FixedSize));
+    }
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    quote!(
+        impl #impl_generics ::ordcode::FixedSize for #name #ty_generics #where_clause {
+            const MIN_ENCODED_SIZE: usize = #min_size;
+            const MAX_ENCODED_SIZE: usize = #max_size;
+        }
+    ).into()
+}
+
+// Sums of minimum and maximum encoded sizes of fields
+fn fields_size(fields: &Fields) -> (TokenStream2, TokenStream2) {
+    let types: Vec<_> = fields.iter().map(|f| &f.ty).collect();
+    (quote!(0 #(+ <#types as ::ordcode::FixedSize>::MIN_ENCODED_SIZE)*),
+     quote!(0 #(+ <#types as ::ordcode::FixedSize>::MAX_ENCODED_SIZE)*))
+}
@@ -0,0 +1,375 @@
+//! Command-line tool for decoding, encoding and inspecting keys
+//!
+//! Run `ordcode --help` for usage. Requires `cli` feature.
+
+#![deny(clippy::all, clippy::pedantic)]
+
+use std::convert::{ TryFrom, TryInto };
+use std::io::{ self, BufRead, Read, Write };
+use ordcode::{ params, primitives };
+use ordcode::params::SerializerParams;
+use ordcode::value::{ Schema, Value };
+use serde_json::Value as Json;
+
+const USAGE: &str = "\
+Usage: ordcode <decode|encode|layout> --schema SCHEMA [OPTIONS] [INPUT...]
+
+Commands:
+  decode    decode keys into JSON text, one per line
+  encode    encode JSON values into keys, one per line
+  layout    show layout of encoded key: bytes of each field at the head and at the tail
+
+Keys or JSON values are taken from INPUT arguments, from --file, or from stdin, one per line.
+
+Options:
+  -s, --schema SCHEMA   schema of values, like \"(u32, str, option<u64>)\", see `ordcode::value`
+  -p, --preset PRESET   serializer parameters: ascending (default), portable, native
+  -o, --order ORDER     asc (default) or desc
+  -f, --format FORMAT   format of keys: hex (default), base64, raw (single key, whole input)
+      --file PATH       read input from file
+  -h, --help            print this help
+";
+
+type CliResult<T = ()> = Result<T, String>;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Command { Decode, Encode, Layout }
+
+#[derive(Clone, Copy, PartialEq)]
+enum Format { Hex, Base64, Raw }
+
+struct Options {
+    command: Command,
+    schema: Schema,
+    descending: bool,
+    format: Format,
+    file: Option<String>,
+    inputs: Vec<String>,
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.is_empty() || args.iter().any(|a| a == "-h" || a == "--help") {
+        print!("{USAGE}");
+        return;
+    }
+    if let Err(e) = run(&args) {
+        eprintln!("ordcode: {e}");
+        std::process::exit(1);
+    }
+}
+
+fn run(args: &[String]) -> CliResult {
+    let mut command = None;
+    let mut schema = None;
+    let mut preset = "ascending".to_string();
+    let mut descending = false;
+    let mut format = Format::Hex;
+    let mut file = None;
+    let mut inputs = Vec::new();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().cloned().ok_or_else(|| format!("missing value for {arg}"));
+        match arg.as_str() {
+            "-s" | "--schema" => schema = Some(value()?.parse::<Schema>().map_err(|e| e.to_string())?),
+            "-p" | "--preset" => preset = value()?,
+            "-o" | "--order" => descending = match value()?.as_str() {
+                "asc" => false,
+                "desc" => true,
+                v => return Err(format!("unknown order: {v}")),
+            },
+            "-f" | "--format" => format = match value()?.as_str() {
+                "hex" => Format::Hex,
+                "base64" => Format::Base64,
+                "raw" => Format::Raw,
+                v => return Err(format!("unknown format: {v}")),
+            },
+            "--file" => file = Some(value()?),
+            "decode" | "encode" | "layout" if command.is_none() => command = Some(match arg.as_str() {
+                "decode" => Command::Decode,
+                "encode" => Command::Encode,
+                _ => Command::Layout,
+            }),
+            a if a.starts_with('-') && a.len() > 1 => return Err(format!("unknown option: {a}")),
+            _ => inputs.push(arg.clone()),
+        }
+    }
+    let opts = Options {
+        command: command.ok_or("missing command, see --help")?,
+        schema: schema.ok_or("missing --schema")?,
+        descending, format, file, inputs,
+    };
+    match preset.as_str() {
+        "ascending" => execute(&opts, params::AscendingOrder),
+        "portable" => execute(&opts, params::PortableBinary),
+        "native" => execute(&opts, params::NativeBinary),
+        p => Err(format!("unknown preset: {p}")),
+    }
+}
+
+fn execute<P: SerializerParams>(opts: &Options, params: P) -> CliResult {
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    if opts.command == Command::Encode {
+        for input in read_inputs(opts, false)? {
+            let text = String::from_utf8(input).map_err(|_| "input is not valid UTF-8".to_string())?;
+            let json = serde_json::from_str(&text).map_err(|e| format!("invalid JSON: {e}"))?;
+            let mut key = from_json(&json, &opts.schema)?.to_vec(&opts.schema, params).map_err(|e| e.to_string())?;
+            if opts.descending {
+                primitives::invert_buffer(&mut key);
+            }
+            match opts.format {
+                Format::Hex => writeln!(out, "{}", to_hex(&key)),
+                Format::Base64 => writeln!(out, "{}", to_base64(&key)),
+                Format::Raw => out.write_all(&key),
+            }.map_err(|e| e.to_string())?;
+        }
+        return Ok(());
+    }
+    for input in read_inputs(opts, true)? {
+        let mut key = match opts.format {
+            Format::Hex => from_hex(&input)?,
+            Format::Base64 => from_base64(&input)?,
+            Format::Raw => input,
+        };
+        if opts.descending {
+            primitives::invert_buffer(&mut key);
+        }
+        let value = Value::from_bytes(&key, &opts.schema, params).map_err(|e| e.to_string())?;
+        if opts.command == Command::Decode {
+            writeln!(out, "{}", to_json(&value, &opts.schema)).map_err(|e| e.to_string())?;
+        } else {
+            print_layout(&mut out, &value, &key, opts, params)?;
+        }
+    }
+    Ok(())
+}
+
+// Inputs from arguments, file or stdin: whole input for raw keys, or non-empty lines
+fn read_inputs(opts: &Options, keys: bool) -> CliResult<Vec<Vec<u8>>> {
+    let raw = keys && opts.format == Format::Raw;
+    if !opts.inputs.is_empty() {
+        if raw {
+            return Err("raw keys can't be passed as arguments, use --file or stdin".to_string());
+        }
+        return Ok(opts.inputs.iter().map(|s| s.as_bytes().to_vec()).collect());
+    }
+    let mut reader: Box<dyn BufRead> = match &opts.file {
+        Some(path) => Box::new(io::BufReader::new(std::fs::File::open(path).map_err(|e| format!("{path}: {e}"))?)),
+        None => Box::new(io::BufReader::new(io::stdin())),
+    };
+    if raw {
+        let mut buf = Vec::new();
+        reader.read_to_end(&mut buf).map_err(|e| e.to_string())?;
+        return Ok(vec![buf]);
+    }
+    let mut inputs = Vec::new();
+    for line in reader.lines() {
+        let line = line.map_err(|e| e.to_string())?;
+        if !line.trim().is_empty() {
+            inputs.push(line.trim().as_bytes().to_vec());
+        }
+    }
+    Ok(inputs)
+}
+
+fn print_layout<P: SerializerParams>(out: &mut impl Write, value: &Value, key: &[u8], opts: &Options, params: P) -> CliResult {
+    let mut encoded = value.to_vec(&opts.schema, params).map_err(|e| e.to_string())?;
+    if encoded != key {
+        writeln!(out, "note: key is not in canonical encoding, showing layout of re-encoded value")
+            .map_err(|e| e.to_string())?;
+    }
+    let layout = ordcode::calc_layout(&value.with_schema(&opts.schema, params), params).map_err(|e| e.to_string())?;
+    if opts.descending {
+        primitives::invert_buffer(&mut encoded);
+    }
+    writeln!(out, "{}", to_json(value, &opts.schema)).map_err(|e| e.to_string())?;
+
+    let range = |r: std::ops::Range<usize>| if r.is_empty() { "-".to_string() } else { format!("{}..{}", r.start, r.end) };
+    let width = layout.iter().map(|entry| entry.path().len()).max().unwrap_or(0).max("<root>".len());
+    for entry in &layout {
+        let path = if entry.path().is_empty() { "<root>" } else { entry.path() };
+        let mut bytes = to_hex(&encoded[entry.head()]);
+        if !entry.tail().is_empty() {
+            bytes.push(' ');
+            bytes.push_str(&to_hex(&encoded[entry.tail()]));
+        }
+        writeln!(out, "  {:width$}  {:<10} {:<10} {}", path, range(entry.head()), range(entry.tail()), bytes)
+            .map_err(|e| e.to_string())?;
+    }
+    writeln!(out, "  head {} bytes, tail {} bytes", layout.head_size(), layout.tail_size()).map_err(|e| e.to_string())
+}
+
+fn to_json(value: &Value, schema: &Schema) -> Json {
+    match (schema, value) {
+        (Schema::Desc(schema), v) => to_json(v, schema),
+        (_, Value::Bool(v)) => Json::from(*v),
+        (_, Value::U8(v)) => Json::from(*v),
+        (_, Value::U16(v)) => Json::from(*v),
+        (_, Value::U32(v)) => Json::from(*v),
+        (_, Value::U64(v)) => Json::from(*v),
+        (_, Value::I8(v)) => Json::from(*v),
+        (_, Value::I16(v)) => Json::from(*v),
+        (_, Value::I32(v)) => Json::from(*v),
+        (_, Value::I64(v)) => Json::from(*v),
+        (_, Value::U128(v)) => u64::try_from(*v).map_or_else(|_| Json::from(v.to_string()), Json::from),
+        (_, Value::I128(v)) => i64::try_from(*v).map_or_else(|_| Json::from(v.to_string()), Json::from),
+        (_, Value::F32(v)) => Json::from(*v),
+        (_, Value::F64(v)) => Json::from(*v),
+        (_, Value::Char(v)) => Json::from(v.to_string()),
+        (_, Value::Str(v)) => Json::from(v.as_str()),
+        (_, Value::Bytes(v)) => Json::from(to_hex(v)),
+        (Schema::Option(schema), Value::Option(v)) => v.as_ref().map_or(Json::Null, |v| to_json(v, schema)),
+        (Schema::Seq(schema), Value::Seq(items)) => items.iter().map(|v| to_json(v, schema)).collect(),
+        (Schema::Map(k, v), Value::Map(entries)) => match **k {
+            Schema::Str => Json::Object(entries.iter().map(|(key, value)| match key {
+                Value::Str(key) => (key.clone(), to_json(value, v)),
+                _ => (String::new(), Json::Null),
+            }).collect()),
+            _ => entries.iter().map(|(key, value)| Json::Array(vec![to_json(key, k), to_json(value, v)])).collect(),
+        },
+        (Schema::Tuple(schemas), Value::Tuple(fields)) => schemas.iter().zip(fields).map(|(s, v)| to_json(v, s)).collect(),
+        (Schema::Struct(schemas), Value::Tuple(fields)) => Json::Object(schemas.iter().zip(fields)
+            .map(|((name, s), v)| (name.clone(), to_json(v, s))).collect()),
+        (Schema::Enum(variants), Value::Enum(index, v)) => match variants.get(*index as usize) {
+            Some((name, Schema::Unit)) => Json::from(name.as_str()),
+            Some((name, schema)) => Json::Object(std::iter::once((name.clone(), to_json(v, schema))).collect()),
+            None => Json::Null,
+        },
+        _ => Json::Null,
+    }
+}
+
+fn from_json(json: &Json, schema: &Schema) -> CliResult<Value> {
+    let mismatch = || format!("expected {schema}, found {json}");
+    let uint = || json.as_u64().ok_or_else(mismatch);
+    let int = || json.as_i64().ok_or_else(mismatch);
+    let narrow = |_| format!("{json} is out of range for {schema}");
+    Ok(match schema {
+        Schema::Unit => if json.is_null() { Value::Unit } else { return Err(mismatch()) },
+        Schema::Bool => Value::Bool(json.as_bool().ok_or_else(mismatch)?),
+        Schema::U8 => Value::U8(uint()?.try_into().map_err(narrow)?),
+        Schema::U16 => Value::U16(uint()?.try_into().map_err(narrow)?),
+        Schema::U32 => Value::U32(uint()?.try_into().map_err(narrow)?),
+        Schema::U64 => Value::U64(uint()?),
+        Schema::I8 => Value::I8(int()?.try_into().map_err(narrow)?),
+        Schema::I16 => Value::I16(int()?.try_into().map_err(narrow)?),
+        Schema::I32 => Value::I32(int()?.try_into().map_err(narrow)?),
+        Schema::I64 => Value::I64(int()?),
+        Schema::U128 => Value::U128(match json {
+            Json::String(s) => s.parse().map_err(|_| mismatch())?,
+            _ => uint()?.into(),
+        }),
+        Schema::I128 => Value::I128(match json {
+            Json::String(s) => s.parse().map_err(|_| mismatch())?,
+            _ => int()?.into(),
+        }),
+        #[allow(clippy::cast_possible_truncation)]
+        Schema::F32 => Value::F32(json.as_f64().ok_or_else(mismatch)? as f32),
+        Schema::F64 => Value::F64(json.as_f64().ok_or_else(mismatch)?),
+        Schema::Char => {
+            let s = json.as_str().ok_or_else(mismatch)?;
+            let mut chars = s.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => Value::Char(c),
+                _ => return Err(mismatch()),
+            }
+        },
+        Schema::Str => Value::Str(json.as_str().ok_or_else(mismatch)?.to_string()),
+        Schema::Bytes => Value::Bytes(from_hex(json.as_str().ok_or_else(mismatch)?.as_bytes())?),
+        Schema::Option(schema) => Value::Option(match json {
+            Json::Null => None,
+            json => Some(Box::new(from_json(json, schema)?)),
+        }),
+        Schema::Desc(schema) => from_json(json, schema)?,
+        Schema::Seq(schema) => Value::Seq(json.as_array().ok_or_else(mismatch)?.iter()
+            .map(|v| from_json(v, schema)).collect::<CliResult<_>>()?),
+        Schema::Map(k, v) => Value::Map(match json {
+            Json::Object(entries) if **k == Schema::Str => entries.iter()
+                .map(|(key, value)| Ok((Value::Str(key.clone()), from_json(value, v)?)))
+                .collect::<CliResult<_>>()?,
+            Json::Array(entries) => entries.iter().map(|entry| match entry.as_array().map(Vec::as_slice) {
+                Some([key, value]) => Ok((from_json(key, k)?, from_json(value, v)?)),
+                _ => Err(format!("expected [key, value] pair, found {entry}")),
+            }).collect::<CliResult<_>>()?,
+            _ => return Err(mismatch()),
+        }),
+        Schema::Tuple(schemas) => match json.as_array() {
+            Some(items) if items.len() == schemas.len() => Value::Tuple(items.iter().zip(schemas)
+                .map(|(v, s)| from_json(v, s)).collect::<CliResult<_>>()?),
+            _ => return Err(mismatch()),
+        },
+        Schema::Struct(schemas) => {
+            let fields = json.as_object().ok_or_else(mismatch)?;
+            Value::Tuple(schemas.iter().map(|(name, s)| {
+                from_json(fields.get(name).unwrap_or(&Json::Null), s).map_err(|e| format!("field `{name}`: {e}"))
+            }).collect::<CliResult<_>>()?)
+        },
+        Schema::Enum(variants) => {
+            let (name, value) = match json {
+                Json::String(name) => (name.as_str(), &Json::Null),
+                Json::Object(map) if map.len() == 1 => map.iter().next().map(|(k, v)| (k.as_str(), v)).ok_or_else(mismatch)?,
+                _ => return Err(mismatch()),
+            };
+            let index = variants.iter().position(|(n, _)| n == name).ok_or_else(|| format!("unknown variant `{name}`"))?;
+            let index_u32 = u32::try_from(index).map_err(|_| mismatch())?;
+            Value::Enum(index_u32, Box::new(from_json(value, &variants[index].1)?))
+        },
+    })
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    use std::fmt::Write as _;
+    bytes.iter().fold(String::new(), |mut s, b| {
+        let _ = write!(s, "{b:02x}");
+        s
+    })
+}
+
+// `usize::is_multiple_of()` requires Rust 1.87
+#[allow(unknown_lints, clippy::manual_is_multiple_of)]
+fn from_hex(input: &[u8]) -> CliResult<Vec<u8>> {
+    let digits: Vec<u8> = input.iter().copied().filter(|c| !c.is_ascii_whitespace()).collect();
+    let digits = digits.strip_prefix(b"0x").unwrap_or(&digits);
+    if digits.len() % 2 != 0 {
+        return Err("odd number of hex digits".to_string());
+    }
+    digits.chunks(2).map(|pair| {
+        std::str::from_utf8(pair).ok().and_then(|s| u8::from_str_radix(s, 16).ok())
+            .ok_or_else(|| format!("invalid hex digits: {}", String::from_utf8_lossy(pair)))
+    }).collect()
+}
+
+const BASE64_ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+fn to_base64(bytes: &[u8]) -> String {
+    let mut out = String::new();
+    for chunk in bytes.chunks(3) {
+        let n = chunk.iter().enumerate().fold(0_u32, |n, (i, b)| n | u32::from(*b) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(char::from(BASE64_ALPHABET[(n >> (18 - 6 * i) & 0x3F) as usize]));
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+fn from_base64(input: &[u8]) -> CliResult<Vec<u8>> {
+    let mut out = Vec::new();
+    let (mut n, mut bits) = (0_u32, 0);
+    for c in input.iter().copied().filter(|c| !c.is_ascii_whitespace() && *c != b'=') {
+        let v = BASE64_ALPHABET.iter().position(|a| *a == c)
+            .ok_or_else(|| format!("invalid base64 character: {}", char::from(c)))?;
+        n = n << 6 | u32::try_from(v).map_err(|e| e.to_string())?;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            out.push(u8::try_from(n >> bits & 0xFF).map_err(|e| e.to_string())?);
+        }
+    }
+    Ok(out)
+}
@@ -21,6 +21,24 @@ pub trait ReadBytes {
     /// Returns view into remaining buffer
     fn remaining_buffer(&mut self) -> &'_[u8];
 
+    /// Number of bytes remaining in the input, if known
+    ///
+    /// Lengths read from input are checked against it by [`Deserializer`](crate::Deserializer)
+    /// with limits set. Readers from streams return `None`.
+    fn remaining_len(&mut self) -> Option<usize> { Some(self.remaining_buffer().len()) }
+
+    /// Make more input available in `remaining_buffer()`, returns `false` at the end of input
+    ///
+    /// Used by [`Deserializer`](crate::Deserializer) to find the end of escaped byte strings,
+    /// which may not fit in the buffer of a stream reader. Readers which hold all input in
+    /// `remaining_buffer()` may rely on default implementation.
+    fn fill_more(&mut self) -> Result<bool> { Ok(false) }
+
+    /// Whether `remaining_buffer()` holds bitwise inverted bytes of the input, like [`InvertingReader`] does
+    ///
+    /// Used by [`Deserializer`](crate::Deserializer) to scan escaped byte strings in `remaining_buffer()`.
+    fn is_buffer_inverted(&self) -> bool { false }
+
     /// Check if buffer is fully consumed (empty)
     fn is_complete(&mut self) -> Result {
         if self.remaining_buffer().is_empty() {
@@ -46,30 +64,65 @@ pub trait TailReadBytes: ReadBytes {
     }
 }
 
+/// Trait for readers which can lend bytes from the underlying buffer for its whole lifetime `'de`
+///
+/// Enables zero-copy deserialization of `&'de str` and `&'de [u8]` with [`Deserializer`](crate::Deserializer).
+/// Since version 0.3, `Deserializer` requires its reader to implement this trait, so custom
+/// readers which implemented only [`ReadBytes`] and [`TailReadBytes`] need an implementation too.
+/// Readers which cannot lend bytes (for example, reading from a stream) may rely on default
+/// implementation, so that deserializer falls back to copying data with [`ReadBytes::read`]:
+/// ```
+/// # use ordcode::{ Result, buf::{ ReadBytes, ReadBytesBorrow } };
+/// struct MyReader;
+/// # impl ReadBytes for MyReader {
+/// #    fn peek<F, R>(&mut self, n: usize, f: F) -> Result<R> where F: FnOnce(&[u8]) -> Result<R> { f(&[]) }
+/// #    fn advance(&mut self, n: usize) {}
+/// #    fn remaining_buffer(&mut self) -> &'_[u8] { &[] }
+/// # }
+/// impl<'de> ReadBytesBorrow<'de> for MyReader {}
+/// ```
+pub trait ReadBytesBorrow<'de>: ReadBytes {
+    /// Get `n` bytes from the beginning of buffer borrowed for `'de`, advance by `n` bytes.
+    ///
+    /// Returns `Ok(None)` and does not advance if reader is unable to lend bytes.
+    fn read_borrowed(&mut self, _n: usize) -> Result<Option<&'de [u8]>> { Ok(None) }
+}
+
 // forwarding for being able to use `&mut ReadBytes` in place of `ReadBytes`
-impl<'a, T> ReadBytes for &'a mut T where T: ReadBytes  {
+impl<T> ReadBytes for &mut T where T: ReadBytes  {
     fn peek<F, R>(&mut self, n: usize, f: F) -> Result<R> where F: FnOnce(&[u8]) -> Result<R> {
         (*self).peek(n, f)
     }
     fn advance(&mut self, n: usize) {
-        (*self).advance(n)
+        (*self).advance(n);
     }
     fn remaining_buffer(&mut self) -> &'_[u8] { (*self).remaining_buffer() }
+    fn remaining_len(&mut self) -> Option<usize> { (*self).remaining_len() }
+    fn fill_more(&mut self) -> Result<bool> { (*self).fill_more() }
+    fn is_buffer_inverted(&self) -> bool { (**self).is_buffer_inverted() }
 }
 
 // forwarding for being able to use `&mut ReadBytes` in place of `ReadBytes`
-impl<'a, T> TailReadBytes for &'a mut T where T: TailReadBytes  {
+impl<T> TailReadBytes for &mut T where T: TailReadBytes  {
     fn peek_tail<F, R>(&mut self, n: usize, f: F) -> Result<R> where F: FnOnce(&[u8]) -> Result<R> {
         (*self).peek_tail(n, f)
     }
     fn advance_tail(&mut self, n: usize) {
-        (*self).advance_tail(n)
+        (*self).advance_tail(n);
+    }
+}
+
+// forwarding for being able to use `&mut ReadBytesBorrow` in place of `ReadBytesBorrow`
+impl<'de, T> ReadBytesBorrow<'de> for &mut T where T: ReadBytesBorrow<'de>  {
+    fn read_borrowed(&mut self, n: usize) -> Result<Option<&'de [u8]>> {
+        (*self).read_borrowed(n)
     }
 }
 
 /// Adapter type which implements double-ended read buffer over byte slice
 ///
 /// Implements [`ReadBytes`], [`TailReadBytes`] traits and intended to be used as input to [`Deserializer`](crate::Deserializer).
+/// Also implements [`ReadBytesBorrow`], so borrowed `&str` and `&[u8]` can be deserialized without copying.
 pub struct DeBytesReader<'a> {
     buf: &'a [u8],
 }
@@ -79,7 +132,7 @@ impl<'a> DeBytesReader<'a> {
     #[must_use] pub fn new(buf: &'a [u8]) -> Self { Self { buf } }
 }
 
-impl <'a> ReadBytes for DeBytesReader<'a> {
+impl  ReadBytes for DeBytesReader<'_> {
     fn peek<F, R>(&mut self, n: usize, f: F) -> Result<R>
         where F: FnOnce(&[u8]) -> Result<R>,
     {
@@ -97,7 +150,7 @@ impl <'a> ReadBytes for DeBytesReader<'a> {
     fn remaining_buffer(&mut self) -> &'_[u8] { self.buf }
 }
 
-impl<'a> TailReadBytes for DeBytesReader<'a> {
+impl TailReadBytes for DeBytesReader<'_> {
     fn peek_tail<F, R>(&mut self, n: usize, f: F) -> Result<R>
         where F: FnOnce(&[u8]) -> Result<R>,
     {
@@ -113,6 +166,18 @@ impl<'a> TailReadBytes for DeBytesReader<'a> {
     }
 }
 
+impl<'de> ReadBytesBorrow<'de> for DeBytesReader<'de> {
+    fn read_borrowed(&mut self, n: usize) -> Result<Option<&'de [u8]>> {
+        if n <= self.buf.len() {
+            let (head, rest) = self.buf.split_at(n);
+            self.buf = rest;
+            Ok(Some(head))
+        } else {
+            Err(Error::PrematureEndOfInput)
+        }
+    }
+}
+
 /// Adapter which implements [`ReadBytes`] for reading from the end of the buffer.
 /// ```
 /// # use ordcode::{ DeBytesReader, ReadFromTail, params, primitives::SerializableValue };
@@ -122,7 +187,7 @@ impl<'a> TailReadBytes for DeBytesReader<'a> {
 /// ```
 pub struct ReadFromTail<'a, R>(pub &'a mut R) where R: TailReadBytes;
 
-impl <'a, R> ReadBytes for ReadFromTail<'a, R>
+impl <R> ReadBytes for ReadFromTail<'_, R>
     where R: TailReadBytes,
 {
     fn peek<F, RV>(&mut self, n: usize, f: F) -> Result<RV>
@@ -131,9 +196,173 @@ impl <'a, R> ReadBytes for ReadFromTail<'a, R>
         self.0.peek_tail(n, f)
     }
     fn advance(&mut self, n: usize) {
-        self.0.advance_tail(n)
+        self.0.advance_tail(n);
     }
     fn remaining_buffer(&mut self) -> &'_[u8] { self.0.remaining_buffer() }
+    fn remaining_len(&mut self) -> Option<usize> { self.0.remaining_len() }
+    fn is_buffer_inverted(&self) -> bool { self.0.is_buffer_inverted() }
+}
+
+// reads up to this size are inverted on stack
+const INV_INLINE_LEN: usize = 16;
+
+/// Buffer for temporary data of readers, like bytes inverted by [`InvertingReader`]
+///
+/// Implemented for `Vec<u8>`, which grows as needed, for `&mut [u8]` provided by caller,
+/// and for `()`, which does not hold anything.
+pub trait ScratchBuffer {
+    /// Get buffer for `n` bytes, or `None` if `n` bytes do not fit
+    fn get(&mut self, n: usize) -> Option<&mut [u8]>;
+}
+
+#[cfg(feature="std")]
+impl ScratchBuffer for Vec<u8> {
+    fn get(&mut self, n: usize) -> Option<&mut [u8]> {
+        if self.len() < n {
+            self.resize(n, 0);
+        }
+        Some(&mut self[..n])
+    }
+}
+
+impl ScratchBuffer for &mut [u8] {
+    fn get(&mut self, n: usize) -> Option<&mut [u8]> { (**self).get_mut(..n) }
+}
+
+impl ScratchBuffer for () {
+    fn get(&mut self, _n: usize) -> Option<&mut [u8]> { None }
+}
+
+#[cfg(feature="std")]
+pub(crate) type DefaultScratchBuffer = Vec<u8>;
+#[cfg(not(feature="std"))]
+pub(crate) type DefaultScratchBuffer = ();
+
+/// Adapter which bitwise inverts bytes read from underlying reader on the fly
+///
+/// Makes possible to deserialize data serialized in [`Order::Descending`](crate::Order::Descending)
+/// directly from immutable buffer, without copying or inverting it in place.
+/// Reads of up to 16 bytes (primitive values and varints) are inverted on stack, longer reads
+/// (strings and byte strings) are inverted into a buffer, see [`ScratchBuffer`]. With `std`
+/// feature, [`InvertingReader::new()`] uses internal growable buffer. Without it, pass a buffer
+/// big enough for the longest string to [`InvertingReader::with_buffer()`], otherwise longer
+/// reads fail with [`Error::BufferOverflow`].
+///
+/// Note that `remaining_buffer()` returns underlying bytes as is, not inverted, see
+/// [`ReadBytes::is_buffer_inverted()`].
+/// ```
+/// # use ordcode::{ DeBytesReader, buf::{ ReadBytes, InvertingReader }, params, primitives::SerializableValue };
+/// let buf = [!0_u8, !1];
+/// let mut reader = InvertingReader::new(DeBytesReader::new(&buf));
+/// assert_eq!(<u16>::from_reader(&mut reader, params::AscendingOrder).unwrap(), 1);
+///
+/// let buf = [!b'a'; 20];
+/// let mut tmp = [0_u8; 32];
+/// let mut reader = InvertingReader::with_buffer(DeBytesReader::new(&buf), &mut tmp[..]);
+/// assert!(reader.read(20, |b| Ok(b == [b'a'; 20])).unwrap());
+/// ```
+pub struct InvertingReader<R, B = DefaultScratchBuffer> {
+    reader: R,
+    buffer: B,
+    inverted: bool,
+}
+
+impl<R> InvertingReader<R> where R: ReadBytes {
+    /// Wrap `reader` which contains bitwise inverted data
+    #[must_use]
+    pub fn new(reader: R) -> Self {
+        Self { reader, buffer: DefaultScratchBuffer::default(), inverted: true }
+    }
+}
+
+impl<R, B> InvertingReader<R, B> where R: ReadBytes, B: ScratchBuffer {
+    /// Wrap `reader` which contains bitwise inverted data, reads longer than 16 bytes
+    /// are inverted into `buffer`
+    #[must_use]
+    pub fn with_buffer(reader: R, buffer: B) -> Self {
+        Self { reader, buffer, inverted: true }
+    }
+    /// Unwrap underlying reader
+    pub fn into_inner(self) -> R { self.reader }
+}
+
+// `Deserializer` reads all input through `InvertingReader`, and switches inversion on and off
+// for `Desc` values
+#[cfg(feature="serde")]
+impl<R, B> InvertingReader<R, B> {
+    pub(crate) fn with_mode(reader: R, buffer: B, inverted: bool) -> Self {
+        Self { reader, buffer, inverted }
+    }
+    pub(crate) fn is_inverted(&self) -> bool { self.inverted }
+    pub(crate) fn set_inverted(&mut self, inverted: bool) { self.inverted = inverted; }
+    pub(crate) fn get_ref(&self) -> &R { &self.reader }
+    // Underlying reader and buffer, for reading bytes as is
+    pub(crate) fn parts(&mut self) -> (&mut R, &mut B) { (&mut self.reader, &mut self.buffer) }
+    pub(crate) fn into_parts(self) -> (R, B) { (self.reader, self.buffer) }
+}
+
+#[inline]
+fn with_inverted<F, R>(buf: &[u8], buffer: &mut impl ScratchBuffer, f: F) -> Result<R>
+    where F: FnOnce(&[u8]) -> Result<R>,
+{
+    let mut tmp = [0_u8; INV_INLINE_LEN];
+    let out = if buf.len() <= INV_INLINE_LEN {
+        &mut tmp[..buf.len()]
+    } else {
+        buffer.get(buf.len()).ok_or(Error::BufferOverflow)?
+    };
+    for (t, b) in out.iter_mut().zip(buf) {
+        *t = !*b;
+    }
+    f(out)
+}
+
+impl<R, B> ReadBytes for InvertingReader<R, B> where R: ReadBytes, B: ScratchBuffer {
+    fn peek<F, RV>(&mut self, n: usize, f: F) -> Result<RV>
+        where F: FnOnce(&[u8]) -> Result<RV>,
+    {
+        let Self { reader, buffer, inverted } = self;
+        if *inverted {
+            reader.peek(n, |buf| with_inverted(buf, buffer, f))
+        } else {
+            reader.peek(n, f)
+        }
+    }
+    fn advance(&mut self, n: usize) {
+        self.reader.advance(n);
+    }
+    /// Returns underlying bytes as is, not inverted
+    fn remaining_buffer(&mut self) -> &'_[u8] { self.reader.remaining_buffer() }
+    fn remaining_len(&mut self) -> Option<usize> { self.reader.remaining_len() }
+    fn fill_more(&mut self) -> Result<bool> { self.reader.fill_more() }
+    fn is_buffer_inverted(&self) -> bool { self.inverted != self.reader.is_buffer_inverted() }
+}
+
+impl<R, B> TailReadBytes for InvertingReader<R, B> where R: TailReadBytes, B: ScratchBuffer {
+    fn peek_tail<F, RV>(&mut self, n: usize, f: F) -> Result<RV>
+        where F: FnOnce(&[u8]) -> Result<RV>,
+    {
+        let Self { reader, buffer, inverted } = self;
+        if *inverted {
+            reader.peek_tail(n, |buf| with_inverted(buf, buffer, f))
+        } else {
+            reader.peek_tail(n, f)
+        }
+    }
+    fn advance_tail(&mut self, n: usize) {
+        self.reader.advance_tail(n);
+    }
+}
+
+// inverted bytes can't be lent, so deserializer falls back to copying
+impl<'de, R, B> ReadBytesBorrow<'de> for InvertingReader<R, B> where R: ReadBytesBorrow<'de>, B: ScratchBuffer {
+    fn read_borrowed(&mut self, n: usize) -> Result<Option<&'de [u8]>> {
+        if self.inverted {
+            Ok(None)
+        } else {
+            self.reader.read_borrowed(n)
+        }
+    }
 }
 
 #[cfg(feature="std")]
@@ -153,6 +382,85 @@ pub trait WriteBytes {
 pub trait TailWriteBytes: WriteBytes {
     /// Write to the tail of byte buffer
     fn write_tail(&mut self, value: &[u8]) -> Result;
+
+    // `Serializer` reports positions of nested values to `LayoutCalc` writer only
+    #[doc(hidden)]
+    #[cfg(all(feature="std", feature="serde"))]
+    #[inline]
+    fn layout_calc(&mut self) -> Option<&mut crate::LayoutCalc> { None }
+}
+
+// writes are inverted on stack in chunks of this size
+const INV_WRITE_CHUNK_LEN: usize = 64;
+
+/// Adapter which bitwise inverts bytes written to underlying writer on the fly
+///
+/// Used by [`Serializer`](crate::Serializer) to encode [`Desc`](crate::Desc) values;
+/// does not allocate.
+/// ```
+/// # use ordcode::{ buf::InvertingWriter, params, primitives::SerializableValue };
+/// let mut buf = Vec::new();
+/// 1_u16.to_writer(InvertingWriter::new(&mut buf), params::AscendingOrder).unwrap();
+/// assert_eq!(buf, vec![!0_u8, !1]);
+/// ```
+pub struct InvertingWriter<W> {
+    writer: W,
+    inverted: bool,
+}
+
+impl<W> InvertingWriter<W> where W: WriteBytes {
+    /// Wrap `writer`, data written to it will be bitwise inverted
+    #[must_use]
+    pub fn new(writer: W) -> Self { Self { writer, inverted: true } }
+    /// Unwrap underlying writer
+    pub fn into_inner(self) -> W { self.writer }
+}
+
+// `Serializer` writes all output through `InvertingWriter`, and switches inversion on and off
+// for `Desc` values
+#[cfg(feature="serde")]
+impl<W> InvertingWriter<W> {
+    pub(crate) fn with_mode(writer: W, inverted: bool) -> Self { Self { writer, inverted } }
+    pub(crate) fn is_inverted(&self) -> bool { self.inverted }
+    pub(crate) fn set_inverted(&mut self, inverted: bool) { self.inverted = inverted; }
+    pub(crate) fn get_ref(&self) -> &W { &self.writer }
+    #[cfg(feature="std")]
+    pub(crate) fn get_mut(&mut self) -> &mut W { &mut self.writer }
+}
+
+#[inline]
+fn inverted_chunk<'a>(chunk: &[u8], tmp: &'a mut [u8; INV_WRITE_CHUNK_LEN]) -> &'a [u8] {
+    for (t, b) in tmp.iter_mut().zip(chunk) {
+        *t = !*b;
+    }
+    &tmp[..chunk.len()]
+}
+
+impl<W> WriteBytes for InvertingWriter<W> where W: WriteBytes {
+    fn write(&mut self, value: &[u8]) -> Result {
+        if !self.inverted {
+            return self.writer.write(value);
+        }
+        let mut tmp = [0_u8; INV_WRITE_CHUNK_LEN];
+        for chunk in value.chunks(INV_WRITE_CHUNK_LEN) {
+            self.writer.write(inverted_chunk(chunk, &mut tmp))?;
+        }
+        Ok(())
+    }
+}
+
+impl<W> TailWriteBytes for InvertingWriter<W> where W: TailWriteBytes {
+    fn write_tail(&mut self, value: &[u8]) -> Result {
+        if !self.inverted {
+            return self.writer.write_tail(value);
+        }
+        // tail grows backwards, so write chunks starting from the end to keep their order
+        let mut tmp = [0_u8; INV_WRITE_CHUNK_LEN];
+        for chunk in value.rchunks(INV_WRITE_CHUNK_LEN) {
+            self.writer.write_tail(inverted_chunk(chunk, &mut tmp))?;
+        }
+        Ok(())
+    }
 }
 
 /// Adapter type which implements double-ended write byte buffer over mutable byte slice
@@ -193,7 +501,7 @@ impl<'a> DeBytesWriter<'a> {
     }
 }
 
-impl<'a> WriteBytes for DeBytesWriter<'a> {
+impl WriteBytes for DeBytesWriter<'_> {
     fn write(&mut self, value: &[u8]) -> Result {
         if (self.head + value.len()) > self.tail {
             Err(Error::BufferOverflow)
@@ -205,7 +513,7 @@ impl<'a> WriteBytes for DeBytesWriter<'a> {
     }
 }
 
-impl<'a> TailWriteBytes for DeBytesWriter<'a> {
+impl TailWriteBytes for DeBytesWriter<'_> {
     fn write_tail(&mut self, value: &[u8]) -> Result {
         if (self.head + value.len()) > self.tail {
             Err(Error::BufferOverflow)
@@ -228,7 +536,7 @@ impl<'a> TailWriteBytes for DeBytesWriter<'a> {
 /// ```
 pub struct WriteToTail<'a, W>(pub &'a mut W) where W: TailWriteBytes;
 
-impl<'a, W> WriteBytes for WriteToTail<'a, W>
+impl<W> WriteBytes for WriteToTail<'_, W>
     where W: TailWriteBytes
 {
     fn write(&mut self, value: &[u8]) -> Result {
@@ -243,6 +551,9 @@ impl<T> WriteBytes for &mut T where T: WriteBytes {
 
 impl<T> TailWriteBytes for &mut T where T: TailWriteBytes {
     fn write_tail(&mut self, buf: &[u8]) -> Result { (*self).write_tail(buf) }
+    #[cfg(all(feature="std", feature="serde"))]
+    #[inline]
+    fn layout_calc(&mut self) -> Option<&mut crate::LayoutCalc> { (*self).layout_calc() }
 }
 
 /// Pushes data to the vector
@@ -258,6 +569,7 @@ impl WriteBytes for Vec<u8> {
 ///
 /// This means that `Serializer` can write to `Vec<u8>` buffer directly and grow it as needed,
 /// however in this case lexicographic ordering property will not be preserved.
+/// Use [`DeVecWriter`] for growable buffer which preserves ordering.
 #[cfg(feature="std")]
 impl TailWriteBytes for Vec<u8> {
     fn write_tail(&mut self, buf: &[u8]) -> Result {
@@ -266,12 +578,87 @@ impl TailWriteBytes for Vec<u8> {
     }
 }
 
+/// Growable double-ended write buffer
+///
+/// Unlike [`DeBytesWriter`], does not need a pre-allocated buffer of exact size, so value can be
+/// serialized in a single pass, without calculating its size first. Unlike `Vec<u8>`, keeps data
+/// written to the tail at the end of the buffer, so the result is laid out exactly as
+/// [`DeBytesWriter`] would do it.
+/// ```
+/// # use ordcode::{ buf::DeVecWriter, new_ser_asc, ser_to_vec_ordered, Order };
+/// # use serde::ser::Serialize;
+/// let value = ("abc", 1_u16, vec!["d", "ef"]);
+/// let mut writer = DeVecWriter::new();
+/// value.serialize(&mut new_ser_asc(&mut writer)).unwrap();
+/// assert_eq!(writer.into_vec(), ser_to_vec_ordered(&value, Order::Ascending).unwrap());
+/// ```
+#[cfg(feature="std")]
+#[derive(Default)]
+pub struct DeVecWriter {
+    head: Vec<u8>,
+    // tail data in reverse byte order
+    tail: Vec<u8>,
+}
+
+#[cfg(feature="std")]
+impl DeVecWriter {
+    /// Create empty buffer
+    #[must_use]
+    pub fn new() -> Self { Self::default() }
+
+    /// Create empty buffer with pre-allocated capacity for `head` and `tail` data
+    #[must_use]
+    pub fn with_capacity(head: usize, tail: usize) -> Self {
+        Self { head: Vec::with_capacity(head), tail: Vec::with_capacity(tail) }
+    }
+    /// Length of data written so far
+    #[must_use]
+    pub fn len(&self) -> usize { self.head.len() + self.tail.len() }
+
+    /// Returns `true` if nothing was written yet
+    #[must_use]
+    pub fn is_empty(&self) -> bool { self.len() == 0 }
+
+    /// Get serialized data: head followed by tail
+    #[must_use]
+    pub fn into_vec(self) -> Vec<u8> {
+        let Self { mut head, tail } = self;
+        head.reserve_exact(tail.len());
+        head.extend(tail.iter().rev());
+        head
+    }
+    /// Write buffer contents to another double-ended writer: head data to its head,
+    /// tail data to its tail, preserving the layout
+    pub fn write_to(self, mut writer: impl TailWriteBytes) -> Result {
+        let Self { head, mut tail } = self;
+        tail.reverse();
+        writer.write(&head)?;
+        writer.write_tail(&tail)
+    }
+}
+
+#[cfg(feature="std")]
+impl WriteBytes for DeVecWriter {
+    fn write(&mut self, value: &[u8]) -> Result {
+        self.head.extend_from_slice(value);
+        Ok(())
+    }
+}
+
+#[cfg(feature="std")]
+impl TailWriteBytes for DeVecWriter {
+    fn write_tail(&mut self, value: &[u8]) -> Result {
+        self.tail.extend(value.iter().rev());
+        Ok(())
+    }
+}
+
 /// Adapter for always writing to buffer head, even for `write_tail()`
 ///
 /// Useful e.g. for appending serialized suffix to the buffer
 pub struct WriteToHead<'a, W>(pub &'a mut W) where W: WriteBytes;
 
-impl<'a, W> WriteBytes for WriteToHead<'a, W>
+impl<W> WriteBytes for WriteToHead<'_, W>
     where W: TailWriteBytes
 {
     fn write(&mut self, value: &[u8]) -> Result {
@@ -279,7 +666,7 @@ impl<'a, W> WriteBytes for WriteToHead<'a, W>
     }
 }
 
-impl<'a, W> TailWriteBytes for WriteToHead<'a, W>
+impl<W> TailWriteBytes for WriteToHead<'_, W>
     where W: TailWriteBytes
 {
     fn write_tail(&mut self, value: &[u8]) -> Result {
@@ -287,6 +674,348 @@ impl<'a, W> TailWriteBytes for WriteToHead<'a, W>
     }
 }
 
+/// Adapter for serializing into `std::io::Write` stream
+///
+/// Streams have no tail, so it can be used only with parameters which don't write to the tail
+/// of the buffer: [`params::PortableBinary`](crate::params::PortableBinary),
+/// [`params::NativeBinary`](crate::params::NativeBinary) and
+/// [`params::EscapedAscending`](crate::params::EscapedAscending). With other parameters,
+/// serialization fails with [`Error::TailNotSupported`].
+///
+/// Every primitive value is written with a separate `write_all()` call, so unbuffered streams
+/// like `File` or `TcpStream` should be wrapped into `std::io::BufWriter`.
+/// ```
+/// # use ordcode::{ Serializer, Deserializer, params, buf::{ IoWriter, IoReader, ReadBytes } };
+/// # use serde::{ Serialize, Deserialize };
+/// let mut stream = Vec::new();
+/// let mut ser = Serializer::new(IoWriter::new(&mut stream), params::PortableBinary);
+/// for record in &[(1_u32, "abc"), (2, "de")] {
+///     record.serialize(&mut ser).unwrap();
+/// }
+///
+/// let mut reader = IoReader::new(stream.as_slice());
+/// let mut records = Vec::new();
+/// while reader.is_complete().is_err() {
+///     let mut de = Deserializer::new(&mut reader, params::PortableBinary);
+///     records.push(<(u32, String)>::deserialize(&mut de).unwrap());
+/// }
+/// assert_eq!(records, [(1, "abc".to_string()), (2, "de".to_string())]);
+/// ```
+#[cfg(feature="std")]
+pub struct IoWriter<W> {
+    writer: W,
+}
+
+#[cfg(feature="std")]
+impl<W> IoWriter<W> where W: std::io::Write {
+    /// Wrap `writer`
+    #[must_use]
+    pub fn new(writer: W) -> Self { Self { writer } }
+
+    /// Get reference to underlying writer
+    pub fn get_mut(&mut self) -> &mut W { &mut self.writer }
+
+    /// Unwrap underlying writer
+    pub fn into_inner(self) -> W { self.writer }
+}
+
+#[cfg(feature="std")]
+impl<W> WriteBytes for IoWriter<W> where W: std::io::Write {
+    fn write(&mut self, value: &[u8]) -> Result {
+        self.writer.write_all(value).map_err(Error::from)
+    }
+}
+
+#[cfg(feature="std")]
+impl<W> TailWriteBytes for IoWriter<W> where W: std::io::Write {
+    fn write_tail(&mut self, value: &[u8]) -> Result {
+        if value.is_empty() { Ok(()) } else { Err(Error::TailNotSupported) }
+    }
+}
+
+/// Adapter for deserializing from `std::io::BufRead` stream
+///
+/// Reads from the stream as needed, without reading the whole serialized value into memory.
+/// Can be used only with parameters which don't use the tail of the buffer, see [`IoWriter`]
+/// for example. Deserialization with other parameters fails with [`Error::TailNotSupported`].
+///
+/// Values which span boundary of stream buffer are copied into internal buffer, other values
+/// are read from the stream buffer directly. Byte strings with
+/// [`params::EscapedAscending`](crate::params::EscapedAscending) have no length, so when
+/// their end is not in the stream buffer, the stream is read into internal buffer until it is.
+///
+/// Length of input is unknown, so lengths are not checked against it by
+/// [`Limits`](crate::Limits), only against limits themselves. `is_complete()` checks
+/// for the end of the stream, and returns errors of the stream which occurred while checking it.
+#[cfg(feature="std")]
+pub struct IoReader<R> {
+    reader: R,
+    // bytes taken from the stream for a read which spans its buffer boundary, or for scanning
+    // of escaped byte string; bytes before `pos` are consumed
+    pending: Vec<u8>,
+    pos: usize,
+    // error of the stream in `remaining_buffer()`, returned by the next read
+    error: Option<Error>,
+}
+
+#[cfg(feature="std")]
+impl<R> IoReader<R> where R: std::io::BufRead {
+    /// Wrap `reader`
+    #[must_use]
+    pub fn new(reader: R) -> Self { Self { reader, pending: Vec::new(), pos: 0, error: None } }
+
+    /// Get reference to underlying reader
+    pub fn get_mut(&mut self) -> &mut R { &mut self.reader }
+
+    /// Unwrap underlying reader
+    ///
+    /// Bytes which were taken from the stream, but not consumed by deserializer, are lost.
+    /// There are none after deserialization of a value has succeeded.
+    pub fn into_inner(self) -> R { self.reader }
+}
+
+// `BufRead::fill_buf()`, retrying on interrupts
+#[cfg(feature="std")]
+fn fill_buf(reader: &mut impl std::io::BufRead) -> Result<&[u8]> {
+    loop {
+        match reader.fill_buf() {
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => (),
+            Err(e) => return Err(e.into()),
+            Ok(_) => break,
+        }
+    }
+    reader.fill_buf().map_err(Error::from)
+}
+
+#[cfg(feature="std")]
+impl<R> ReadBytes for IoReader<R> where R: std::io::BufRead {
+    fn peek<F, RV>(&mut self, n: usize, f: F) -> Result<RV>
+        where F: FnOnce(&[u8]) -> Result<RV>,
+    {
+        if let Some(e) = self.error.take() {
+            return Err(e);
+        }
+        if self.pending.is_empty() {
+            let buf = fill_buf(&mut self.reader)?;
+            if buf.len() >= n {
+                return f(&buf[..n]);
+            }
+        }
+        while self.pending.len() - self.pos < n {
+            let buf = fill_buf(&mut self.reader)?;
+            if buf.is_empty() {
+                return Err(Error::PrematureEndOfInput);
+            }
+            let len = buf.len().min(n - (self.pending.len() - self.pos));
+            self.pending.extend_from_slice(&buf[..len]);
+            self.reader.consume(len);
+        }
+        f(&self.pending[self.pos..self.pos + n])
+    }
+    fn advance(&mut self, n: usize) {
+        if self.pending.is_empty() {
+            self.reader.consume(n);
+        } else {
+            self.pos += n;
+            if self.pos >= self.pending.len() {
+                self.pending.clear();
+                self.pos = 0;
+            }
+        }
+    }
+    // I/O error is reported by the next read or `is_complete()`
+    fn remaining_buffer(&mut self) -> &'_[u8] {
+        if !self.pending.is_empty() {
+            return &self.pending[self.pos..];
+        }
+        match fill_buf(&mut self.reader) {
+            Ok(buf) => buf,
+            Err(e) => {
+                self.error = Some(e);
+                &[]
+            },
+        }
+    }
+    fn remaining_len(&mut self) -> Option<usize> { None }
+    // take the whole stream buffer, so that the next one is read
+    fn fill_more(&mut self) -> Result<bool> {
+        if let Some(e) = self.error.take() {
+            return Err(e);
+        }
+        let mut more = false;
+        while !more {
+            let buf = fill_buf(&mut self.reader)?;
+            if buf.is_empty() {
+                break;
+            }
+            // after bytes of the current stream buffer are moved, the next one is appended
+            more = !self.pending.is_empty();
+            let len = buf.len();
+            self.pending.extend_from_slice(buf);
+            self.reader.consume(len);
+        }
+        Ok(more)
+    }
+    fn is_complete(&mut self) -> Result {
+        if !self.remaining_buffer().is_empty() {
+            return Err(Error::BufferUnderflow);
+        }
+        self.error.take().map_or(Ok(()), Err)
+    }
+}
+
+#[cfg(feature="std")]
+impl<R> TailReadBytes for IoReader<R> where R: std::io::BufRead {
+    fn peek_tail<F, RV>(&mut self, n: usize, f: F) -> Result<RV>
+        where F: FnOnce(&[u8]) -> Result<RV>,
+    {
+        if n == 0 { f(&[]) } else { Err(Error::TailNotSupported) }
+    }
+    fn advance_tail(&mut self, _n: usize) {}
+}
+
+// stream buffer is reused, so bytes can't be lent
+#[cfg(feature="std")]
+impl<R> ReadBytesBorrow<'_> for IoReader<R> where R: std::io::BufRead {}
+
+// Adapter which counts bytes read or written at the head and at the tail of the buffer,
+// used by `Serializer` and `Deserializer` for error context
+#[cfg(feature="serde")]
+pub(crate) struct Counting<T> {
+    pub(crate) inner: T,
+    pub(crate) head: usize,
+    pub(crate) tail: usize,
+}
+
+#[cfg(feature="serde")]
+impl<T> Counting<T> {
+    pub(crate) fn new(inner: T) -> Self {
+        Self { inner, head: 0, tail: 0 }
+    }
+}
+
+#[cfg(feature="serde")]
+impl<T> ReadBytes for Counting<T> where T: ReadBytes {
+    #[inline]
+    fn peek<F, R>(&mut self, n: usize, f: F) -> Result<R> where F: FnOnce(&[u8]) -> Result<R> {
+        self.inner.peek(n, f)
+    }
+    #[inline]
+    fn advance(&mut self, n: usize) {
+        self.inner.advance(n);
+        self.head += n;
+    }
+    #[inline]
+    fn remaining_buffer(&mut self) -> &'_[u8] { self.inner.remaining_buffer() }
+    #[inline]
+    fn remaining_len(&mut self) -> Option<usize> { self.inner.remaining_len() }
+    #[inline]
+    fn fill_more(&mut self) -> Result<bool> { self.inner.fill_more() }
+    #[inline]
+    fn is_buffer_inverted(&self) -> bool { self.inner.is_buffer_inverted() }
+}
+
+#[cfg(feature="serde")]
+impl<T> TailReadBytes for Counting<T> where T: TailReadBytes {
+    #[inline]
+    fn peek_tail<F, R>(&mut self, n: usize, f: F) -> Result<R> where F: FnOnce(&[u8]) -> Result<R> {
+        self.inner.peek_tail(n, f)
+    }
+    #[inline]
+    fn advance_tail(&mut self, n: usize) {
+        self.inner.advance_tail(n);
+        self.tail += n;
+    }
+}
+
+#[cfg(feature="serde")]
+impl<'de, T> ReadBytesBorrow<'de> for Counting<T> where T: ReadBytesBorrow<'de> {
+    #[inline]
+    fn read_borrowed(&mut self, n: usize) -> Result<Option<&'de [u8]>> {
+        let buf = self.inner.read_borrowed(n)?;
+        if buf.is_some() {
+            self.head += n;
+        }
+        Ok(buf)
+    }
+}
+
+#[cfg(feature="serde")]
+impl<T> WriteBytes for Counting<T> where T: WriteBytes {
+    #[inline]
+    fn write(&mut self, value: &[u8]) -> Result {
+        self.inner.write(value)?;
+        self.head += value.len();
+        Ok(())
+    }
+}
+
+#[cfg(feature="serde")]
+impl<T> TailWriteBytes for Counting<T> where T: TailWriteBytes {
+    #[inline]
+    fn write_tail(&mut self, value: &[u8]) -> Result {
+        self.inner.write_tail(value)?;
+        self.tail += value.len();
+        Ok(())
+    }
+}
+
+// Adapter which holds back data written to the tail, used by `Serializer` for sequences
+// of unknown length when their lengths are written to the tail: sequence length is known
+// in the end only, but it must precede lengths of nested sequences in the tail
+#[cfg(feature="serde")]
+pub(crate) struct DeferringTail<W> {
+    pub(crate) inner: W,
+    // deferred tail data in reverse byte order, like in `DeVecWriter`
+    #[cfg(feature="std")]
+    deferred: Option<Vec<u8>>,
+}
+
+#[cfg(feature="serde")]
+impl<W> DeferringTail<W> where W: TailWriteBytes {
+    pub(crate) fn new(inner: W) -> Self {
+        Self { inner, #[cfg(feature="std")] deferred: None }
+    }
+    // Start holding back tail data, returns data held back so far
+    #[cfg(feature="std")]
+    pub(crate) fn defer(&mut self) -> Option<Vec<u8>> {
+        self.deferred.replace(Vec::new())
+    }
+    // Restore state returned by `defer()`, returns data held back since then
+    #[cfg(feature="std")]
+    pub(crate) fn restore(&mut self, deferred: Option<Vec<u8>>) -> Vec<u8> {
+        core::mem::replace(&mut self.deferred, deferred).unwrap_or_default()
+    }
+    // Write data returned by `restore()`
+    #[cfg(feature="std")]
+    pub(crate) fn write_deferred(&mut self, mut data: Vec<u8>) -> Result {
+        if data.is_empty() {
+            return Ok(());
+        }
+        data.reverse();
+        self.write_tail(&data)
+    }
+}
+
+#[cfg(feature="serde")]
+impl<W> WriteBytes for DeferringTail<W> where W: WriteBytes {
+    #[inline]
+    fn write(&mut self, value: &[u8]) -> Result { self.inner.write(value) }
+}
+
+#[cfg(feature="serde")]
+impl<W> TailWriteBytes for DeferringTail<W> where W: TailWriteBytes {
+    #[inline]
+    fn write_tail(&mut self, value: &[u8]) -> Result {
+        #[cfg(feature="std")]
+        if let Some(deferred) = &mut self.deferred {
+            deferred.extend(value.iter().rev());
+            return Ok(());
+        }
+        self.inner.write_tail(value)
+    }
+}
+
 #[test]
 fn test_debuffer() {
     let mut byte_buf = [0_u8; 7];
@@ -299,9 +1028,9 @@ fn test_debuffer() {
     bib.is_complete().unwrap();
     assert_eq!(byte_buf.as_ref(), b"aabbd21");
     let mut rb = DeBytesReader::new(byte_buf.as_mut());
-    assert_eq!(rb.read(3, |b| Ok(b == b"aab")).unwrap(), true);
-    assert_eq!(rb.read_tail(1, |b| Ok(b == b"1")).unwrap(), true);
-    assert_eq!(rb.read_tail(1, |b| Ok(b == b"2")).unwrap(), true);
-    assert_eq!(rb.read(2, |b| Ok(b == b"bd")).unwrap(), true);
+    assert!(rb.read(3, |b| Ok(b == b"aab")).unwrap());
+    assert!(rb.read_tail(1, |b| Ok(b == b"1")).unwrap());
+    assert!(rb.read_tail(1, |b| Ok(b == b"2")).unwrap());
+    assert!(rb.read(2, |b| Ok(b == b"bd")).unwrap());
     rb.is_complete().unwrap();
 }
\ No newline at end of file
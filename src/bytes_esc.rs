@@ -5,13 +5,20 @@
 //! `{ 0xF8, 0x01 }` for ascending order, `{ 0x07, 0xFE }` for descending order. Escaped byte
 //!   value `0xF8` is chosen because it does not appear in valid UTF-8, and escaping zero
 //!   is impractical (it is too common)
+//!
+//! Note that this encoding is prefix-free, but does not preserve ordering of sequences where one is
+//! a prefix of another: terminator `{ 0xF8, 0x01 }` sorts after most of byte values.
+//! If this is required (e.g. for composite keys), use `*_ordered` functions, which escape
+//! byte value `0x00` as `{ 0x00, 0xFF }` and terminate sequence with `{ 0x00, 0x01 }`
+//! (bitwise complemented for descending order), so shorter sequence always sorts first.
 use crate::{Error, buf::{ReadBytes, WriteBytes}, Result, Order, params::EncodingParams};
 
 fn apply_over_esc<R, F>(rb: &mut R, esc: u8, advance: bool, f: &mut F) -> Result
     where F: FnMut(&[u8], u8) -> Result<bool>,
     R: ReadBytes,
 {
-    let mut b = &rb.remaining_buffer()[..];
+    let mut b = rb.remaining_buffer();
+    let total = b.len();
     let r = loop {
         if let Some(pos) = b.iter().position(|v| *v == esc) {
             if pos + 1 >= b.len() {
@@ -26,9 +33,9 @@ fn apply_over_esc<R, F>(rb: &mut R, esc: u8, advance: bool, f: &mut F) -> Result
             break Err(Error::PrematureEndOfInput)
         }
     };
-    let len = b.len();
+    let consumed = total - b.len();
     if advance {
-        rb.advance(len);
+        rb.advance(consumed);
     }
     r
 }
@@ -41,6 +48,12 @@ const BSTR_ESCAPE_DESC: ByteStrEscapes = ByteStrEscapes {
     start: !BSTR_ESCAPE_ASC.start, esc: !BSTR_ESCAPE_ASC.esc, term: !BSTR_ESCAPE_ASC.term
 };
 
+// Escape and terminator sequences for order-preserving prefix-free byte array encoding.
+const BSTR_ORD_ESCAPE_ASC: ByteStrEscapes  = ByteStrEscapes { start: 0x00, esc: 0xFF, term: 0x01 };
+const BSTR_ORD_ESCAPE_DESC: ByteStrEscapes = ByteStrEscapes {
+    start: !BSTR_ORD_ESCAPE_ASC.start, esc: !BSTR_ORD_ESCAPE_ASC.esc, term: !BSTR_ORD_ESCAPE_ASC.term
+};
+
 // Calculates unescaped length of escaped sequence, does not advance reader
 #[inline]
 fn unescaped_length(rb: &mut impl ReadBytes, esc: &ByteStrEscapes) -> Result<usize> {
@@ -65,52 +78,43 @@ pub fn bytes_length<P: EncodingParams>(mut reader: impl ReadBytes, _param: P) ->
               unescaped_length(&mut reader, &BSTR_ESCAPE_ASC))
 }
 
-/// Serialize byte sequence to escaped representation
-pub fn serialize_bytes<P: EncodingParams>(mut writer: impl WriteBytes, value: &[u8], _param: P) -> Result {
-    ord_cond!(P, {
-        for b in value {
-            if BSTR_ESCAPE_ASC.start == *b {
-                writer.write(&[BSTR_ESCAPE_DESC.start, BSTR_ESCAPE_DESC.esc])?;
-            } else {
-                writer.write(&[!*b])?;
-            }
-        }
-        writer.write(&[BSTR_ESCAPE_DESC.start, BSTR_ESCAPE_DESC.term])
-    }, {
-        for b in value {
-            if BSTR_ESCAPE_ASC.start == *b {
-                writer.write(&[BSTR_ESCAPE_ASC.start, BSTR_ESCAPE_ASC.esc])?;
-            } else {
-                writer.write(&[*b])?;
-            }
-        }
-        writer.write(&[BSTR_ESCAPE_ASC.start, BSTR_ESCAPE_ASC.term])
-    })
-}
-
-fn read_escaped_bytes_asc(mut rb: impl ReadBytes, mut out: impl WriteBytes) -> Result
+// Write escaped representation of `value`; `asc` are escapes for ascending order, the
+// value of byte `asc.start` is escaped.
+fn write_escaped(mut writer: impl WriteBytes, value: &[u8], asc: &ByteStrEscapes, esc: &ByteStrEscapes,
+                 complement: bool) -> Result
 {
-    apply_over_esc(&mut rb,BSTR_ESCAPE_ASC.start, true, &mut |buf, c| {
-        if c == BSTR_ESCAPE_ASC.esc {
-            out.write(&buf[..buf.len()])?;
-            Ok(true)
-        } else if c == BSTR_ESCAPE_ASC.term {
-            out.write(&buf[..buf.len() - 1])?;
-            Ok(false)
+    for b in value {
+        if asc.start == *b {
+            writer.write(&[esc.start, esc.esc])?;
+        } else if complement {
+            writer.write(&[!*b])?;
         } else {
-            Err(Error::InvalidByteSequenceEscape)
+            writer.write(&[*b])?;
         }
-    })
+    }
+    writer.write(&[esc.start, esc.term])
+}
+
+/// Serialize byte sequence to escaped representation
+pub fn serialize_bytes<P: EncodingParams>(writer: impl WriteBytes, value: &[u8], _param: P) -> Result {
+    ord_cond!(P, write_escaped(writer, value, &BSTR_ESCAPE_ASC, &BSTR_ESCAPE_DESC, true),
+              write_escaped(writer, value, &BSTR_ESCAPE_ASC, &BSTR_ESCAPE_ASC, false))
 }
 
-fn read_escaped_bytes_desc(mut rb: impl ReadBytes, mut out: impl WriteBytes) -> Result
+fn read_escaped_bytes(mut rb: impl ReadBytes, mut out: impl WriteBytes, esc: &ByteStrEscapes,
+                      complement: bool) -> Result
 {
-    apply_over_esc(&mut rb,BSTR_ESCAPE_DESC.start, true, &mut |buf, c| {
-        if c == BSTR_ESCAPE_DESC.esc {
-            write_complement_bytes(&mut out,&buf[..buf.len()])?;
+    let mut write = |buf: &[u8]| if complement {
+        write_complement_bytes(&mut out, buf)
+    } else {
+        out.write(buf)
+    };
+    apply_over_esc(&mut rb, esc.start, true, &mut |buf, c| {
+        if c == esc.esc {
+            write(buf)?;
             Ok(true)
-        } else if c == BSTR_ESCAPE_DESC.term {
-            write_complement_bytes(&mut out,&buf[..buf.len() - 1])?;
+        } else if c == esc.term {
+            write(&buf[..buf.len() - 1])?;
             Ok(false)
         } else {
             Err(Error::InvalidByteSequenceEscape)
@@ -122,17 +126,87 @@ fn read_escaped_bytes_desc(mut rb: impl ReadBytes, mut out: impl WriteBytes) ->
 #[inline]
 pub fn deserialize_bytes_to_writer<P: EncodingParams>(reader: impl ReadBytes, out: impl WriteBytes, _param: P) -> Result
 {
-    ord_cond!(P, read_escaped_bytes_desc(reader, out),
-              read_escaped_bytes_asc(reader, out))
+    ord_cond!(P, read_escaped_bytes(reader, out, &BSTR_ESCAPE_DESC, true),
+              read_escaped_bytes(reader, out, &BSTR_ESCAPE_ASC, false))
 }
 
 /// Deserialize escaped byte sequence
 #[cfg(feature="std")]
 pub fn deserialize_bytes_to_vec<P: EncodingParams>(mut reader: impl ReadBytes, param: P) -> Result<Vec<u8>>
 {
-    let len = bytes_length(&mut reader, &param)?;
+    let len = bytes_length(&mut reader, param)?;
+    let mut v = Vec::with_capacity(len);
+    deserialize_bytes_to_writer(&mut reader, &mut v, param)?;
+    Ok(v)
+}
+
+/// Calculate length of order-preserving escaped representation of `value`, including terminator
+#[must_use]
+#[inline]
+#[allow(clippy::naive_bytecount)]
+pub fn escaped_length_ordered(value: &[u8]) -> usize {
+    value.len() + value.iter().filter(|b| **b == BSTR_ORD_ESCAPE_ASC.start).count() + 2
+}
+
+/// Serialize byte sequence to order-preserving escaped representation
+pub fn serialize_bytes_ordered<P: EncodingParams>(writer: impl WriteBytes, value: &[u8], _param: P) -> Result {
+    ord_cond!(P, write_escaped(writer, value, &BSTR_ORD_ESCAPE_ASC, &BSTR_ORD_ESCAPE_DESC, true),
+              write_escaped(writer, value, &BSTR_ORD_ESCAPE_ASC, &BSTR_ORD_ESCAPE_ASC, false))
+}
+
+/// Calculate length of pending order-preserving escaped byte sequence from reader
+#[inline]
+pub fn bytes_length_ordered<P: EncodingParams>(mut reader: impl ReadBytes, _param: P) -> Result<usize> {
+    ord_cond!(P, unescaped_length(&mut reader, &BSTR_ORD_ESCAPE_DESC),
+              unescaped_length(&mut reader, &BSTR_ORD_ESCAPE_ASC))
+}
+
+/// Scan order-preserving escaped byte sequence at the beginning of `buf`
+///
+/// Returns length of escaped sequence without terminator, and number of escaped bytes in it.
+/// If there are no escaped bytes, escaped sequence is identical to unescaped one
+/// (bitwise complemented for descending order).
+pub fn scan_escaped_ordered<P: EncodingParams>(buf: &[u8], _param: P) -> Result<(usize, usize)> {
+    ord_cond!(P, scan_escaped(buf, &BSTR_ORD_ESCAPE_DESC), scan_escaped(buf, &BSTR_ORD_ESCAPE_ASC))
+}
+
+fn scan_escaped(buf: &[u8], esc: &ByteStrEscapes) -> Result<(usize, usize)> {
+    let mut pos = 0;
+    let mut n_escaped = 0;
+    loop {
+        match buf[pos..].iter().position(|v| *v == esc.start) {
+            Some(p) if pos + p + 1 < buf.len() => {
+                pos += p;
+                let c = buf[pos + 1];
+                if c == esc.term {
+                    break Ok((pos, n_escaped))
+                } else if c == esc.esc {
+                    n_escaped += 1;
+                    pos += 2;
+                } else {
+                    break Err(Error::InvalidByteSequenceEscape)
+                }
+            },
+            _ => break Err(Error::PrematureEndOfInput)
+        }
+    }
+}
+
+/// Deserialize order-preserving escaped byte sequence and write result to `WriteBytes`
+#[inline]
+pub fn deserialize_bytes_ordered_to_writer<P: EncodingParams>(reader: impl ReadBytes, out: impl WriteBytes, _param: P) -> Result
+{
+    ord_cond!(P, read_escaped_bytes(reader, out, &BSTR_ORD_ESCAPE_DESC, true),
+              read_escaped_bytes(reader, out, &BSTR_ORD_ESCAPE_ASC, false))
+}
+
+/// Deserialize order-preserving escaped byte sequence
+#[cfg(feature="std")]
+pub fn deserialize_bytes_ordered_to_vec<P: EncodingParams>(mut reader: impl ReadBytes, param: P) -> Result<Vec<u8>>
+{
+    let len = bytes_length_ordered(&mut reader, param)?;
     let mut v = Vec::with_capacity(len);
-    deserialize_bytes_to_writer(&mut reader, &mut v, &param)?;
+    deserialize_bytes_ordered_to_writer(&mut reader, &mut v, param)?;
     Ok(v)
 }
 
@@ -0,0 +1,132 @@
+//! Descending lexicographic ordering for a part of serialized value
+//!
+//! [`Serializer`](crate::Serializer) encodes values wrapped in [`Desc`] with bitwise inverted bytes,
+//! [`Deserializer`](crate::Deserializer) inverts them back, so it is possible to mix ascending and
+//! descending ordering within one key:
+//!
+//! ```
+//! # use ordcode::{ Desc, ser_to_vec_ordered, de_from_bytes_asc, Order };
+//! // (device_id ascending, timestamp descending)
+//! let k1 = ser_to_vec_ordered(&(1_u32, Desc(200_u64)), Order::Ascending).unwrap();
+//! let k2 = ser_to_vec_ordered(&(1_u32, Desc(100_u64)), Order::Ascending).unwrap();
+//! let k3 = ser_to_vec_ordered(&(2_u32, Desc(300_u64)), Order::Ascending).unwrap();
+//! assert!(k1 < k2 && k2 < k3);
+//!
+//! let (id, ts): (u32, Desc<u64>) = de_from_bytes_asc(&k2).unwrap();
+//! assert_eq!((id, ts.0), (1, 100));
+//! ```
+//!
+//! `serde` implementations for [`core::cmp::Reverse`] are transparent, so serializer can't tell
+//! `Reverse<T>` from `T`. For `Reverse<T>` fields (or for any field which should be encoded
+//! in descending order without changing its type), use `#[serde(with = "ordcode::desc")]`:
+//!
+//! ```
+//! # use std::cmp::Reverse;
+//! # use ordcode::{ ser_to_vec_ordered, de_from_bytes_asc, Order };
+//! #[derive(serde_derive::Serialize, serde_derive::Deserialize, PartialEq, Debug)]
+//! struct Key {
+//!     device_id: u32,
+//!     #[serde(with = "ordcode::desc")]
+//!     timestamp: Reverse<u64>,
+//! }
+//! let k1 = ser_to_vec_ordered(&Key { device_id: 1, timestamp: Reverse(200) }, Order::Ascending).unwrap();
+//! let k2 = ser_to_vec_ordered(&Key { device_id: 1, timestamp: Reverse(100) }, Order::Ascending).unwrap();
+//! assert!(k1 < k2);
+//! let key: Key = de_from_bytes_asc(&k2).unwrap();
+//! assert_eq!(key, Key { device_id: 1, timestamp: Reverse(100) });
+//! ```
+//!
+//! Size of encoded value does not depend on the ordering, so [`calc_size()`](crate::calc_size)
+//! returns the same result for `T` and `Desc<T>`. As with [`Order::Descending`](crate::Order::Descending),
+//! variable-length values in `Desc` preserve ordering only with
+//! [`params::EscapedAscending`](crate::params::EscapedAscending).
+//!
+//! For other data formats, `Desc<T>` is serialized as a newtype struct.
+
+use core::cmp::{ Ordering, Reverse };
+use serde::{ ser, de };
+
+// Type names which are recognized by `Serializer`, `Deserializer` and `SizeCalc`. Names of
+// types are `&'static str` in `serde` API, so these can't collide with names of Rust types.
+
+// Newtype struct name of `Desc<T>`, its value is encoded with inverted bytes (except for
+// self-describing format, where ordering is irrelevant)
+pub(crate) const DESC_NEWTYPE_NAME: &str = "$ordcode::Desc";
+
+// Tuple struct name of enum variants of `value::Value`, for self-describing format only: variant
+// names are known at runtime only, so fields of this tuple struct are variant name and variant
+// value, which are encoded same as `serialize_newtype_variant()` would encode them
+pub(crate) const RUNTIME_VARIANT_NAME: &str = "$ordcode::Variant";
+
+/// Wrapper for values which are serialized in descending lexicographic order
+///
+/// Implements `Ord` in reverse, same as [`core::cmp::Reverse`]. See [module documentation](self).
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Desc<T>(pub T);
+
+impl<T: PartialOrd> PartialOrd for Desc<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        other.0.partial_cmp(&self.0)
+    }
+}
+
+impl<T: Ord> Ord for Desc<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.0.cmp(&self.0)
+    }
+}
+
+impl<T> From<T> for Desc<T> {
+    fn from(value: T) -> Self { Desc(value) }
+}
+
+impl<T> From<Reverse<T>> for Desc<T> {
+    fn from(value: Reverse<T>) -> Self { Desc(value.0) }
+}
+
+impl<T> From<Desc<T>> for Reverse<T> {
+    fn from(value: Desc<T>) -> Self { Reverse(value.0) }
+}
+
+impl<T> ser::Serialize for Desc<T> where T: ser::Serialize {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize(&self.0, serializer)
+    }
+}
+
+impl<'de, T> de::Deserialize<'de> for Desc<T> where T: de::Deserialize<'de> {
+    fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct DescVisitor<T>(core::marker::PhantomData<T>);
+
+        impl<'de, T> de::Visitor<'de> for DescVisitor<T> where T: de::Deserialize<'de> {
+            type Value = Desc<T>;
+
+            fn expecting(&self, formatter: &mut core::fmt::Formatter) -> core::fmt::Result {
+                formatter.write_str("value in descending order")
+            }
+            fn visit_newtype_struct<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+                where D: de::Deserializer<'de>,
+            {
+                T::deserialize(deserializer).map(Desc)
+            }
+        }
+        deserializer.deserialize_newtype_struct(DESC_NEWTYPE_NAME, DescVisitor(core::marker::PhantomData))
+    }
+}
+
+/// Serialize `value` in descending order, for use with `#[serde(serialize_with = "ordcode::desc::serialize")]`
+pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where T: ?Sized + ser::Serialize,
+          S: ser::Serializer,
+{
+    serializer.serialize_newtype_struct(DESC_NEWTYPE_NAME, value)
+}
+
+/// Deserialize value serialized in descending order, for use with
+/// `#[serde(deserialize_with = "ordcode::desc::deserialize")]`
+pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where T: de::Deserialize<'de>,
+          D: de::Deserializer<'de>,
+{
+    <Desc<T> as de::Deserialize>::deserialize(deserializer).map(|v| v.0)
+}
@@ -0,0 +1,219 @@
+//! Typed wrappers for serialized bytes
+//!
+//! [`Encoded<T, P>`](Encoded) holds bytes of a value of type `T` serialized with parameters `P`,
+//! so that the type and encoding of the bytes are not lost when they are passed around.
+//! `Eq`, `Ord` and `Hash` are implemented by bytes, so `Encoded` can be used as a key of
+//! `BTreeMap` and sorts exactly like keys in the database. Value is decoded on demand with
+//! [`Encoded::decode()`]. Small values (up to [`INLINE_CAPACITY`] bytes) are stored inline,
+//! without heap allocation.
+//!
+//! [`EncodedRef<'a, T, P>`](EncodedRef) is the same for borrowed bytes, for example for values
+//! read from a database. It can decode values which borrow from the bytes.
+//!
+//! ```
+//! # use std::collections::BTreeMap;
+//! # use ordcode::{ Encoded, EncodedRef, params };
+//! type Key = Encoded<(u32, String), params::EscapedAscending>;
+//!
+//! let mut map = BTreeMap::new();
+//! map.insert(Key::new(&(2, "a".to_string())).unwrap(), 1);
+//! map.insert(Key::new(&(1, "b".to_string())).unwrap(), 2);
+//! let (key, _) = map.iter().next().unwrap();
+//! assert_eq!(key.decode().unwrap(), (1, "b".to_string()));
+//!
+//! // lookup by raw bytes
+//! assert_eq!(map.get(key.as_bytes()), Some(&2));
+//!
+//! // borrowing decode
+//! let key: EncodedRef<(u32, &str), params::EscapedAscending> = EncodedRef::new(key.as_bytes());
+//! assert_eq!(key.decode().unwrap(), (1, "b"));
+//! ```
+//!
+//! Byte order is the same as order of values for fixed-size types and for
+//! [`params::EscapedAscending`](crate::params::EscapedAscending). With
+//! [`params::AscendingOrder`](crate::params::AscendingOrder), byte order of tuples and structs
+//! with variable-length fields followed by other fields may differ from order of their values.
+//! For descending order, wrap the value in [`Desc`](crate::Desc).
+
+use core::borrow::Borrow;
+use core::cmp::Ordering;
+use core::convert::TryFrom;
+use core::fmt;
+use core::hash::{ Hash, Hasher };
+use core::marker::PhantomData;
+use crate::{ params, Result, DeBytesReader, DeBytesWriter, Deserializer, Serializer };
+use crate::buf::ReadBytes;
+
+/// Maximum size of encoded value which is stored inline by [`Encoded`]
+pub const INLINE_CAPACITY: usize = 22;
+
+#[derive(Clone)]
+enum Storage {
+    Inline { len: u8, buf: [u8; INLINE_CAPACITY] },
+    Heap(Vec<u8>),
+}
+
+/// Serialized value of type `T`, see [module documentation](self)
+pub struct Encoded<T, P = params::AscendingOrder> {
+    storage: Storage,
+    _marker: PhantomData<fn() -> (T, P)>,
+}
+
+/// Borrowed serialized value of type `T`, see [module documentation](self)
+pub struct EncodedRef<'a, T, P = params::AscendingOrder> {
+    bytes: &'a [u8],
+    _marker: PhantomData<fn() -> (T, P)>,
+}
+
+impl<T, P> Encoded<T, P> {
+    /// Wrap bytes of serialized value, bytes are not checked
+    #[must_use]
+    pub fn from_bytes(bytes: Vec<u8>) -> Self {
+        Self { storage: Storage::Heap(bytes), _marker: PhantomData }
+    }
+    /// Copy bytes of serialized value, bytes are not checked
+    #[must_use]
+    pub fn from_slice(bytes: &[u8]) -> Self {
+        let storage = match u8::try_from(bytes.len()) {
+            Ok(len) if bytes.len() <= INLINE_CAPACITY => {
+                let mut buf = [0_u8; INLINE_CAPACITY];
+                buf[..bytes.len()].copy_from_slice(bytes);
+                Storage::Inline { len, buf }
+            },
+            _ => Storage::Heap(bytes.to_vec()),
+        };
+        Self { storage, _marker: PhantomData }
+    }
+    /// Bytes of serialized value
+    #[must_use]
+    pub fn as_bytes(&self) -> &[u8] {
+        match &self.storage {
+            Storage::Inline { len, buf } => &buf[..usize::from(*len)],
+            Storage::Heap(v) => v,
+        }
+    }
+    /// Convert into bytes of serialized value
+    #[must_use]
+    pub fn into_bytes(self) -> Vec<u8> {
+        match self.storage {
+            Storage::Inline { .. } => self.as_bytes().to_vec(),
+            Storage::Heap(v) => v,
+        }
+    }
+    /// Borrow as [`EncodedRef`]
+    #[must_use]
+    pub fn as_encoded_ref(&self) -> EncodedRef<'_, T, P> {
+        EncodedRef::new(self.as_bytes())
+    }
+}
+
+impl<T, P> Encoded<T, P>
+    where T: serde::ser::Serialize,
+          P: params::SerializerParams + Default,
+{
+    /// Serialize `value`
+    ///
+    /// Serialization may fail (for example, for `serde` implementations which return errors),
+    /// so there is no `From<&T>` implementation. There is no `TryFrom<&T>` either: other crates
+    /// may implement `From<&T>` for `Encoded<T, P>` with their own `T`, which conflicts with
+    /// the blanket `TryFrom` implementation for types implementing `Into`.
+    pub fn new(value: &T) -> Result<Self> {
+        let len = crate::calc_size(value, P::default())?;
+        let mut storage = match u8::try_from(len) {
+            Ok(len) if usize::from(len) <= INLINE_CAPACITY => Storage::Inline { len, buf: [0; INLINE_CAPACITY] },
+            _ => Storage::Heap(vec![0; len]),
+        };
+        let buf = match &mut storage {
+            Storage::Inline { buf, .. } => &mut buf[..len],
+            Storage::Heap(v) => v.as_mut_slice(),
+        };
+        let mut writer = DeBytesWriter::new(buf);
+        value.serialize(&mut Serializer::new(&mut writer, P::default()))?;
+        writer.is_complete()?;
+        Ok(Self { storage, _marker: PhantomData })
+    }
+}
+
+impl<T, P> Encoded<T, P>
+    where T: serde::de::DeserializeOwned,
+          P: params::SerializerParams + Default,
+{
+    /// Deserialize value, fails if bytes are not fully consumed
+    pub fn decode(&self) -> Result<T> {
+        self.as_encoded_ref().decode()
+    }
+}
+
+impl<'a, T, P> EncodedRef<'a, T, P> {
+    /// Wrap bytes of serialized value, bytes are not checked
+    #[must_use]
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, _marker: PhantomData }
+    }
+    /// Bytes of serialized value
+    #[must_use]
+    pub fn as_bytes(&self) -> &'a [u8] { self.bytes }
+
+    /// Copy into owned [`Encoded`]
+    #[must_use]
+    pub fn to_encoded(&self) -> Encoded<T, P> {
+        Encoded::from_slice(self.bytes)
+    }
+}
+
+impl<'a, T, P> EncodedRef<'a, T, P>
+    where T: serde::de::Deserialize<'a>,
+          P: params::SerializerParams + Default,
+{
+    /// Deserialize value, which may borrow from the bytes; fails if bytes are not fully consumed
+    pub fn decode(&self) -> Result<T> {
+        let mut reader = DeBytesReader::new(self.bytes);
+        let value = T::deserialize(&mut Deserializer::new(&mut reader, P::default()))?;
+        reader.is_complete()?;
+        Ok(value)
+    }
+}
+
+impl<T, P> Clone for Encoded<T, P> {
+    fn clone(&self) -> Self {
+        Self { storage: self.storage.clone(), _marker: PhantomData }
+    }
+}
+
+impl<T, P> Clone for EncodedRef<'_, T, P> {
+    fn clone(&self) -> Self { *self }
+}
+
+impl<T, P> Copy for EncodedRef<'_, T, P> {}
+
+macro_rules! impl_bytes_traits {
+    ($($name:ident $ty:ty),*) => { $(
+        impl<T, P> fmt::Debug for $ty {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.debug_tuple(stringify!($name)).field(&self.as_bytes()).finish()
+            }
+        }
+        impl<T, P> PartialEq for $ty {
+            fn eq(&self, other: &Self) -> bool { self.as_bytes() == other.as_bytes() }
+        }
+        impl<T, P> Eq for $ty {}
+
+        impl<T, P> PartialOrd for $ty {
+            fn partial_cmp(&self, other: &Self) -> Option<Ordering> { Some(self.cmp(other)) }
+        }
+        impl<T, P> Ord for $ty {
+            fn cmp(&self, other: &Self) -> Ordering { self.as_bytes().cmp(other.as_bytes()) }
+        }
+        impl<T, P> Hash for $ty {
+            fn hash<H: Hasher>(&self, state: &mut H) { self.as_bytes().hash(state) }
+        }
+        impl<T, P> AsRef<[u8]> for $ty {
+            fn as_ref(&self) -> &[u8] { self.as_bytes() }
+        }
+        impl<T, P> Borrow<[u8]> for $ty {
+            fn borrow(&self) -> &[u8] { self.as_bytes() }
+        }
+    )* };
+}
+
+impl_bytes_traits!(Encoded Encoded<T, P>, EncodedRef EncodedRef<'_, T, P>);
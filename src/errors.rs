@@ -1,13 +1,17 @@
 
 /// Serialization and deserialization errors
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Clone)]
 #[non_exhaustive]
 pub enum Error {
-    #[doc(hidden)]
-    Serde,  // not used, but need to satisfy serde Error traits
+    /// Error reported by `Serialize` or `Deserialize` implementation, e.g. invalid value
+    /// or unknown enum variant
+    Custom(ErrorMessage),
     SerializeSequenceMustHaveLength,
     BufferOverflow,
     BufferUnderflow,
+    /// Input is not fully consumed after value is read, reported by [`de_from_bytes_strict()`](crate::de_from_bytes_strict)
+    /// and [`validate()`](crate::validate)
+    TrailingBytes,
     PrematureEndOfInput,
     InvalidByteSequenceEscape,
     DeserializeAnyNotSupported,
@@ -16,6 +20,21 @@ pub enum Error {
     InvalidUtf8Encoding,
     InvalidTagEncoding,
     InvalidVarintEncoding,
+    InvalidBoolEncoding,
+    LengthLimitExceeded,
+    DepthLimitExceeded,
+    AllocationLimitExceeded,
+    /// Writer or reader does not support the tail of the buffer, see [`buf::IoWriter`](crate::buf::IoWriter)
+    TailNotSupported,
+    /// Input ends in the middle of a frame, see [`frame`](crate::frame) module
+    TruncatedFrame,
+    /// Frame checksum does not match its contents, see [`frame`](crate::frame) module
+    FrameChecksumMismatch,
+    /// Frame extends beyond the end of input, but valid frames follow its start, so its length
+    /// prefix is corrupted; detected with checksums only, see [`frame`](crate::frame) module
+    CorruptFrame,
+    /// Error of underlying `std::io` writer or reader
+    #[cfg(feature="std")] Io(std::sync::Arc<std::io::Error>),
     #[cfg(not(feature="std"))] CannotSerializeDisplayInNoStdContext,
 }
 
@@ -23,10 +42,11 @@ impl Error {
     #[cfg(feature = "std")]
     fn descr(&self) -> &str {
         match self {
-            Error::Serde => "serde custom error", // not used
+            Error::Custom(msg) => msg.as_str(),
             Error::SerializeSequenceMustHaveLength => "serialized sequence must have length",
             Error::BufferOverflow => "serialized data buffer overflow",
             Error::BufferUnderflow => "serialized data buffer underflow",
+            Error::TrailingBytes => "trailing bytes after serialized value",
             Error::PrematureEndOfInput => "premature end of input",
             Error::InvalidByteSequenceEscape => "invalid byte sequence escaping",
             Error::DeserializeAnyNotSupported => "deserialize to any type not supported",
@@ -35,33 +55,206 @@ impl Error {
             Error::InvalidUtf8Encoding => "invalid UTF-8 encoding",
             Error::InvalidTagEncoding => "invalid encoding for enum tag",
             Error::InvalidVarintEncoding => "invalid varint encoding",
+            Error::InvalidBoolEncoding => "invalid bool encoding",
+            Error::LengthLimitExceeded => "sequence length exceeds limit or remaining input",
+            Error::DepthLimitExceeded => "nesting depth limit exceeded",
+            Error::AllocationLimitExceeded => "allocation limit exceeded",
+            Error::TailNotSupported => "buffer tail not supported, use parameters without tail, like PortableBinary",
+            Error::TruncatedFrame => "truncated or torn frame at the end of input",
+            Error::FrameChecksumMismatch => "frame checksum mismatch",
+            Error::CorruptFrame => "corrupted frame length in the middle of input",
+            Error::Io(_) => "I/O error",
             #[cfg(not(feature = "std"))] Error::CannotSerializeDisplayInNoStdContext => "", // kill ide warning
         }
     }
     #[cfg(not(feature = "std"))]
-    fn descr(&self) -> &str { "" }
+    fn descr(&self) -> &str {
+        match self {
+            Error::Custom(msg) => msg.as_str(),
+            _ => "",
+        }
+    }
 }
 
 impl core::fmt::Display for Error {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        #[cfg(feature="std")]
+        if let Error::Io(e) = self {
+            return write!(f, "{}: {e}", self.descr());
+        }
         f.write_str(self.descr())?;
         Ok(())
     }
 }
 
 #[cfg(feature="std")]
-impl std::error::Error for Error {}
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(e) => Some(&**e),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature="std")]
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self { Error::Io(std::sync::Arc::new(e)) }
+}
+
+/// Location in serialized data at which serialization or deserialization failed
+///
+/// Collected by [`Serializer`](crate::Serializer) and [`Deserializer`](crate::Deserializer) while
+/// error propagates, see `error_context()` methods. Offsets are counted in bytes from the beginning
+/// (head) and from the end (tail) of serialized data. Path to the failed value (for example,
+/// `Foo.items[3].name`) is available with `alloc` feature only.
+///
+/// Offsets are captured by the innermost sequence, tuple or struct containing the failed value,
+/// so errors in top-level primitive values have no context.
+#[derive(Debug, Clone, Default)]
+pub struct ErrorContext {
+    head_offset: usize,
+    tail_offset: usize,
+    // innermost segment first
+    #[cfg(feature="alloc")]
+    path: alloc::vec::Vec<PathSegment>,
+}
+
+// Element of path to the failed value
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(not(all(feature="alloc", feature="serde")), allow(dead_code))]
+pub(crate) enum PathSegment {
+    // struct type name, shown for the outermost struct only
+    Type(&'static str),
+    Field(&'static str),
+    Index(usize),
+}
+
+#[cfg(feature="serde")]
+impl ErrorContext {
+    pub(crate) fn new(head_offset: usize, tail_offset: usize) -> Self {
+        Self { head_offset, tail_offset, #[cfg(feature="alloc")] path: alloc::vec::Vec::new() }
+    }
+    #[cfg(feature="std")]
+    pub(crate) fn set_offsets(&mut self, head_offset: usize, tail_offset: usize) {
+        self.head_offset = head_offset;
+        self.tail_offset = tail_offset;
+    }
+    #[cfg_attr(not(feature="alloc"), allow(clippy::unused_self))]
+    pub(crate) fn push(&mut self, segment: PathSegment) {
+        #[cfg(feature="alloc")]
+        self.path.push(segment);
+        #[cfg(not(feature="alloc"))]
+        let _ = segment;
+    }
+}
+
+impl ErrorContext {
+    /// Offset from the beginning of serialized data
+    #[must_use]
+    pub fn head_offset(&self) -> usize { self.head_offset }
+
+    /// Offset from the end of serialized data (lengths of sequences are stored in the tail)
+    #[must_use]
+    pub fn tail_offset(&self) -> usize { self.tail_offset }
+
+    /// Path to the failed value, like `Foo.items[3].name`
+    #[cfg(feature="alloc")]
+    #[must_use]
+    pub fn path(&self) -> alloc::string::String {
+        format_path(self.path.iter().rev())
+    }
+}
+
+// Format path from segments, outermost first
+#[cfg(feature="alloc")]
+pub(crate) fn format_path<'a>(segments: impl Iterator<Item = &'a PathSegment>) -> alloc::string::String {
+    use core::fmt::Write;
+    let mut path = alloc::string::String::new();
+    for (i, segment) in segments.enumerate() {
+        match segment {
+            PathSegment::Type(name) if i == 0 => path.push_str(name),
+            PathSegment::Type(_) => (),
+            PathSegment::Field(name) => {
+                if !path.is_empty() {
+                    path.push('.');
+                }
+                path.push_str(name);
+            },
+            PathSegment::Index(idx) => { let _ = write!(path, "[{idx}]"); },
+        }
+    }
+    path
+}
+
+impl core::fmt::Display for ErrorContext {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        #[cfg(feature="alloc")]
+        write!(f, "at {}, ", self.path())?;
+        write!(f, "head offset {}, tail offset {}", self.head_offset, self.tail_offset)
+    }
+}
+
+// serde requires error types to implement its `StdError` trait without `std`
+#[cfg(all(feature="serde", not(feature="std")))]
+impl serde::de::StdError for Error {}
+
+/// Message of [`Error::Custom`]
+///
+/// With `alloc` feature, the message is kept on the heap, so that [`Error`] stays small.
+/// Without `alloc`, text of the message is not kept, and a static description is reported instead.
+#[derive(Clone, PartialEq, Eq)]
+pub struct ErrorMessage {
+    #[cfg(feature="alloc")]
+    msg: alloc::boxed::Box<str>,
+    #[cfg(not(feature="alloc"))]
+    msg: &'static str,
+}
+
+impl ErrorMessage {
+    /// Create message from anything implementing `Display`
+    #[cfg(feature="alloc")]
+    pub fn new<T: core::fmt::Display + ?Sized>(msg: &T) -> Self {
+        use alloc::string::ToString;
+        Self { msg: msg.to_string().into_boxed_str() }
+    }
+    /// Create message from anything implementing `Display`, the text is discarded without `alloc`
+    #[cfg(not(feature="alloc"))]
+    pub fn new<T: core::fmt::Display + ?Sized>(_msg: &T) -> Self {
+        Self { msg: "error reported by Serialize or Deserialize implementation" }
+    }
+    /// Message text
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        #[cfg(feature="alloc")]
+        return &self.msg;
+        #[cfg(not(feature="alloc"))]
+        return self.msg;
+    }
+}
+
+impl core::fmt::Debug for ErrorMessage {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        core::fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl core::fmt::Display for ErrorMessage {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
 
 #[cfg(feature="serde")]
 const _: () =  {
     impl serde::ser::Error for Error {
-        fn custom<T: core::fmt::Display>(_msg: T) -> Self {
-            Self::Serde
+        fn custom<T: core::fmt::Display>(msg: T) -> Self {
+            Self::Custom(ErrorMessage::new(&msg))
         }
     }
     impl serde::de::Error for Error {
-        fn custom<T: core::fmt::Display>(_msg: T) -> Self {
-            Self::Serde
+        fn custom<T: core::fmt::Display>(msg: T) -> Self {
+            Self::Custom(ErrorMessage::new(&msg))
         }
     }
 };
\ No newline at end of file
@@ -0,0 +1,160 @@
+//! Compile-time encoded size of fixed-size types
+//!
+//! [`FixedSize`] provides encoded size of a type as associated constants, so it can be used for
+//! declaring buffers, for example on no-heap targets. Sizes follow the same rules as
+//! [`calc_size()`](crate::calc_size) for [`params::AscendingOrder`](crate::params::AscendingOrder)
+//! and [`params::EscapedAscending`](crate::params::EscapedAscending) pre-sets.
+//!
+//! The trait is implemented for primitive types, arrays, tuples, `Option` and [`Desc`](crate::Desc).
+//! For structs and enums, it can be derived with `#[derive(FixedSize)]` from `ordcode-derive` crate
+//! (re-exported by `ordcode` with `derive` feature).
+//!
+//! With `derive` feature, `use ordcode::FixedSize;` imports both the trait and the derive macro,
+//! so don't import `ordcode_derive::FixedSize` too: the names clash. Without the feature, import
+//! the trait as `ordcode::fixed_size::FixedSize` and the macro from `ordcode_derive`:
+//!
+//! ```
+//! # use ordcode::ser_to_buf_asc_exact;
+//! use ordcode::fixed_size::FixedSize;
+//! use ordcode_derive::FixedSize;
+//!
+//! #[derive(FixedSize, serde_derive::Serialize)]
+//! struct Key { device: u16, ts: u64, flags: [u8; 2] }
+//!
+//! #[derive(FixedSize)]
+//! enum Event { Start(Key), Stop(Key, Option<u32>), Reset }
+//!
+//! let mut buf = [0_u8; Key::ENCODED_SIZE];
+//! ser_to_buf_asc_exact(&mut buf, &Key { device: 1, ts: 2, flags: [3, 4] }).unwrap();
+//! assert_eq!(Key::ENCODED_SIZE, 12);
+//!
+//! // discriminant (1 byte) and the largest variant
+//! assert_eq!(Event::MAX_ENCODED_SIZE, 1 + 12 + 5);
+//! assert_eq!(Event::MIN_ENCODED_SIZE, 1);
+//! ```
+//!
+//! For types with variable encoded size, like `Option<T>` or enums with variants of different
+//! sizes, `ENCODED_SIZE` is not defined: its use fails to compile. Bounds of their encoded size
+//! are available as `MIN_ENCODED_SIZE` and `MAX_ENCODED_SIZE`.
+//!
+//! ```compile_fail
+//! # use ordcode::FixedSize;
+//! let buf = [0_u8; Option::<u32>::ENCODED_SIZE];
+//! ```
+//!
+//! Note that sizes are derived from types only: `serde` attributes which change serialized
+//! representation, like `#[serde(skip)]` or `#[serde(with)]`, are not taken into account.
+
+use core::marker::PhantomData;
+use core::cmp::Reverse;
+
+/// Encoded size of a type, which does not depend on value or is bounded
+///
+/// See [module documentation](self) for details.
+pub trait FixedSize {
+    /// Minimum encoded size of a value
+    const MIN_ENCODED_SIZE: usize;
+
+    /// Maximum encoded size of a value
+    const MAX_ENCODED_SIZE: usize;
+
+    /// Exact encoded size of a value, fails to compile if values have different encoded sizes
+    const ENCODED_SIZE: usize = {
+        assert!(Self::MIN_ENCODED_SIZE == Self::MAX_ENCODED_SIZE, "type has variable encoded size");
+        Self::MAX_ENCODED_SIZE
+    };
+}
+
+macro_rules! impl_primitive {
+    ($($t:ty),*) => { $(
+        impl FixedSize for $t {
+            const MIN_ENCODED_SIZE: usize = core::mem::size_of::<$t>();
+            const MAX_ENCODED_SIZE: usize = core::mem::size_of::<$t>();
+        }
+    )* };
+}
+
+impl_primitive!(bool, u8, u16, u32, u64, i8, i16, i32, i64, f32, f64, char);
+
+#[cfg(not(no_i128))]
+impl_primitive!(u128, i128);
+
+impl FixedSize for () {
+    const MIN_ENCODED_SIZE: usize = 0;
+    const MAX_ENCODED_SIZE: usize = 0;
+}
+
+impl<T: ?Sized> FixedSize for PhantomData<T> {
+    const MIN_ENCODED_SIZE: usize = 0;
+    const MAX_ENCODED_SIZE: usize = 0;
+}
+
+impl<T: FixedSize, const N: usize> FixedSize for [T; N] {
+    const MIN_ENCODED_SIZE: usize = T::MIN_ENCODED_SIZE * N;
+    const MAX_ENCODED_SIZE: usize = T::MAX_ENCODED_SIZE * N;
+}
+
+impl<T: FixedSize> FixedSize for Option<T> {
+    // option marker byte, followed by value for `Some`
+    const MIN_ENCODED_SIZE: usize = 1;
+    const MAX_ENCODED_SIZE: usize = 1 + T::MAX_ENCODED_SIZE;
+}
+
+impl<T: FixedSize> FixedSize for Reverse<T> {
+    const MIN_ENCODED_SIZE: usize = T::MIN_ENCODED_SIZE;
+    const MAX_ENCODED_SIZE: usize = T::MAX_ENCODED_SIZE;
+}
+
+#[cfg(feature="serde")]
+impl<T: FixedSize> FixedSize for crate::Desc<T> {
+    const MIN_ENCODED_SIZE: usize = T::MIN_ENCODED_SIZE;
+    const MAX_ENCODED_SIZE: usize = T::MAX_ENCODED_SIZE;
+}
+
+macro_rules! impl_tuple {
+    ($($t:ident),*) => {
+        impl<$($t: FixedSize),*> FixedSize for ($($t,)*) {
+            const MIN_ENCODED_SIZE: usize = 0 $(+ $t::MIN_ENCODED_SIZE)*;
+            const MAX_ENCODED_SIZE: usize = 0 $(+ $t::MAX_ENCODED_SIZE)*;
+        }
+    };
+}
+
+impl_tuple!(T0);
+impl_tuple!(T0, T1);
+impl_tuple!(T0, T1, T2);
+impl_tuple!(T0, T1, T2, T3);
+impl_tuple!(T0, T1, T2, T3, T4);
+impl_tuple!(T0, T1, T2, T3, T4, T5);
+impl_tuple!(T0, T1, T2, T3, T4, T5, T6);
+impl_tuple!(T0, T1, T2, T3, T4, T5, T6, T7);
+impl_tuple!(T0, T1, T2, T3, T4, T5, T6, T7, T8);
+impl_tuple!(T0, T1, T2, T3, T4, T5, T6, T7, T8, T9);
+impl_tuple!(T0, T1, T2, T3, T4, T5, T6, T7, T8, T9, T10);
+impl_tuple!(T0, T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11);
+
+// Helpers for `#[derive(FixedSize)]`, not a public API
+
+#[doc(hidden)]
+#[must_use]
+pub const fn min(a: usize, b: usize) -> usize {
+    if a < b { a } else { b }
+}
+
+#[doc(hidden)]
+#[must_use]
+pub const fn max(a: usize, b: usize) -> usize {
+    if a > b { a } else { b }
+}
+
+// Encoded size of enum discriminant, same as `varint::OrdVarIntDiscrEncoder::calc_size()`
+#[doc(hidden)]
+#[must_use]
+pub const fn discriminant_size(index: u32) -> usize {
+    match index {
+        0..=240 => 1,
+        241..=2287 => 2,
+        2288..=67823 => 3,
+        v => (5 - v.leading_zeros() / 8) as usize,
+    }
+}
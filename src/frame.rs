@@ -0,0 +1,349 @@
+//! Framing of concatenated records, for log files and streams
+//!
+//! Serialized value is decoded from a buffer which holds exactly this value (with
+//! [`params::AscendingOrder`](crate::params::AscendingOrder), lengths are read from the end of
+//! the buffer), so values can't be simply appended one after another. Frames mark record
+//! boundaries: each frame is a record prefixed with its length, and optionally followed
+//! by a checksum:
+//!
+//! | Length prefix                    | Payload      | Checksum (optional)                           |
+//! |----------------------------------|--------------|-----------------------------------------------|
+//! | [`VarUInt`] of payload length    | record bytes | 4 bytes, little-endian [`crc32c()`] of length prefix and payload |
+//!
+//! [`FrameWriter`] writes frames to `std::io::Write`. [`FrameReader`] iterates over frames in
+//! a byte slice, [`IoFrameReader`] reads them from `std::io::Read`. Payload of every frame is
+//! a complete serialized record, which can be decoded with [`Deserializer`](crate::Deserializer)
+//! or functions like [`de_from_bytes_asc()`](crate::de_from_bytes_asc).
+//!
+//! Input which ends in the middle of a frame, like a log file with torn write of its last
+//! record, is reported with [`Error::TruncatedFrame`]. A frame which fails checksum
+//! verification is reported with [`Error::FrameChecksumMismatch`]. A frame with corrupted
+//! length prefix may extend beyond the end of input too; with checksums, it is reported with
+//! [`Error::CorruptFrame`] if a valid frame starts within the rest of input, because torn write
+//! leaves only a part of the last frame. Reading stops at the first error;
+//! [`FrameReader::position()`] is the end of the last valid frame, where the log may be
+//! truncated before appending new records. Without checksums, only frames which are shorter
+//! than their length prefix are detected, so use checksums where garbage may be left after
+//! a crash (for example, in pre-allocated files).
+//!
+//! ```
+//! # use ordcode::{ Error, params, de_from_bytes_asc, frame::{ FrameWriter, FrameReader } };
+//! let mut log = FrameWriter::new(Vec::new()).with_checksum();
+//! log.write_value(&(1_u32, "abc"), params::AscendingOrder).unwrap();
+//! log.write_value(&(2_u32, "de"), params::AscendingOrder).unwrap();
+//! let mut log = log.into_inner();
+//! let valid_len = log.len();
+//! log.extend_from_slice(&[9, 0, 0]); // torn write
+//!
+//! let mut reader = FrameReader::new(&log).with_checksum();
+//! let records: Vec<(u32, String)> = reader.by_ref()
+//!     .take_while(|frame| frame.is_ok())
+//!     .map(|frame| de_from_bytes_asc(frame.unwrap()).unwrap())
+//!     .collect();
+//! assert_eq!(records, [(1, "abc".to_string()), (2, "de".to_string())]);
+//! assert_eq!(reader.position(), valid_len);
+//!
+//! assert!(matches!(FrameReader::new(&log).with_checksum().last(), Some(Err(Error::TruncatedFrame))));
+//! ```
+
+use std::convert::TryFrom;
+use std::io::{ self, Read, Write };
+use crate::{ Error, Result, varint::VarUInt };
+
+// maximum length of length prefix
+const MAX_PREFIX_LEN: usize = 9;
+const CHECKSUM_LEN: usize = 4;
+
+const CRC32C_TABLE: [u32; 256] = crc32c_table();
+
+// table for reflected Castagnoli polynomial
+#[allow(clippy::cast_possible_truncation)]
+const fn crc32c_table() -> [u32; 256] {
+    let mut table = [0_u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 1 { (crc >> 1) ^ 0x82F6_3B78 } else { crc >> 1 };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
+
+/// Continue CRC32C calculation of `crc` over `data`
+#[must_use]
+pub fn crc32c_append(crc: u32, data: &[u8]) -> u32 {
+    let crc = data.iter().fold(!crc, |crc, b| {
+        CRC32C_TABLE[usize::from(crc.to_le_bytes()[0] ^ b)] ^ (crc >> 8)
+    });
+    !crc
+}
+
+/// CRC32C (Castagnoli) checksum of `data`, as used for frame checksums
+#[must_use]
+pub fn crc32c(data: &[u8]) -> u32 { crc32c_append(0, data) }
+
+// encode length prefix, returns its buffer and length
+fn length_prefix(len: usize) -> ([u8; MAX_PREFIX_LEN], usize) {
+    let mut prefix = [0_u8; MAX_PREFIX_LEN];
+    let prefix_len = (len as u64).varu_to_slice(&mut prefix);
+    (prefix, usize::from(prefix_len))
+}
+
+// decode payload length from the length prefix
+fn payload_len(prefix: &[u8]) -> Result<usize> {
+    let (len, _) = u64::varu_from_slice(prefix)?;
+    usize::try_from(len).map_err(|_| Error::LengthLimitExceeded)
+}
+
+// Read frame at the start of `input`, returns its payload and frame length
+fn read_frame(input: &[u8], checksum: bool) -> Result<(&[u8], usize)> {
+    let prefix_len = usize::from(u64::varu_decoded_len(input[0]));
+    if input.len() < prefix_len {
+        return Err(Error::TruncatedFrame);
+    }
+    let len = payload_len(&input[..prefix_len])?;
+    let checksum_len = if checksum { CHECKSUM_LEN } else { 0 };
+    let frame_len = len.saturating_add(prefix_len + checksum_len);
+    if input.len() < frame_len {
+        return Err(Error::TruncatedFrame);
+    }
+    let payload = &input[prefix_len..prefix_len + len];
+    if checksum {
+        let mut crc = [0_u8; CHECKSUM_LEN];
+        crc.copy_from_slice(&input[prefix_len + len..frame_len]);
+        if crc32c(&input[..prefix_len + len]) != u32::from_le_bytes(crc) {
+            return Err(Error::FrameChecksumMismatch);
+        }
+    }
+    Ok((payload, frame_len))
+}
+
+// Error for checksummed frame which extends beyond the end of input, `rest` is input after its
+// first byte: torn write leaves no valid frames after the start of the last one
+fn truncated_frame_error(rest: &[u8]) -> Error {
+    if (0..rest.len()).any(|offset| read_frame(&rest[offset..], true).is_ok()) {
+        Error::CorruptFrame
+    } else {
+        Error::TruncatedFrame
+    }
+}
+
+/// Writer of frames to `std::io::Write`, see [module documentation](self)
+///
+/// Every frame is written with a few `write_all()` calls, so unbuffered streams like `File`
+/// should be wrapped into `std::io::BufWriter`.
+pub struct FrameWriter<W> {
+    writer: W,
+    checksum: bool,
+    // serialization buffer for `write_value()`
+    buf: Vec<u8>,
+}
+
+impl<W> FrameWriter<W> where W: Write {
+    /// Write frames without checksums to `writer`
+    #[must_use]
+    pub fn new(writer: W) -> Self { Self { writer, checksum: false, buf: Vec::new() } }
+
+    /// Append checksum to every frame
+    #[must_use]
+    pub fn with_checksum(self) -> Self { Self { checksum: true, ..self } }
+
+    /// Get reference to underlying writer
+    pub fn get_mut(&mut self) -> &mut W { &mut self.writer }
+
+    /// Unwrap underlying writer
+    pub fn into_inner(self) -> W { self.writer }
+
+    /// Write frame with `payload`
+    pub fn write_frame(&mut self, payload: &[u8]) -> Result {
+        let (prefix, prefix_len) = length_prefix(payload.len());
+        self.writer.write_all(&prefix[..prefix_len])?;
+        self.writer.write_all(payload)?;
+        if self.checksum {
+            let crc = crc32c_append(crc32c(&prefix[..prefix_len]), payload);
+            self.writer.write_all(&crc.to_le_bytes())?;
+        }
+        Ok(())
+    }
+
+    /// Serialize `value` with parameters `params` and write it as a frame
+    #[cfg(feature="serde")]
+    pub fn write_value<T, P>(&mut self, value: &T, params: P) -> Result
+        where T: ?Sized + serde::Serialize,
+              P: crate::params::SerializerParams,
+    {
+        let mut buf = std::mem::take(&mut self.buf);
+        buf.clear();
+        buf.resize(crate::calc_size(value, params)?, 0);
+        let res = serialize_exact(&mut buf, value, params).and_then(|()| self.write_frame(&buf));
+        self.buf = buf;
+        res
+    }
+}
+
+#[cfg(feature="serde")]
+fn serialize_exact<T, P>(buf: &mut [u8], value: &T, params: P) -> Result
+    where T: ?Sized + serde::Serialize,
+          P: crate::params::SerializerParams,
+{
+    let mut writer = crate::DeBytesWriter::new(buf);
+    value.serialize(&mut crate::Serializer::new(&mut writer, params))?;
+    writer.is_complete()
+}
+
+/// Iterator over frames in a byte slice, see [module documentation](self)
+///
+/// Yields payloads of frames, borrowed from the slice. Iteration stops after the first error.
+pub struct FrameReader<'a> {
+    buf: &'a [u8],
+    position: usize,
+    checksum: bool,
+    failed: bool,
+}
+
+impl<'a> FrameReader<'a> {
+    /// Read frames without checksums from `buf`
+    #[must_use]
+    pub fn new(buf: &'a [u8]) -> Self { Self { buf, position: 0, checksum: false, failed: false } }
+
+    /// Verify checksum of every frame
+    #[must_use]
+    pub fn with_checksum(self) -> Self { Self { checksum: true, ..self } }
+
+    /// Offset of the end of the last valid frame
+    #[must_use]
+    pub fn position(&self) -> usize { self.position }
+
+    fn read_frame(&self) -> Result<(&'a [u8], usize)> {
+        let input = &self.buf[self.position..];
+        match read_frame(input, self.checksum) {
+            Err(Error::TruncatedFrame) if self.checksum => Err(truncated_frame_error(&input[1..])),
+            res => res,
+        }
+    }
+}
+
+impl<'a> Iterator for FrameReader<'a> {
+    type Item = Result<&'a [u8]>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed || self.position == self.buf.len() {
+            return None;
+        }
+        match self.read_frame() {
+            Ok((payload, frame_len)) => {
+                self.position += frame_len;
+                Some(Ok(payload))
+            },
+            Err(e) => {
+                self.failed = true;
+                Some(Err(e))
+            },
+        }
+    }
+}
+
+/// Iterator over frames read from `std::io::Read`, see [module documentation](self)
+///
+/// Yields payloads of frames. Iteration stops after the first error. Reads are small, so
+/// unbuffered streams like `File` should be wrapped into `std::io::BufReader`.
+pub struct IoFrameReader<R> {
+    reader: R,
+    position: u64,
+    checksum: bool,
+    max_len: usize,
+    failed: bool,
+}
+
+impl<R> IoFrameReader<R> where R: Read {
+    /// Read frames without checksums from `reader`
+    #[must_use]
+    pub fn new(reader: R) -> Self {
+        Self { reader, position: 0, checksum: false, max_len: usize::MAX, failed: false }
+    }
+
+    /// Verify checksum of every frame
+    #[must_use]
+    pub fn with_checksum(self) -> Self { Self { checksum: true, ..self } }
+
+    /// Maximum payload length, longer frames fail with [`Error::LengthLimitExceeded`]
+    #[must_use]
+    pub fn with_max_len(self, max_len: usize) -> Self { Self { max_len, ..self } }
+
+    /// Offset of the end of the last valid frame
+    #[must_use]
+    pub fn position(&self) -> u64 { self.position }
+
+    /// Unwrap underlying reader
+    pub fn into_inner(self) -> R { self.reader }
+
+    // read first byte of the frame, `None` at the end of input
+    fn read_first_byte(&mut self) -> Result<Option<u8>> {
+        let mut byte = [0_u8];
+        loop {
+            match self.reader.read(&mut byte) {
+                Ok(0) => return Ok(None),
+                Ok(_) => return Ok(Some(byte[0])),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => (),
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+    fn read_frame(&mut self, first_byte: u8) -> Result<Vec<u8>> {
+        let mut prefix = [0_u8; MAX_PREFIX_LEN];
+        prefix[0] = first_byte;
+        let prefix_len = usize::from(u64::varu_decoded_len(first_byte));
+        self.reader.read_exact(&mut prefix[1..prefix_len]).map_err(|e| match e.kind() {
+            io::ErrorKind::UnexpectedEof => Error::TruncatedFrame,
+            _ => e.into(),
+        })?;
+        let len = payload_len(&prefix[..prefix_len])?;
+        if len > self.max_len {
+            return Err(Error::LengthLimitExceeded);
+        }
+        // payload and checksum; allocation grows with data actually read, not with untrusted length
+        let checksum_len = if self.checksum { CHECKSUM_LEN } else { 0 };
+        let frame_len = len.saturating_add(checksum_len);
+        let mut payload = Vec::new();
+        (&mut self.reader).take(frame_len as u64).read_to_end(&mut payload)?;
+        if payload.len() < frame_len {
+            if self.checksum {
+                let rest: Vec<u8> = prefix[1..prefix_len].iter().chain(&payload).copied().collect();
+                return Err(truncated_frame_error(&rest));
+            }
+            return Err(Error::TruncatedFrame);
+        }
+        if self.checksum {
+            let mut crc = [0_u8; CHECKSUM_LEN];
+            crc.copy_from_slice(&payload[len..]);
+            payload.truncate(len);
+            if crc32c_append(crc32c(&prefix[..prefix_len]), &payload) != u32::from_le_bytes(crc) {
+                return Err(Error::FrameChecksumMismatch);
+            }
+        }
+        self.position += (prefix_len + frame_len) as u64;
+        Ok(payload)
+    }
+}
+
+impl<R> Iterator for IoFrameReader<R> where R: Read {
+    type Item = Result<Vec<u8>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        let res = match self.read_first_byte() {
+            Ok(None) => return None,
+            Ok(Some(first_byte)) => self.read_frame(first_byte),
+            Err(e) => Err(e),
+        };
+        self.failed = res.is_err();
+        Some(res)
+    }
+}
@@ -0,0 +1,230 @@
+//! Building and reading keys field by field, without `serde`
+//!
+//! [`KeyBuilder`] writes fields of a composite key one after another, each in ascending or
+//! descending order (methods with `_desc` suffix), to any [`WriteBytes`] implementation.
+//! [`KeyReader`] reads them back in the same order from [`ReadBytes`]. Keys are built from
+//! runtime data, like for secondary indexes, where defining a struct for every key is awkward.
+//!
+//! Field encodings:
+//! - primitive types use fixed-size encoding from [`primitives`](crate::primitives)
+//! - `*_compact` integers use order-preserving variable-length encoding from [`varint`](crate::varint)
+//! - strings and byte strings are escaped with
+//!   [`bytes_esc::serialize_bytes_ordered()`](crate::bytes_esc::serialize_bytes_ordered),
+//!   so keys with string fields sort exactly like tuples of their fields
+//! - [`KeyBuilder::value()`] writes any [`SerializableValue`]
+//!
+//! Fields in descending order are bitwise inverted, so key `(a, b_desc)` encodes same as
+//! `(a, Desc(b))` with [`params::EscapedAscending`](crate::params::EscapedAscending) serde pre-set,
+//! and with the same encodings of fields.
+//!
+//! ```
+//! # use ordcode::{ key::{ KeyBuilder, KeyReader }, DeBytesWriter, DeBytesReader };
+//! let mut buf = [0_u8; 32];
+//! let mut writer = DeBytesWriter::new(&mut buf);
+//! KeyBuilder::new(&mut writer).u32(7).str_escaped("name").i64_desc(-100).finish().unwrap();
+//! let len = writer.finalize().unwrap();
+//! assert_eq!(len, 4 + 6 + 8);
+//!
+//! let mut reader = KeyReader::new(DeBytesReader::new(&buf[..len]));
+//! assert_eq!(reader.u32().unwrap(), 7);
+//! assert_eq!(reader.str_escaped().unwrap(), "name");
+//! assert_eq!(reader.i64_desc().unwrap(), -100);
+//! reader.finish().unwrap();
+//! ```
+
+use crate::{ Result, bytes_esc, Order };
+use crate::buf::{ ReadBytes, WriteBytes, InvertingReader, InvertingWriter };
+use crate::params::{ AscendingOrder, DescendingOrder };
+use crate::primitives::SerializableValue;
+use crate::varint::{ OrdVarUInt, OrdVarInt };
+
+/// Builder of composite keys, see [module documentation](self)
+///
+/// Methods can be chained; after the first error, the rest of fields are not written,
+/// and the error is returned by [`finish()`](KeyBuilder::finish).
+pub struct KeyBuilder<W> {
+    writer: W,
+    result: Result,
+}
+
+/// Reader of composite keys built with [`KeyBuilder`], see [module documentation](self)
+pub struct KeyReader<R> {
+    reader: R,
+}
+
+macro_rules! builder_fields {
+    ($($ty:ident $desc_fn:ident),*) => { $(
+        #[doc = concat!("Write `", stringify!($ty), "` field in ascending order")]
+        #[must_use]
+        pub fn $ty(self, value: $ty) -> Self {
+            self.with_writer(|w| value.to_writer(w, AscendingOrder))
+        }
+        #[doc = concat!("Write `", stringify!($ty), "` field in descending order")]
+        #[must_use]
+        pub fn $desc_fn(self, value: $ty) -> Self {
+            self.with_writer(|w| value.to_writer(w, DescendingOrder))
+        }
+    )* };
+}
+
+macro_rules! builder_compact_fields {
+    ($($ty:ident $fn:ident $desc_fn:ident $to_writer:ident),*) => { $(
+        #[doc = concat!("Write `", stringify!($ty), "` field with variable-length encoding, in ascending order")]
+        #[must_use]
+        pub fn $fn(self, value: $ty) -> Self {
+            self.with_writer(|w| value.$to_writer(w))
+        }
+        #[doc = concat!("Write `", stringify!($ty), "` field with variable-length encoding, in descending order")]
+        #[must_use]
+        pub fn $desc_fn(self, value: $ty) -> Self {
+            self.with_writer(|w| value.$to_writer(InvertingWriter::new(w)))
+        }
+    )* };
+}
+
+impl<W> KeyBuilder<W> where W: WriteBytes {
+    /// Create builder which writes key fields to `writer`
+    #[must_use]
+    pub fn new(writer: W) -> Self {
+        Self { writer, result: Ok(()) }
+    }
+    /// Finish building the key, returns writer or the first error
+    pub fn finish(self) -> Result<W> {
+        let Self { writer, result } = self;
+        result.map(|()| writer)
+    }
+    fn with_writer<F>(mut self, f: F) -> Self
+        where F: FnOnce(&mut W) -> Result,
+    {
+        if self.result.is_ok() {
+            self.result = f(&mut self.writer);
+        }
+        self
+    }
+    builder_fields!(bool bool_desc, u8 u8_desc, u16 u16_desc, u32 u32_desc, u64 u64_desc,
+        i8 i8_desc, i16 i16_desc, i32 i32_desc, i64 i64_desc, f32 f32_desc, f64 f64_desc, char char_desc);
+    #[cfg(not(no_i128))]
+    builder_fields!(u128 u128_desc, i128 i128_desc);
+    builder_compact_fields!(u32 u32_compact u32_compact_desc ord_varu_to_writer,
+        u64 u64_compact u64_compact_desc ord_varu_to_writer,
+        i32 i32_compact i32_compact_desc ord_vari_to_writer,
+        i64 i64_compact i64_compact_desc ord_vari_to_writer);
+
+    /// Write escaped string field in ascending order
+    #[must_use]
+    pub fn str_escaped(self, value: &str) -> Self {
+        self.bytes_escaped(value.as_bytes())
+    }
+    /// Write escaped string field in descending order
+    #[must_use]
+    pub fn str_escaped_desc(self, value: &str) -> Self {
+        self.bytes_escaped_desc(value.as_bytes())
+    }
+    /// Write escaped byte string field in ascending order
+    #[must_use]
+    pub fn bytes_escaped(self, value: &[u8]) -> Self {
+        self.with_writer(|w| bytes_esc::serialize_bytes_ordered(w, value, AscendingOrder))
+    }
+    /// Write escaped byte string field in descending order
+    #[must_use]
+    pub fn bytes_escaped_desc(self, value: &[u8]) -> Self {
+        self.with_writer(|w| bytes_esc::serialize_bytes_ordered(w, value, DescendingOrder))
+    }
+    /// Write field of any [`SerializableValue`] type with `order`
+    #[must_use]
+    pub fn value<T>(self, value: &T, order: Order) -> Self
+        where T: SerializableValue + ?Sized,
+    {
+        match order {
+            Order::Ascending | Order::Unordered => self.with_writer(|w| value.to_writer(w, AscendingOrder)),
+            Order::Descending => self.with_writer(|w| value.to_writer(w, DescendingOrder)),
+        }
+    }
+}
+
+macro_rules! reader_fields {
+    ($($ty:ident $desc_fn:ident),*) => { $(
+        #[doc = concat!("Read `", stringify!($ty), "` field in ascending order")]
+        pub fn $ty(&mut self) -> Result<$ty> {
+            <$ty>::from_reader(&mut self.reader, AscendingOrder)
+        }
+        #[doc = concat!("Read `", stringify!($ty), "` field in descending order")]
+        pub fn $desc_fn(&mut self) -> Result<$ty> {
+            <$ty>::from_reader(&mut self.reader, DescendingOrder)
+        }
+    )* };
+}
+
+macro_rules! reader_compact_fields {
+    ($($ty:ident $fn:ident $desc_fn:ident $from_reader:ident),*) => { $(
+        #[doc = concat!("Read `", stringify!($ty), "` field with variable-length encoding, in ascending order")]
+        pub fn $fn(&mut self) -> Result<$ty> {
+            <$ty>::$from_reader(&mut self.reader)
+        }
+        #[doc = concat!("Read `", stringify!($ty), "` field with variable-length encoding, in descending order")]
+        pub fn $desc_fn(&mut self) -> Result<$ty> {
+            <$ty>::$from_reader(InvertingReader::new(&mut self.reader))
+        }
+    )* };
+}
+
+impl<R> KeyReader<R> where R: ReadBytes {
+    /// Create reader of key fields from `reader`
+    #[must_use]
+    pub fn new(reader: R) -> Self {
+        Self { reader }
+    }
+    /// Check that all fields have been read
+    pub fn finish(mut self) -> Result {
+        self.reader.is_complete()
+    }
+    /// Unwrap underlying reader
+    pub fn into_inner(self) -> R { self.reader }
+
+    reader_fields!(bool bool_desc, u8 u8_desc, u16 u16_desc, u32 u32_desc, u64 u64_desc,
+        i8 i8_desc, i16 i16_desc, i32 i32_desc, i64 i64_desc, f32 f32_desc, f64 f64_desc, char char_desc);
+    #[cfg(not(no_i128))]
+    reader_fields!(u128 u128_desc, i128 i128_desc);
+    reader_compact_fields!(u32 u32_compact u32_compact_desc ord_varu_from_reader,
+        u64 u64_compact u64_compact_desc ord_varu_from_reader,
+        i32 i32_compact i32_compact_desc ord_vari_from_reader,
+        i64 i64_compact i64_compact_desc ord_vari_from_reader);
+
+    /// Read escaped string field in ascending order
+    #[cfg(feature="std")]
+    pub fn str_escaped(&mut self) -> Result<String> {
+        String::from_utf8(self.bytes_escaped()?).map_err(|_| crate::Error::InvalidUtf8Encoding)
+    }
+    /// Read escaped string field in descending order
+    #[cfg(feature="std")]
+    pub fn str_escaped_desc(&mut self) -> Result<String> {
+        String::from_utf8(self.bytes_escaped_desc()?).map_err(|_| crate::Error::InvalidUtf8Encoding)
+    }
+    /// Read escaped byte string field in ascending order
+    #[cfg(feature="std")]
+    pub fn bytes_escaped(&mut self) -> Result<Vec<u8>> {
+        bytes_esc::deserialize_bytes_ordered_to_vec(&mut self.reader, AscendingOrder)
+    }
+    /// Read escaped byte string field in descending order
+    #[cfg(feature="std")]
+    pub fn bytes_escaped_desc(&mut self) -> Result<Vec<u8>> {
+        bytes_esc::deserialize_bytes_ordered_to_vec(&mut self.reader, DescendingOrder)
+    }
+    /// Read escaped byte string field in ascending order, unescaped bytes are written to `out`
+    pub fn bytes_escaped_to_writer(&mut self, out: impl WriteBytes) -> Result {
+        bytes_esc::deserialize_bytes_ordered_to_writer(&mut self.reader, out, AscendingOrder)
+    }
+    /// Read escaped byte string field in descending order, unescaped bytes are written to `out`
+    pub fn bytes_escaped_desc_to_writer(&mut self, out: impl WriteBytes) -> Result {
+        bytes_esc::deserialize_bytes_ordered_to_writer(&mut self.reader, out, DescendingOrder)
+    }
+    /// Read field of any [`SerializableValue`] type with `order`
+    pub fn value<T>(&mut self, order: Order) -> Result<T>
+        where T: SerializableValue,
+    {
+        match order {
+            Order::Ascending | Order::Unordered => T::from_reader(&mut self.reader, AscendingOrder),
+            Order::Descending => T::from_reader(&mut self.reader, DescendingOrder),
+        }
+    }
+}
@@ -0,0 +1,235 @@
+//! Per-field layout of serialized data
+//!
+//! [`calc_layout()`](crate::calc_layout) reports which bytes each value nested in serialized data
+//! takes in the head and in the tail of the buffer, and how many of them are spent on lengths of
+//! sequences, strings and byte strings (length prefixes, or markers and escaping for escaped
+//! sequences). It helps to find fields worth moving to compact encodings, and to check that
+//! layout of keys matches the design.
+//!
+//! ```
+//! # use ordcode::{ calc_layout, params };
+//! #[derive(serde_derive::Serialize)]
+//! struct Key { id: u32, name: String, tags: Vec<u8> }
+//!
+//! let key = Key { id: 7, name: "ab".to_string(), tags: vec![1, 2] };
+//! let layout = calc_layout(&key, params::AscendingOrder).unwrap();
+//! assert_eq!(layout.size(), 10);
+//!
+//! let name = layout.iter().find(|entry| entry.path() == "name").unwrap();
+//! assert_eq!(name.head(), 4..6);
+//! assert_eq!(name.tail(), 9..10);
+//! assert_eq!(name.len_overhead(), 1);
+//!
+//! println!("{}", layout);
+//! // path     head   tail     size  lengths
+//! // <root>   0..8   8..10      10        2
+//! // id       0..4   -           4        0
+//! // name     4..6   9..10       3        1
+//! // tags     6..8   8..9        3        1
+//! // tags[0]  6..7   -           1        0
+//! // tags[1]  7..8   -           1        0
+//! ```
+//!
+//! Paths have the same format as in [`ErrorContext::path()`](crate::ErrorContext::path),
+//! without the type name: tuple fields and items of sequences are indexed like `[1]`, key and
+//! value of a map item share the path of the item. Enum variants are not part of the path.
+//! Entries are listed in serialization order, the outer value before values nested in it;
+//! the first entry is the whole serialized value, with empty path.
+//!
+//! Values inside [`Desc`](crate::Desc) wrapper take the same bytes as without it.
+//!
+//! Layout is recorded by [`Serializer`](crate::Serializer) while it writes into [`LayoutCalc`],
+//! so it is exact for any serializer parameters.
+
+use core::fmt;
+use core::ops::Range;
+use crate::{ Result, buf::{ WriteBytes, TailWriteBytes } };
+use crate::errors::{ PathSegment, format_path };
+
+/// Layout of serialized value, see [module documentation](self)
+#[derive(Debug, Clone)]
+pub struct Layout {
+    entries: Vec<LayoutEntry>,
+    head: usize,
+    tail: usize,
+}
+
+/// Bytes taken by a value nested in serialized data
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LayoutEntry {
+    path: String,
+    head: Range<usize>,
+    tail: Range<usize>,
+    // positions of `LayoutCalc` length overhead counter
+    overhead: Range<usize>,
+}
+
+impl Layout {
+    /// Total serialized size
+    #[must_use]
+    pub fn size(&self) -> usize { self.head + self.tail }
+
+    /// Number of bytes at the head of the buffer
+    #[must_use]
+    pub fn head_size(&self) -> usize { self.head }
+
+    /// Number of bytes at the tail of the buffer
+    #[must_use]
+    pub fn tail_size(&self) -> usize { self.tail }
+
+    /// All entries, the whole value first
+    #[must_use]
+    pub fn entries(&self) -> &[LayoutEntry] { &self.entries }
+
+    /// Iterate over entries, the whole value first
+    pub fn iter(&self) -> core::slice::Iter<'_, LayoutEntry> { self.entries.iter() }
+}
+
+impl<'a> IntoIterator for &'a Layout {
+    type Item = &'a LayoutEntry;
+    type IntoIter = core::slice::Iter<'a, LayoutEntry>;
+
+    fn into_iter(self) -> Self::IntoIter { self.entries.iter() }
+}
+
+impl IntoIterator for Layout {
+    type Item = LayoutEntry;
+    type IntoIter = std::vec::IntoIter<LayoutEntry>;
+
+    fn into_iter(self) -> Self::IntoIter { self.entries.into_iter() }
+}
+
+impl LayoutEntry {
+    /// Path to the value, like `items[3].name`; empty for the whole value
+    #[must_use]
+    pub fn path(&self) -> &str { &self.path }
+
+    /// Byte range at the head of the buffer, in serialized data
+    #[must_use]
+    pub fn head(&self) -> Range<usize> { self.head.clone() }
+
+    /// Byte range at the tail of the buffer, in serialized data
+    #[must_use]
+    pub fn tail(&self) -> Range<usize> { self.tail.clone() }
+
+    /// Number of bytes taken by the value
+    #[must_use]
+    pub fn size(&self) -> usize { self.head.len() + self.tail.len() }
+
+    /// Number of bytes taken by lengths of sequences, strings and byte strings within the value
+    #[must_use]
+    pub fn len_overhead(&self) -> usize { self.overhead.len() }
+}
+
+impl fmt::Display for Layout {
+    /// Print layout as a table, one entry per line
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn range(r: &Range<usize>) -> String {
+            if r.is_empty() { "-".to_string() } else { format!("{}..{}", r.start, r.end) }
+        }
+        const ROOT: &str = "<root>";
+        let width = self.entries.iter().map(|e| e.path.len()).chain(Some(ROOT.len())).max().unwrap_or(0);
+        let rwidth = self.entries.iter().map(|e| range(&e.head).len().max(range(&e.tail).len()))
+            .chain(Some(4)).max().unwrap_or(0);
+        write!(f, "{:width$}  {:rwidth$}  {:rwidth$}  {:>6}  {:>7}", "path", "head", "tail", "size", "lengths")?;
+        for entry in &self.entries {
+            let path = if entry.path.is_empty() { ROOT } else { &entry.path };
+            write!(f, "\n{:width$}  {:rwidth$}  {:rwidth$}  {:>6}  {:>7}", path, range(&entry.head),
+                   range(&entry.tail), entry.size(), entry.len_overhead())?;
+        }
+        Ok(())
+    }
+}
+
+/// Writer which records layout of data serialized into it
+///
+/// Serialize a value with [`Serializer`](crate::Serializer) into `LayoutCalc`, then
+/// `.into_layout()` will return [`Layout`] of serialized data; the data itself is not kept.
+/// See [module documentation](self) and [`calc_layout()`](crate::calc_layout).
+#[derive(Default)]
+pub struct LayoutCalc {
+    head: usize,
+    tail: usize,
+    // bytes taken by lengths so far
+    overhead: usize,
+    // entries of values being serialized, with their path segments
+    open: Vec<(usize, PathSegment)>,
+    // last finished entry, which is extended by map value
+    last: Option<(usize, PathSegment)>,
+    // tail ranges are counted from the end of data until `into_layout()`
+    entries: Vec<LayoutEntry>,
+}
+
+impl LayoutCalc {
+    #[must_use]
+    pub fn new() -> Self { Self::default() }
+
+    /// Returns calculated layout
+    #[must_use]
+    pub fn into_layout(self) -> Layout {
+        let (head, tail) = (self.head, self.tail);
+        let size = head + tail;
+        let root = LayoutEntry { path: String::new(), head: 0..head, tail: head..size, overhead: 0..self.overhead };
+        let entries = Some(root).into_iter().chain(self.entries.into_iter().map(|mut entry| {
+            entry.tail = (size - entry.tail.end)..(size - entry.tail.start);
+            entry
+        })).collect();
+        Layout { entries, head, tail }
+    }
+
+    // Start entry for nested value at offsets `at`
+    pub(crate) fn begin(&mut self, segment: PathSegment, at: (usize, usize)) {
+        self.open.push((self.entries.len(), segment));
+        self.entries.push(LayoutEntry {
+            path: format_path(self.open.iter().map(|(_, segment)| segment)),
+            head: at.0..at.0,
+            tail: at.1..at.1,
+            overhead: self.overhead..self.overhead,
+        });
+    }
+    // Extend the innermost started entry up to offsets `at`
+    pub(crate) fn end(&mut self, at: (usize, usize)) {
+        if let Some((index, segment)) = self.open.pop() {
+            let entry = &mut self.entries[index];
+            entry.head.end = at.0;
+            entry.tail.end = at.1;
+            entry.overhead.end = self.overhead;
+            self.last = Some((index, segment));
+        }
+    }
+    // Start again the last finished entry: map value extends entry of its key
+    pub(crate) fn reopen(&mut self) {
+        if let Some(last) = self.last.take() {
+            self.open.push(last);
+        }
+    }
+    pub(crate) fn add_overhead(&mut self, n: usize) {
+        self.overhead += n;
+    }
+    // Index of the next entry
+    pub(crate) fn next_entry(&self) -> usize { self.entries.len() }
+
+    // Length of sequence, written after its items starting with entry `first`, precedes them:
+    // move them by the size of the length
+    pub(crate) fn move_entries(&mut self, first: usize, head: usize, tail: usize) {
+        for entry in &mut self.entries[first..] {
+            entry.head = (entry.head.start + head)..(entry.head.end + head);
+            entry.tail = (entry.tail.start + tail)..(entry.tail.end + tail);
+        }
+    }
+}
+
+impl WriteBytes for LayoutCalc {
+    fn write(&mut self, value: &[u8]) -> Result {
+        self.head += value.len();
+        Ok(())
+    }
+}
+
+impl TailWriteBytes for LayoutCalc {
+    fn write_tail(&mut self, value: &[u8]) -> Result {
+        self.tail += value.len();
+        Ok(())
+    }
+    fn layout_calc(&mut self) -> Option<&mut LayoutCalc> { Some(self) }
+}
@@ -24,9 +24,30 @@
 //! * encoding puts lengths of variable-size sequences to the end of serialized data,
 //!   so resulting encoding is prefix-free and friendly to lexicographic ordering
 //! * zero allocations, supports `#[no_std]` environments
+//! * zero-copy deserialization of borrowed `&str` and `&[u8]`
 //! * method to cheaply get exact size of serialized data without doing actual serialization,
 //!   for effective buffer management
+//! * growable double-ended buffer `buf::DeVecWriter` for single-pass serialization
 //! * space-efficient varint encoding for sequence lengths and discriminants
+//! * optional order-preserving varint encoding for integers (`params::CompactAscending` pre-set)
+//! * per-field descending ordering with `Desc<T>` wrapper
+//! * appending of struct fields with default values, with `params::Versioned` parameters
+//! * resource limits (sequence length, nesting depth, allocations) for untrusted input
+//! * strict decoding mode which accepts only canonical encoding of values
+//! * validation of stored data against a type (`validate()`)
+//! * compile-time encoded size of fixed-size types with `FixedSize` trait and derive macro
+//! * `key::KeyBuilder` and `key::KeyReader` for building composite keys field by field without `serde`
+//! * `range::prefix_range()` for scanning keys by prefix of their fields, usable with `BTreeMap::range()`
+//! * typed `Encoded<T>` bytes wrapper, ordered by bytes and decoded on demand
+//! * dynamic `value::Value` described by `value::Schema` at runtime, encoded same as Rust types
+//! * per-field layout report with `calc_layout()`: head and tail byte ranges and length overhead
+//! * streaming `buf::IoWriter` and `buf::IoReader` adapters over `std::io` for pre-sets without tail
+//! * `frame` module for appending records to log files, with length prefixes, optional CRC32C and
+//!   detection of torn writes
+//! * `ordcode` command-line tool for decoding, encoding and showing layout of keys
+//! * `params::EscapedAscending` pre-set for composite keys which sort exactly like tuples they came from
+//! * self-describing `params::SelfDescribing` pre-set with `deserialize_any()` support, for untagged
+//!   and internally tagged enums and for skipping unknown struct fields
 //! * easily customizable (endianness, encoding of primitive types etc.), with useful pre-sets
 //! * reader/writer traits for double-ended buffers, so you can implement your own or use
 //!   implementations provided by the crate
@@ -35,14 +56,25 @@
 //! ## Cargo.toml features and dependencies
 //!
 //! * `serde` (on by default): include `serde` serializer and deserializer.
-//!    If you need only primitives, you can opt out.
+//!   If you need only primitives, you can opt out.
 //! * `std` (on by default): opt out for `#[no-std]` use, you will lose some convenience methods
 //!   which use `Vec<u8>`
+//! * `alloc` (enabled by `std`): paths to failed values in `ErrorContext` and text of custom error
+//!   messages, for `#[no-std]` targets with an allocator
+//! * `derive`: re-export `#[derive(FixedSize)]` macro from `ordcode-derive` crate
+//! * `cli`: build `ordcode` command-line tool (`cargo install ordcode --features cli`)
 //!
 //! ## Stability guarantees
 //! The underlying encoding format is simple and unlikely to change.
 //! As a safeguard, `Serializer` and `Deserializer` implement `FormatVersion` trait for all serializer parameter
-//! pre-sets (`params::AscendingOrder`, `params::PortableBinary`, `params::NativeBinary`).
+//! pre-sets (`params::AscendingOrder`, `params::EscapedAscending`, `params::CompactAscending`,
+//! `params::SelfDescribing`, `params::PortableBinary`, `params::NativeBinary`).
+//!
+//! ## Breaking changes in 0.3.0
+//! * `Error` is no longer `Copy`: `Error::Custom` keeps the message reported by `Serialize` or
+//!   `Deserialize` implementations (the text is kept with `alloc` feature only), and `Error::Io` keeps
+//!   errors of `std::io` readers and writers. Use `clone()` where errors were copied.
+//! * hidden `Error::Serde` variant is replaced with `Error::Custom`
 //!
 //! Note: serializing with descending lexicographic order is particularly useful for key-value
 //! databases like _rocksdb_, where reverse iteration is slower than forward iteration.
@@ -51,11 +83,12 @@
 #![deny(clippy::all, clippy::pedantic)]
 #![allow(clippy::missing_errors_doc, clippy::map_err_ignore )]
 
-#[cfg(feature="serde")] #[macro_use] extern crate serde;
+#[cfg(feature="serde")] extern crate serde;
+#[cfg(feature="alloc")] extern crate alloc;
 
 #[macro_use] mod errors;
 #[doc(inline)]
-pub use errors::Error;
+pub use errors::{Error, ErrorContext, ErrorMessage};
 
 /// A convenient Result type
 pub type Result<T = (), E = errors::Error> = core::result::Result<T, E>;
@@ -66,21 +99,38 @@ pub mod bytes_esc;
 
 pub mod params;
 pub mod buf;
+pub mod fixed_size;
+pub mod key;
+pub mod range;
+#[cfg(feature="std")] pub mod frame;
 
 #[doc(inline)]
 pub use params::Order;
 pub use buf::{DeBytesReader, DeBytesWriter, ReadFromTail, WriteToTail };
+#[doc(inline)]
+pub use fixed_size::FixedSize;
+#[cfg(feature="derive")] pub use ordcode_derive::FixedSize;
 
+#[cfg(feature="serde")] pub mod desc;
 #[cfg(feature="serde")] mod size_calc;
 #[cfg(feature="serde")] mod ord_ser;
 #[cfg(feature="serde")] mod ord_de;
+#[cfg(all(feature="std", feature="serde"))] pub mod encoded;
+#[cfg(all(feature="std", feature="serde"))] pub mod value;
+#[cfg(all(feature="std", feature="serde"))] pub mod layout;
 
 #[doc(inline)]
 #[cfg(feature="serde")] pub use ord_ser::Serializer;
 #[doc(inline)]
-#[cfg(feature="serde")] pub use ord_de::Deserializer;
+#[cfg(feature="serde")] pub use ord_de::{Deserializer, Limits};
 #[doc(inline)]
 #[cfg(feature="serde")] pub use size_calc::SizeCalc;
+#[doc(inline)]
+#[cfg(all(feature="std", feature="serde"))] pub use layout::LayoutCalc;
+#[doc(inline)]
+#[cfg(feature="serde")] pub use desc::Desc;
+#[doc(inline)]
+#[cfg(all(feature="std", feature="serde"))] pub use encoded::{Encoded, EncodedRef};
 
 /// Current version of data encoding format for [`Serializer`] parametrized with
 /// some [`params::SerializerParams`].
@@ -123,6 +173,27 @@ pub fn calc_size_asc<T>(value: &T) -> Result<usize>
     calc_size(value, params::AscendingOrder)
 }
 
+/// Calculate per-field layout of serialized data for a [`serde::Serialize`] value
+///
+/// For each value nested in serialized data, [`layout::Layout`] has its byte ranges at the head
+/// and at the tail of the buffer, and number of bytes taken by lengths. See [`layout`] module.
+///
+/// ```
+/// # use ordcode::{ calc_layout, params };
+/// let layout = calc_layout(&(1_u16, "abc"), params::AscendingOrder).unwrap();
+/// let ranges: Vec<_> = layout.iter().map(|e| (e.path(), e.head(), e.tail())).collect();
+/// assert_eq!(ranges, [("", 0..5, 5..6), ("[0]", 0..2, 6..6), ("[1]", 2..5, 5..6)]);
+/// ```
+#[cfg(all(feature="std", feature="serde"))]
+pub fn calc_layout<T, P>(value: &T, params: P) -> Result<layout::Layout>
+    where T: ?Sized + serde::ser::Serialize,
+          P: params::SerializerParams,
+{
+    let mut lc = layout::LayoutCalc::new();
+    value.serialize(&mut Serializer::new(&mut lc, params))?;
+    Ok(lc.into_layout())
+}
+
 /// Serialize `value` into pre-allocated byte buffer.
 ///
 /// Buffer is supposed to be large enough to hold serialized data. You can use [`calc_size()`]
@@ -245,9 +316,151 @@ pub fn de_from_bytes_asc<I, T>(input: I) -> Result<T>
     let mut deser = new_de_asc(&mut reader);
     T::deserialize(&mut deser)
 }
+
+/// Deserialize value which may borrow from byte slice, with [`params::AscendingOrder`]
+///
+/// Borrowed `&str` and `&[u8]` fields point directly into `input`, no copying is done.
+///
+/// *Example*
+/// ```
+/// # use serde::de::Deserialize;
+/// # use ordcode::de_from_bytes_borrowed;
+///
+/// #[derive(serde_derive::Deserialize)]
+/// struct Foo<'a>(u16, &'a str);
+///
+/// let buf = [0_u8, 1, b'a', b'b', b'c', 7];
+/// let foo: Foo = de_from_bytes_borrowed(&buf).unwrap();
+/// assert_eq!(foo.0, 1);
+/// assert_eq!(foo.1, "abc");
+/// ```
+#[cfg(feature="serde")]
+pub fn de_from_bytes_borrowed<'de, T>(input: &'de [u8]) -> Result<T>
+    where T: serde::de::Deserialize<'de>,
+{
+    let mut reader = DeBytesReader::new(input);
+    let mut deser = new_de_asc(&mut reader);
+    T::deserialize(&mut deser)
+}
+
+/// Deserialize value from byte slice with [`params::AscendingOrder`], accepting only canonical encoding
+///
+/// Fails if input is not fully consumed, or has non-canonical encoding of values (see
+/// [`Deserializer::with_strict_decoding()`]), so that there is exactly one byte string for each value.
+///
+/// *Example*
+/// ```
+/// # use ordcode::{ Error, de_from_bytes_strict };
+/// assert_eq!(de_from_bytes_strict::<_, (u8, bool)>(&[1, 1]).unwrap(), (1, true));
+/// assert!(matches!(de_from_bytes_strict::<_, (u8, bool)>(&[1, 2]), Err(Error::InvalidBoolEncoding)));
+/// assert!(matches!(de_from_bytes_strict::<_, (u8, bool)>(&[1, 1, 0]), Err(Error::TrailingBytes)));
+/// ```
+#[cfg(feature="serde")]
+pub fn de_from_bytes_strict<I, T>(input: I) -> Result<T>
+    where I: AsRef<[u8]>,
+          T: serde::de::DeserializeOwned,
+{
+    let mut reader = DeBytesReader::new(input.as_ref());
+    let mut deser = new_de_asc(&mut reader).with_strict_decoding();
+    let value = T::deserialize(&mut deser)?;
+    check_consumed(&mut reader)?;
+    Ok(value)
+}
+
+/// Check that byte slice contains a valid serialized value of type `T`
+///
+/// Deserializes `T` with the [`Deserializer`] and drops it, checking structure, lengths, escaping
+/// and UTF-8 encoding of strings, checks of `Deserialize` implementations of `T` (like `try_from`
+/// conversions), and that input is fully consumed. Useful for scrubbing stored data after a crash
+/// or a format upgrade.
+///
+/// Note that the value is built: strings, vectors, maps and boxes in `T` are allocated as with
+/// any deserialization. Use types with borrowed fields (`&str`, `&[u8]` with `serde_bytes`) to avoid
+/// copies of strings. Strings with escaped bytes (see [`params::EscapedAscending`]) and strings inside
+/// [`Desc`] are still copied to the scratch buffer of the deserializer, which grows as needed with `std`
+/// feature. Values can't be skipped without building them either: serialized data does not describe
+/// its shape, so `deserialize_ignored_any()` is supported with [`params::SelfDescribing`] only.
+///
+/// To validate with other deserializer options, use [`Deserializer::validate()`].
+///
+/// *Example*
+/// ```
+/// # use ordcode::{ Error, params, validate };
+/// #[derive(serde_derive::Deserialize)]
+/// struct Foo(u16, String);
+///
+/// let mut buf = [0_u8, 1, b'a', b'b', b'c', 7];
+/// assert!(validate::<Foo>(&buf, params::AscendingOrder).is_ok());
+/// buf[2] = 0xFF;
+/// assert!(matches!(validate::<Foo>(&buf, params::AscendingOrder), Err(Error::InvalidUtf8Encoding)));
+/// ```
+#[cfg(feature="serde")]
+pub fn validate<'de, T>(input: &'de [u8], params: impl params::SerializerParams) -> Result
+    where T: serde::de::Deserialize<'de>,
+{
+    let mut reader = DeBytesReader::new(input);
+    Deserializer::new(&mut reader, params).validate::<T>()?;
+    check_consumed(&mut reader)
+}
+
+// Unlike `ReadBytes::is_complete()`, tells trailing bytes from truncated input
+#[cfg(feature="serde")]
+fn check_consumed(reader: &mut impl buf::ReadBytes) -> Result {
+    if reader.remaining_buffer().is_empty() { Ok(()) } else { Err(Error::TrailingBytes) }
+}
+
+/// Deserialize value from untrusted byte slice with [`params::AscendingOrder`], enforcing resource limits
+///
+/// See [`Limits`] for details.
+///
+/// *Example*
+/// ```
+/// # use ordcode::{ Error, Limits, de_from_bytes_with_limits };
+/// // sequence of 1000 items in 3 bytes
+/// let buf = [0_u8, 0x0F, 0xA2];
+/// assert!(matches!(de_from_bytes_with_limits::<_, Vec<u8>>(&buf, Limits::new()), Err(Error::LengthLimitExceeded)));
+/// ```
+#[cfg(feature="serde")]
+pub fn de_from_bytes_with_limits<I, T>(input: I, limits: Limits) -> Result<T>
+    where I: AsRef<[u8]>,
+          T: serde::de::DeserializeOwned,
+{
+    let mut reader = DeBytesReader::new(input.as_ref());
+    let mut deser = new_de_asc(&mut reader).with_limits(limits);
+    T::deserialize(&mut deser)
+}
+
+/// Deserialize value from byte slice serialized in [`Order::Descending`]
+///
+/// Input buffer is not modified, bytes are inverted on the fly with [`buf::InvertingReader`].
+///
+/// *Example*
+/// ```
+/// # use serde::de::Deserialize;
+/// # use ordcode::de_from_bytes_desc;
+///
+/// #[derive(serde_derive::Deserialize)]
+/// struct Foo(u16, String);
+///
+/// let buf = [255_u8, 254, 158, 157, 156, 248];
+/// let foo: Foo = de_from_bytes_desc(&buf).unwrap();
+/// assert_eq!(foo.0, 1);
+/// assert_eq!(foo.1, "abc");
+/// ```
+#[cfg(feature="serde")]
+pub fn de_from_bytes_desc<I, T>(input: I) -> Result<T>
+    where I: AsRef<[u8]>,
+          T: serde::de::DeserializeOwned,
+{
+    let mut reader = buf::InvertingReader::new(DeBytesReader::new(input.as_ref()));
+    let mut deser = new_de_asc(&mut reader);
+    T::deserialize(&mut deser)
+}
+
 /// Deserialize value from mutable byte slice.
 ///
-/// For [`Order::Descending`], the buffer will be inverted in-place.
+/// For [`Order::Descending`], the buffer will be inverted in-place. If you need to keep
+/// input buffer intact, use [`de_from_bytes_desc()`].
 ///
 /// *Example*
 /// ```
@@ -1,115 +1,605 @@
-use crate::{Error, FormatVersion, buf::TailReadBytes, Result, params::{SerializerParams, LengthEncoder }};
-use crate::params::{AscendingOrder, PortableBinary, NativeBinary};
+use crate::{Error, ErrorContext, FormatVersion, buf::{ReadBytes, TailReadBytes, ReadBytesBorrow, InvertingReader, Counting, ScratchBuffer, DefaultScratchBuffer}, Result, bytes_esc,
+            params::{SerializerParams, LengthEncoder, SeqEncoding, IntEncoding, TypeTag, SEQ_ITEM_MARKER, SEQ_END_MARKER },
+            varint::{OrdVarUInt, OrdVarInt}};
+use crate::params::{AscendingOrder, DescendingOrder, PortableBinary, NativeBinary, EscapedAscending, CompactAscending, SelfDescribing};
 use crate::primitives::SerializableValue;
+use crate::desc::DESC_NEWTYPE_NAME;
+use crate::errors::PathSegment;
 use serde::de::IntoDeserializer;
 
 /// `serde` deserializer for binary data format which may preserve lexicographic ordering of values
 ///
 /// Please see `Serializer` documentation for more details.
-pub struct Deserializer<R, P> {
-    reader: R,
+///
+/// Deserializer does not require the input to be fully consumed, so data with extra trailing
+/// fields appended by newer versions of struct definitions can be read by old code. Use
+/// [`ReadBytes::is_complete()`](crate::buf::ReadBytes::is_complete) on reader to detect such data.
+/// To read old data into structs with appended fields, see [`params::Versioned`](crate::params::Versioned).
+///
+/// When deserialization fails, location of the error is available with [`Deserializer::error_context()`].
+///
+/// For untrusted input, set resource limits with [`Deserializer::with_limits()`].
+pub struct Deserializer<R, P, B = DefaultScratchBuffer> {
+    // inverts bytes inside `Desc` values
+    reader: InvertingReader<Counting<R>, B>,
     params: P,
+    state: State,
+    error_context: Option<ErrorContext>,
+}
+
+// Options and limit counters
+#[derive(Copy, Clone, Default)]
+struct State {
+    strict: bool,
+    limits: Option<Limits>,
+    depth: usize,
+    allocated: usize,
+}
+
+/// Resource limits for deserialization of untrusted input
+///
+/// With limits set by [`Deserializer::with_limits()`], lengths of sequences, maps and byte
+/// strings are checked before anything is read or allocated: length should not exceed
+/// the remaining input size (so sequences of zero-sized items like `Vec<()>` can't be longer),
+/// if the reader knows it (see [`ReadBytes::remaining_len()`](crate::buf::ReadBytes::remaining_len)),
+/// and [`max_seq_len`](Limits::max_seq_len). Otherwise, [`Error::LengthLimitExceeded`] is returned.
+///
+/// Allocation budget is an upper bound of memory which can be allocated by deserialized value:
+/// it is spent by lengths of all strings, byte strings, sequences and maps, and
+/// [`Error::AllocationLimitExceeded`] is returned when it is exhausted.
+///
+/// Nesting depth counts sequences, tuples, structs, maps, enums, options and newtype structs,
+/// [`Error::DepthLimitExceeded`] is returned for deeper values.
+///
+/// ```
+/// # use ordcode::{ Limits, Error, de_from_bytes_with_limits, ser_to_vec_ordered, Order };
+/// let buf = ser_to_vec_ordered(&vec![vec![1_u8, 2], vec![3]], Order::Ascending).unwrap();
+/// let limits = Limits::new().max_depth(1);
+/// assert!(matches!(de_from_bytes_with_limits::<_, Vec<Vec<u8>>>(&buf, limits), Err(Error::DepthLimitExceeded)));
+/// let limits = Limits::new().max_depth(2).max_alloc(5);
+/// assert_eq!(de_from_bytes_with_limits::<_, Vec<Vec<u8>>>(&buf, limits).unwrap(), vec![vec![1, 2], vec![3]]);
+/// ```
+#[derive(Copy, Clone, Debug)]
+pub struct Limits {
+    seq_len: usize,
+    depth: usize,
+    alloc: usize,
+}
+
+impl Limits {
+    /// Limits sequence lengths by remaining input size only
+    #[must_use]
+    pub const fn new() -> Self {
+        Self { seq_len: usize::MAX, depth: usize::MAX, alloc: usize::MAX }
+    }
+    /// Maximum number of items in sequence or map, or of bytes in byte string
+    #[must_use]
+    pub const fn max_seq_len(self, max_seq_len: usize) -> Self {
+        Self { seq_len: max_seq_len, ..self }
+    }
+    /// Maximum nesting depth
+    #[must_use]
+    pub const fn max_depth(self, max_depth: usize) -> Self {
+        Self { depth: max_depth, ..self }
+    }
+    /// Total allocation budget, in bytes and items of sequences
+    #[must_use]
+    pub const fn max_alloc(self, max_alloc: usize) -> Self {
+        Self { alloc: max_alloc, ..self }
+    }
+}
+
+impl Default for Limits {
+    fn default() -> Self { Self::new() }
+}
+
+macro_rules! read_int_fn {
+    ($fn:ident, $ty:ty, $compact_fn:ident, $len_fn:ident) => {
+        // Read integer with fixed or compact encoding, depending on `P::INT_ENCODING`
+        #[inline]
+        fn $fn(&mut self) -> Result<$ty> {
+            match P::INT_ENCODING {
+                IntEncoding::Fixed => <$ty>::from_reader(&mut self.reader, self.params),
+                IntEncoding::Compact => {
+                    let start = self.position();
+                    let value = <$ty>::$compact_fn(&mut self.reader)?;
+                    self.check_canonical(start, value.$len_fn().into())?;
+                    Ok(value)
+                },
+            }
+        }
+    };
 }
 
-impl<'de, R, P> Deserializer<R, P>
+impl<R, P> Deserializer<R, P>
     where R: TailReadBytes,
           P: SerializerParams,
 {
     #[must_use]
+    #[cfg_attr(not(feature="std"), allow(clippy::unit_arg))]
     pub fn new(reader: R, params: P) -> Self {
-        Deserializer { reader, params }
+        let reader = InvertingReader::with_mode(Counting::new(reader), DefaultScratchBuffer::default(), false);
+        Deserializer { reader, params, state: State::default(), error_context: None }
+    }
+}
+
+impl<R, P, B> Deserializer<R, P, B>
+    where R: TailReadBytes,
+          P: SerializerParams,
+          B: ScratchBuffer,
+{
+    pub fn into_reader(self) -> R { self.reader.into_inner().inner }
+
+    /// Location of the error, after deserialization has failed
+    ///
+    /// Context is kept until the deserializer is dropped, so it may describe one of previous
+    /// errors if the deserializer is reused after failure.
+    ///
+    /// ```
+    /// # use ordcode::{ Deserializer, DeBytesReader, params, ser_to_vec_ordered, Order };
+    /// # use serde::Deserialize;
+    /// #[derive(serde_derive::Serialize, serde_derive::Deserialize)]
+    /// struct Foo { id: u8, items: Vec<Item> }
+    /// #[derive(serde_derive::Serialize, serde_derive::Deserialize)]
+    /// struct Item { name: String }
+    ///
+    /// let foo = Foo { id: 1, items: vec![Item { name: "abc".to_string() }] };
+    /// let mut buf = ser_to_vec_ordered(&foo, Order::Ascending).unwrap();
+    /// buf[2] = 0xFF; // invalid UTF-8
+    /// let mut deser = Deserializer::new(DeBytesReader::new(&buf), params::AscendingOrder);
+    /// assert!(Foo::deserialize(&mut deser).is_err());
+    /// let context = deser.error_context().unwrap();
+    /// assert_eq!(context.path(), "Foo.items[0].name");
+    /// // `id` and `name` bytes were read from the head, lengths of `items` and `name` from the tail
+    /// assert_eq!((context.head_offset(), context.tail_offset()), (4, 2));
+    /// ```
+    #[must_use]
+    pub fn error_context(&self) -> Option<&ErrorContext> {
+        self.error_context.as_ref()
+    }
+
+    // Record location of error while it propagates, `segment` is prepended to the path
+    #[cold]
+    fn error_at(&mut self, err: Error, segment: PathSegment) -> Error {
+        let (head, tail) = (self.reader.get_ref().head, self.reader.get_ref().tail);
+        self.error_context.get_or_insert_with(|| ErrorContext::new(head, tail)).push(segment);
+        err
+    }
+
+    /// Accept only canonical encoding, so that each value has exactly one valid serialized form
+    ///
+    /// In strict mode, varints (sequence lengths, enum discriminants, integers with
+    /// [`IntEncoding::Compact`](crate::params::IntEncoding::Compact)) must have the shortest encoding,
+    /// and `bool` values must be encoded as 0 or 1 (otherwise any non-zero byte is accepted as `true`).
+    /// Option and sequence item markers, escape sequences and type tags are always checked.
+    ///
+    /// Deserializer does not check that input is fully consumed, call
+    /// [`ReadBytes::is_complete()`](crate::buf::ReadBytes::is_complete) on reader after deserialization,
+    /// or use [`de_from_bytes_strict()`](crate::de_from_bytes_strict) which does both.
+    #[must_use]
+    pub fn with_strict_decoding(mut self) -> Self {
+        self.state.strict = true;
+        self
+    }
+
+    /// Enforce resource limits, for deserialization of untrusted input
+    ///
+    /// See [`Limits`] for details.
+    #[must_use]
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.state.limits = Some(limits);
+        self
+    }
+
+    /// Use `buffer` for byte strings which can't be passed to `serde` directly from input
+    ///
+    /// With [`SeqEncoding::Escaped`](crate::params::SeqEncoding::Escaped), strings and byte
+    /// strings which contain escaped bytes are unescaped into a buffer. With `std` feature,
+    /// deserializer uses internal growable buffer. Without it, pass a buffer big enough for
+    /// the longest of such strings, otherwise they fail with [`Error::BufferOverflow`].
+    ///
+    /// ```
+    /// # use ordcode::{ Deserializer, DeBytesReader, params };
+    /// # use serde::Deserialize;
+    /// let buf = [b'a', 0, 0xFF, b'b', 0, 1];
+    /// let mut tmp = [0_u8; 16];
+    /// let mut deser = Deserializer::new(DeBytesReader::new(&buf), params::EscapedAscending)
+    ///     .with_buffer(&mut tmp[..]);
+    /// assert_eq!(String::deserialize(&mut deser).unwrap(), "a\0b");
+    /// ```
+    pub fn with_buffer<B2>(self, buffer: B2) -> Deserializer<R, P, B2>
+        where B2: ScratchBuffer,
+    {
+        let Self { reader, params, state, error_context } = self;
+        let inverted = reader.is_inverted();
+        let reader = InvertingReader::with_mode(reader.into_parts().0, buffer, inverted);
+        Deserializer { reader, params, state, error_context }
+    }
+
+    // Check length of sequence, map or byte string read from input against limits
+    fn check_len(&mut self, len: usize) -> Result {
+        if let Some(limits) = &self.state.limits {
+            if len > limits.seq_len || self.reader.remaining_len().is_some_and(|remaining| len > remaining) {
+                return Err(Error::LengthLimitExceeded);
+            }
+            self.state.allocated = self.state.allocated.saturating_add(len);
+            if self.state.allocated > limits.alloc {
+                return Err(Error::AllocationLimitExceeded);
+            }
+        }
+        Ok(())
+    }
+    // Deserialize nested value, checking depth limit
+    fn nested<F, T>(&mut self, f: F) -> Result<T>
+        where F: FnOnce(&mut Self) -> Result<T>,
+    {
+        if let Some(limits) = &self.state.limits {
+            if self.state.depth >= limits.depth {
+                return Err(Error::DepthLimitExceeded);
+            }
+        }
+        self.state.depth += 1;
+        let res = f(self);
+        self.state.depth -= 1;
+        res
+    }
+
+    // Read type tag of self-describing format
+    fn read_tag(&mut self) -> Result<TypeTag> {
+        TypeTag::from_u8(<u8>::from_reader(&mut self.reader, self.params)?).ok_or(Error::InvalidTagEncoding)
+    }
+    // Read type tag of self-describing format and check that it is equal to `tag`
+    fn expect_tag(&mut self, tag: TypeTag) -> Result {
+        if self.read_tag()? == tag { Ok(()) } else { Err(Error::InvalidTagEncoding) }
     }
-    pub fn into_reader(self) -> R { self.reader }
+    read_int_fn!(read_u32, u32, ord_varu_from_reader, ord_varu_encoded_len);
+    read_int_fn!(read_u64, u64, ord_varu_from_reader, ord_varu_encoded_len);
+    read_int_fn!(read_i32, i32, ord_vari_from_reader, ord_vari_encoded_len);
+    read_int_fn!(read_i64, i64, ord_vari_from_reader, ord_vari_encoded_len);
 
-    fn visit_bytebuf<V, F>(&mut self, f: F) -> Result<V::Value>
+    fn read_bool(&mut self) -> Result<bool> {
+        match <u8>::from_reader(&mut self.reader, self.params)? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ if self.state.strict => Err(Error::InvalidBoolEncoding),
+            _ => Ok(true),
+        }
+    }
+    // Read sequence length or enum discriminant
+    fn read_len<E>(&mut self) -> Result<E::Value>
+        where E: LengthEncoder,
+              E::Value: Copy,
+    {
+        let start = self.position();
+        let value = E::read(&mut self.reader)?;
+        self.check_canonical(start, E::calc_size(value))?;
+        Ok(value)
+    }
+    // Total number of bytes read from head and tail
+    fn position(&self) -> usize {
+        self.reader.get_ref().head + self.reader.get_ref().tail
+    }
+    // In strict mode, check that varint read since `start` position has the shortest encoding
+    fn check_canonical(&self, start: usize, encoded_len: usize) -> Result {
+        if self.state.strict && self.position() - start != encoded_len {
+            Err(Error::InvalidVarintEncoding)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl<'de, R, P, B> Deserializer<R, P, B>
+    where R: TailReadBytes + ReadBytesBorrow<'de>,
+          P: SerializerParams,
+          B: ScratchBuffer,
+{
+    /// Check that input contains a valid serialized value of type `T`
+    ///
+    /// Value is built and dropped, so it allocates as deserialization of `T` does.
+    /// See [`validate()`](crate::validate) for details.
+    pub fn validate<T>(&mut self) -> Result
+        where T: serde::de::Deserialize<'de>,
+    {
+        T::deserialize(self).map(drop)
+    }
+    // Read byte string and pass it to visitor
+    fn visit_bytebuf<V>(&mut self, visitor: V) -> Result<V::Value>
+        where V: VisitBytes<'de>,
+    {
+        match P::SEQ_ENCODING {
+            SeqEncoding::Length => {
+                let len = self.read_len::<P::SeqLenEncoder>()?;
+                self.check_len(len)?;
+                self.visit_raw_bytes(len, visitor)
+            },
+            SeqEncoding::Escaped => {
+                let (len, n_escaped) = self.scan_escaped()?;
+                self.check_len(len)?;
+                if n_escaped == 0 {
+                    let value = self.visit_raw_bytes(len, visitor)?;
+                    self.reader.advance(2); // terminator
+                    Ok(value)
+                } else {
+                    self.visit_unescaped_bytes(len - n_escaped, len + 2, visitor)
+                }
+            },
+        }
+    }
+    fn visit_raw_bytes<V>(&mut self, len: usize, visitor: V) -> Result<V::Value>
+        where V: VisitBytes<'de>,
+    {
+        if let Some(buf) = self.reader.read_borrowed(len)? {
+            visitor.visit_borrowed(buf)
+        } else {
+            self.reader.read(len, |buf| visitor.visit(buf))
+        }
+    }
+    // Scan escaped byte string in the input as is, returns its escaped length and number of escapes
+    fn scan_escaped(&mut self) -> Result<(usize, usize)> {
+        // input reader may invert bytes too
+        let inverted = self.reader.is_buffer_inverted();
+        let reader = self.reader.parts().0;
+        loop {
+            let buf = reader.remaining_buffer();
+            let res = if inverted {
+                bytes_esc::scan_escaped_ordered(buf, DescendingOrder)
+            } else {
+                bytes_esc::scan_escaped_ordered(buf, AscendingOrder)
+            };
+            // end of byte string may be beyond the buffer of stream reader
+            match res {
+                Err(Error::PrematureEndOfInput) if reader.fill_more()? => (),
+                res => return res,
+            }
+        }
+    }
+    // Unescape byte string of `len` bytes encoded in `escaped_len` bytes into buffer, and pass it to visitor
+    fn visit_unescaped_bytes<V>(&mut self, len: usize, escaped_len: usize, visitor: V) -> Result<V::Value>
+        where V: VisitBytes<'de>,
+    {
+        let inverted = self.reader.is_inverted();
+        let (reader, buffer) = self.reader.parts();
+        let buf = buffer.get(len).ok_or(Error::BufferOverflow)?;
+        reader.read(escaped_len, |src| {
+            let (src, out) = (crate::DeBytesReader::new(src), crate::DeBytesWriter::new(&mut *buf));
+            if inverted {
+                bytes_esc::deserialize_bytes_ordered_to_writer(src, out, DescendingOrder)
+            } else {
+                bytes_esc::deserialize_bytes_ordered_to_writer(src, out, AscendingOrder)
+            }
+        })?;
+        visitor.visit(buf)
+    }
+    // Visit struct fields, which are encoded as tuple
+    fn visit_struct<V>(&mut self, fields: &'static [&'static str], visitor: V) -> Result<V::Value>
         where V: serde::de::Visitor<'de>,
-              F: FnOnce(&[u8]) -> Result<V::Value>
     {
-        let len = P::SeqLenEncoder::read(&mut self.reader)?;
-        self.reader.read(len, f)
+        visitor.visit_seq(SeqAccess { fields, ..SeqAccess::new(self, Some(fields.len())) })
+    }
+    // Check if the end of input is reached, stream readers are asked for more input
+    fn at_end(&mut self) -> Result<bool> {
+        Ok(self.reader.remaining_buffer().is_empty() && !self.reader.fill_more()?)
+    }
+    fn read_seq_len(&mut self) -> Result<Option<usize>> {
+        match P::SEQ_ENCODING {
+            SeqEncoding::Length => {
+                let len = self.read_len::<P::SeqLenEncoder>()?;
+                self.check_len(len)?;
+                Ok(Some(len))
+            },
+            SeqEncoding::Escaped => Ok(None),
+        }
+    }
+    // Check if there is next item in sequence with `len` remaining items, or with item markers
+    // if `len` is `None`. Returns `false` at the end of sequence.
+    fn next_seq_item(&mut self, len: &mut Option<usize>) -> Result<bool> {
+        match len {
+            Some(0) => Ok(false),
+            Some(n) => {
+                *n -= 1;
+                Ok(true)
+            },
+            None => match <u8>::from_reader(&mut self.reader, self.params)? {
+                SEQ_ITEM_MARKER => Ok(true),
+                SEQ_END_MARKER => {
+                    *len = Some(0);
+                    Ok(false)
+                },
+                _ => Err(Error::InvalidTagEncoding),
+            },
+        }
     }
 }
 
-impl<W> FormatVersion<AscendingOrder> for Deserializer<W, AscendingOrder>  {
+// Adapter for passing byte string to `serde` visitor: borrowed from input or transient
+trait VisitBytes<'de>: Sized {
+    type Value;
+    fn visit_borrowed(self, v: &'de [u8]) -> Result<Self::Value> { self.visit(v) }
+    fn visit(self, v: &[u8]) -> Result<Self::Value>;
+}
+
+struct StrVisitor<V>(V);
+struct StringVisitor<V>(V);
+struct BytesVisitor<V>(V);
+
+#[inline]
+fn to_str(v: &[u8]) -> Result<&str> {
+    core::str::from_utf8(v).map_err(|_| Error::InvalidUtf8Encoding)
+}
+
+#[cfg(feature="std")]
+#[inline]
+fn to_string(v: Vec<u8>) -> Result<String> {
+    String::from_utf8(v).map_err(|_| Error::InvalidUtf8Encoding)
+}
+
+impl<'de, V> VisitBytes<'de> for StrVisitor<V> where V: serde::de::Visitor<'de> {
+    type Value = V::Value;
+    fn visit_borrowed(self, v: &'de [u8]) -> Result<Self::Value> { self.0.visit_borrowed_str(to_str(v)?) }
+    fn visit(self, v: &[u8]) -> Result<Self::Value> { self.0.visit_str(to_str(v)?) }
+}
+
+impl<'de, V> VisitBytes<'de> for StringVisitor<V> where V: serde::de::Visitor<'de> {
+    type Value = V::Value;
+    #[cfg(feature="std")]
+    fn visit(self, v: &[u8]) -> Result<Self::Value> { self.0.visit_string(to_string(Vec::from(v))?) }
+    #[cfg(not(feature="std"))]
+    fn visit(self, v: &[u8]) -> Result<Self::Value> { self.0.visit_str(to_str(v)?) }
+}
+
+impl<'de, V> VisitBytes<'de> for BytesVisitor<V> where V: serde::de::Visitor<'de> {
+    type Value = V::Value;
+    fn visit_borrowed(self, v: &'de [u8]) -> Result<Self::Value> { self.0.visit_borrowed_bytes(v) }
+    fn visit(self, v: &[u8]) -> Result<Self::Value> { self.0.visit_bytes(v) }
+}
+
+impl<W, B> FormatVersion<AscendingOrder> for Deserializer<W, AscendingOrder, B>  {
+    const VERSION: u32 = 2;
+}
+
+impl<W, B> FormatVersion<PortableBinary> for Deserializer<W, PortableBinary, B>  {
     const VERSION: u32 = 1;
 }
 
-impl<W> FormatVersion<PortableBinary> for Deserializer<W, PortableBinary>  {
+impl<W, B> FormatVersion<NativeBinary> for Deserializer<W, NativeBinary, B>  {
     const VERSION: u32 = 1;
 }
 
-impl<W> FormatVersion<NativeBinary> for Deserializer<W, NativeBinary>  {
+impl<W, B> FormatVersion<EscapedAscending> for Deserializer<W, EscapedAscending, B>  {
     const VERSION: u32 = 1;
 }
 
+impl<W, B> FormatVersion<CompactAscending> for Deserializer<W, CompactAscending, B>  {
+    const VERSION: u32 = 1;
+}
+
+impl<W, B> FormatVersion<SelfDescribing> for Deserializer<W, SelfDescribing, B>  {
+    const VERSION: u32 = 1;
+}
+
+// In self-describing format, type of the value is defined by type tag
+macro_rules! forward_self_describing {
+    ($self:ident, $visitor:ident) => {
+        if P::SELF_DESCRIBING {
+            return $self.deserialize_any($visitor);
+        }
+    };
+}
+
+macro_rules! impl_ints {
+    ($dser_method:ident, $visitor_method:ident, $read_fn:ident) => {
+        #[inline]
+        fn $dser_method<V>(self, visitor: V) -> Result<V::Value>
+            where V: serde::de::Visitor<'de>,
+        {
+            forward_self_describing!(self, visitor);
+            visitor.$visitor_method(self.$read_fn()?)
+        }
+    };
+}
+
 macro_rules! impl_nums {
     ($ty:ty, $dser_method:ident, $visitor_method:ident) => {
         #[inline]
         fn $dser_method<V>(self, visitor: V) -> Result<V::Value>
             where V: serde::de::Visitor<'de>,
         {
+            forward_self_describing!(self, visitor);
             let value = <$ty>::from_reader(&mut self.reader, self.params)?;
             visitor.$visitor_method(value)
         }
     }
 }
 
-impl<'a, 'de: 'a, R, P> serde::Deserializer<'de> for &'a mut Deserializer<R, P>
+impl<'a, 'de: 'a, R, P, B> serde::Deserializer<'de> for &'a mut Deserializer<R, P, B>
     where
-        R: TailReadBytes,
+        R: TailReadBytes + ReadBytesBorrow<'de>,
         P: SerializerParams,
+        B: ScratchBuffer,
 {
     type Error = Error;
 
-    fn deserialize_any<V>(self, _visitor: V) -> Result<V::Value>
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
         where
             V: serde::de::Visitor<'de>,
     {
-        Err(Error::DeserializeAnyNotSupported)
+        if !P::SELF_DESCRIBING {
+            return Err(Error::DeserializeAnyNotSupported);
+        }
+        match self.read_tag()? {
+            TypeTag::Unit => visitor.visit_unit(),
+            TypeTag::Bool => visitor.visit_bool(self.read_bool()?),
+            TypeTag::U8 => visitor.visit_u8(<u8>::from_reader(&mut self.reader, self.params)?),
+            TypeTag::U16 => visitor.visit_u16(<u16>::from_reader(&mut self.reader, self.params)?),
+            TypeTag::U32 => visitor.visit_u32(self.read_u32()?),
+            TypeTag::U64 => visitor.visit_u64(self.read_u64()?),
+            TypeTag::U128 => visitor.visit_u128(<u128>::from_reader(&mut self.reader, self.params)?),
+            TypeTag::I8 => visitor.visit_i8(<i8>::from_reader(&mut self.reader, self.params)?),
+            TypeTag::I16 => visitor.visit_i16(<i16>::from_reader(&mut self.reader, self.params)?),
+            TypeTag::I32 => visitor.visit_i32(self.read_i32()?),
+            TypeTag::I64 => visitor.visit_i64(self.read_i64()?),
+            TypeTag::I128 => visitor.visit_i128(<i128>::from_reader(&mut self.reader, self.params)?),
+            TypeTag::F32 => visitor.visit_f32(<f32>::from_reader(&mut self.reader, self.params)?),
+            TypeTag::F64 => visitor.visit_f64(<f64>::from_reader(&mut self.reader, self.params)?),
+            TypeTag::Char => visitor.visit_char(<char>::from_reader(&mut self.reader, self.params)?),
+            TypeTag::Str => self.visit_bytebuf(StrVisitor(visitor)),
+            TypeTag::Bytes => self.visit_bytebuf(BytesVisitor(visitor)),
+            TypeTag::None => visitor.visit_none(),
+            TypeTag::Some => self.nested(|de| visitor.visit_some(de)),
+            TypeTag::Seq => {
+                let len = self.read_seq_len()?;
+                self.nested(|de| visitor.visit_seq(SeqAccess::new(de, len)))
+            },
+            TypeTag::Map => {
+                let len = self.read_seq_len()?;
+                self.nested(|de| visitor.visit_map(MapAccess { deserializer: de, len, index: 0 }))
+            },
+            // enum value is visited as a map with single entry: variant name => variant value
+            TypeTag::Enum => self.nested(|de| visitor.visit_map(VariantMapAccess { deserializer: de, has_key: true })),
+        }
     }
     impl_nums!(u8,  deserialize_u8,  visit_u8);
     impl_nums!(u16, deserialize_u16, visit_u16);
-    impl_nums!(u32, deserialize_u32, visit_u32);
-    impl_nums!(u64, deserialize_u64, visit_u64);
+    impl_ints!(deserialize_u32, visit_u32, read_u32);
+    impl_ints!(deserialize_u64, visit_u64, read_u64);
     impl_nums!(i8,  deserialize_i8,  visit_i8);
     impl_nums!(i16, deserialize_i16, visit_i16);
-    impl_nums!(i32, deserialize_i32, visit_i32);
-    impl_nums!(i64, deserialize_i64, visit_i64);
+    impl_ints!(deserialize_i32, visit_i32, read_i32);
+    impl_ints!(deserialize_i64, visit_i64, read_i64);
     impl_nums!(f32, deserialize_f32, visit_f32);
     impl_nums!(f64, deserialize_f64, visit_f64);
-    impl_nums!(bool, deserialize_bool, visit_bool);
-
-    serde_if_integer128! {
-        impl_nums!(u128, deserialize_u128, visit_u128);
-        impl_nums!(i128, deserialize_i128, visit_i128);
+    fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value>
+        where V: serde::de::Visitor<'de>,
+    {
+        forward_self_describing!(self, visitor);
+        visitor.visit_bool(self.read_bool()?)
     }
+
+    impl_nums!(u128, deserialize_u128, visit_u128);
+    impl_nums!(i128, deserialize_i128, visit_i128);
     impl_nums!(char, deserialize_char, visit_char);
 
     fn deserialize_str<V>(self, visitor: V) -> Result<V::Value>
         where
             V: serde::de::Visitor<'de>,
     {
-        self.visit_bytebuf::<V,_>(|buf| {
-            visitor.visit_str(core::str::from_utf8(buf).map_err(|_| Error::InvalidUtf8Encoding)?)
-        })
+        forward_self_describing!(self, visitor);
+        self.visit_bytebuf(StrVisitor(visitor))
     }
     fn deserialize_string<V>(self, visitor: V) -> Result<V::Value>
         where
             V: serde::de::Visitor<'de>,
     {
-        #[cfg(not(feature="std"))] return self.deserialize_str(visitor);
-        #[cfg(feature="std")] return self.visit_bytebuf::<V,_>(|buf| {
-            visitor.visit_string(String::from_utf8(Vec::from(buf)).
-                map_err(|_| Error::InvalidUtf8Encoding)?)
-        });
+        forward_self_describing!(self, visitor);
+        self.visit_bytebuf(StringVisitor(visitor))
     }
     fn deserialize_bytes<V>(self, visitor: V) -> Result<V::Value>
         where
             V: serde::de::Visitor<'de>,
     {
-        self.visit_bytebuf::<V,_>(|buf| {
-            visitor.visit_bytes(buf)
-        })
+        forward_self_describing!(self, visitor);
+        self.visit_bytebuf(BytesVisitor(visitor))
     }
     fn deserialize_byte_buf<V>(self, visitor: V) -> Result<V::Value>
         where
@@ -121,10 +611,11 @@ impl<'a, 'de: 'a, R, P> serde::Deserializer<'de> for &'a mut Deserializer<R, P>
         where
             V: serde::de::Visitor<'de>,
     {
+        forward_self_describing!(self, visitor);
         let value = <u8>::from_reader(&mut self.reader, self.params)?;
         match value {
             0 => visitor.visit_none(),
-            1 => visitor.visit_some(&mut *self),
+            1 => self.nested(|de| visitor.visit_some(de)),
             _ => Err(Error::InvalidTagEncoding),
         }
     }
@@ -132,32 +623,44 @@ impl<'a, 'de: 'a, R, P> serde::Deserializer<'de> for &'a mut Deserializer<R, P>
         where
             V: serde::de::Visitor<'de>,
     {
+        forward_self_describing!(self, visitor);
         visitor.visit_unit()
     }
     fn deserialize_unit_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value>
         where
             V: serde::de::Visitor<'de>,
     {
+        forward_self_describing!(self, visitor);
         visitor.visit_unit()
     }
-    fn deserialize_newtype_struct<V>(self, _name: &str, visitor: V) -> Result<V::Value>
+    fn deserialize_newtype_struct<V>(self, name: &str, visitor: V) -> Result<V::Value>
         where
             V: serde::de::Visitor<'de>,
     {
-        visitor.visit_newtype_struct(self)
+        if name == DESC_NEWTYPE_NAME && !P::SELF_DESCRIBING {
+            let inverted = self.reader.is_inverted();
+            self.reader.set_inverted(!inverted);
+            let value = self.nested(|de| visitor.visit_newtype_struct(de));
+            self.reader.set_inverted(inverted);
+            value
+        } else {
+            self.nested(|de| visitor.visit_newtype_struct(de))
+        }
     }
     fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value>
         where
             V: serde::de::Visitor<'de>,
     {
-        let len = P::SeqLenEncoder::read(&mut self.reader)?;
-        self.deserialize_tuple(len, visitor)
+        forward_self_describing!(self, visitor);
+        let len = self.read_seq_len()?;
+        self.nested(|de| visitor.visit_seq(SeqAccess::new(de, len)))
     }
     fn deserialize_tuple<V>(self, len: usize, visitor: V) -> Result<V::Value>
         where
             V: serde::de::Visitor<'de>,
     {
-        visitor.visit_seq(SeqAccess { deserializer: self, len })
+        forward_self_describing!(self, visitor);
+        self.nested(|de| visitor.visit_seq(SeqAccess::new(de, Some(len))))
     }
 
     fn deserialize_tuple_struct<V>(
@@ -174,19 +677,21 @@ impl<'a, 'de: 'a, R, P> serde::Deserializer<'de> for &'a mut Deserializer<R, P>
     fn deserialize_map<V>(self, visitor: V) -> Result<V::Value>
         where V: serde::de::Visitor<'de>,
     {
-        let len = P::SeqLenEncoder::read(&mut self.reader)?;
-        visitor.visit_map(MapAccess { deserializer: self, len })
+        forward_self_describing!(self, visitor);
+        let len = self.read_seq_len()?;
+        self.nested(|de| visitor.visit_map(MapAccess { deserializer: de, len, index: 0 }))
     }
     fn deserialize_struct<V>(
         self,
-        _name: &str,
+        name: &'static str,
         fields: &'static [&'static str],
         visitor: V,
     ) -> Result<V::Value>
         where
             V: serde::de::Visitor<'de>,
     {
-        self.deserialize_tuple(fields.len(), visitor)
+        forward_self_describing!(self, visitor);
+        self.nested(|de| de.visit_struct(fields, visitor)).map_err(|e| self.error_at(e, PathSegment::Type(name)))
     }
     fn deserialize_enum<V>(
         self,
@@ -197,83 +702,140 @@ impl<'a, 'de: 'a, R, P> serde::Deserializer<'de> for &'a mut Deserializer<R, P>
         where
             V: serde::de::Visitor<'de>,
     {
-        impl<'a, 'de: 'a, R, P> serde::de::EnumAccess<'de> for &'a mut Deserializer<R, P>
-            where
-                R: TailReadBytes,
-                P: SerializerParams,
-        {
-            type Error = Error;
-            type Variant = Self;
-
-            fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self::Variant)>
-                where
-                    V: serde::de::DeserializeSeed<'de>,
-            {
-                let idx = P::DiscriminantEncoder::read(&mut self.reader)?;
-                let val: Result<_> = seed.deserialize(idx.into_deserializer());
-                Ok((val?, self))
-            }
+        if P::SELF_DESCRIBING {
+            self.expect_tag(TypeTag::Enum)?;
         }
-        visitor.visit_enum(self)
+        self.nested(|de| visitor.visit_enum(de))
     }
-    fn deserialize_identifier<V>(self, _visitor: V) -> Result<V::Value>
+    fn deserialize_identifier<V>(self, visitor: V) -> Result<V::Value>
         where
             V: serde::de::Visitor<'de>,
     {
-        Err(Error::DeserializeIdentifierNotSupported)
+        if P::SELF_DESCRIBING {
+            self.deserialize_any(visitor)
+        } else {
+            Err(Error::DeserializeIdentifierNotSupported)
+        }
     }
 
-    fn deserialize_ignored_any<V>(self, _visitor: V) -> Result<V::Value>
+    fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value>
         where
             V: serde::de::Visitor<'de>,
     {
-        Err(Error::DeserializeIgnoredAny)
+        if P::SELF_DESCRIBING {
+            self.deserialize_any(visitor)
+        } else {
+            Err(Error::DeserializeIgnoredAny)
+        }
     }
     fn is_human_readable(&self) -> bool {
         false
     }
 }
 
-struct SeqAccess<'a, R: TailReadBytes, P: SerializerParams> {
-    deserializer: &'a mut Deserializer<R, P>,
-    len: usize,
+struct SeqAccess<'a, R: TailReadBytes, P: SerializerParams, B> {
+    deserializer: &'a mut Deserializer<R, P, B>,
+    len: Option<usize>,
+    // struct field names, for error context
+    fields: &'static [&'static str],
+    index: usize,
+}
+
+impl<'a, R: TailReadBytes, P: SerializerParams, B: ScratchBuffer> SeqAccess<'a, R, P, B> {
+    fn new(deserializer: &'a mut Deserializer<R, P, B>, len: Option<usize>) -> Self {
+        Self { deserializer, len, fields: &[], index: 0 }
+    }
+    fn next_item<'de, T>(&mut self, seed: T) -> Result<Option<T::Value>>
+        where R: ReadBytesBorrow<'de>,
+              T: serde::de::DeserializeSeed<'de>,
+    {
+        // with versioned structs, fields appended to struct are absent in old data
+        if P::VERSIONED_STRUCTS && !self.fields.is_empty() && self.deserializer.at_end()? {
+            return Ok(None);
+        }
+        if self.deserializer.next_seq_item(&mut self.len)? {
+            let value = seed.deserialize(&mut *self.deserializer)?;
+            Ok(Some(value))
+        } else {
+            Ok(None)
+        }
+    }
 }
 
-impl<'a, 'de: 'a, R: TailReadBytes, P: SerializerParams> serde::de::SeqAccess<'de> for SeqAccess<'a, R, P>
+impl<'a, 'de: 'a, R: TailReadBytes + ReadBytesBorrow<'de>, P: SerializerParams, B: ScratchBuffer> serde::de::SeqAccess<'de> for SeqAccess<'a, R, P, B>
 {
     type Error = Error;
     fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>>
         where
             T: serde::de::DeserializeSeed<'de>,
     {
-        if self.len > 0 {
-            self.len -= 1;
-            let value = seed.deserialize(&mut *self.deserializer)?;
-            Ok(Some(value))
+        let value = self.next_item(seed).map_err(|e| {
+            let segment = match self.fields.get(self.index) {
+                Some(name) => PathSegment::Field(name),
+                None => PathSegment::Index(self.index),
+            };
+            self.deserializer.error_at(e, segment)
+        })?;
+        self.index += 1;
+        Ok(value)
+    }
+    fn size_hint(&self) -> Option<usize> {
+        self.len
+    }
+}
+
+struct MapAccess<'a, R: TailReadBytes, P: SerializerParams, B> {
+    deserializer: &'a mut Deserializer<R, P, B>,
+    len: Option<usize>,
+    index: usize,
+}
+impl<'a, 'de: 'a, R: TailReadBytes + ReadBytesBorrow<'de>, P: SerializerParams, B: ScratchBuffer> serde::de::MapAccess<'de> for MapAccess<'a, R, P, B>
+{
+    type Error = Error;
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
+        where
+            K: serde::de::DeserializeSeed<'de>,
+    {
+        let index = self.index;
+        let deserializer = &mut *self.deserializer;
+        if deserializer.next_seq_item(&mut self.len).map_err(|e| deserializer.error_at(e, PathSegment::Index(index)))? {
+            let key = seed.deserialize(&mut *deserializer).map_err(|e| deserializer.error_at(e, PathSegment::Index(index)))?;
+            Ok(Some(key))
         } else {
             Ok(None)
         }
     }
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
+        where
+            V: serde::de::DeserializeSeed<'de>,
+    {
+        let index = self.index;
+        let value = seed.deserialize(&mut *self.deserializer)
+            .map_err(|e| self.deserializer.error_at(e, PathSegment::Index(index)))?;
+        self.index += 1;
+        Ok(value)
+    }
     fn size_hint(&self) -> Option<usize> {
-        Some(self.len)
+        self.len
     }
 }
 
-struct MapAccess<'a, R: TailReadBytes, P: SerializerParams> {
-    deserializer: &'a mut Deserializer<R, P>,
-    len: usize,
+// Enum value of self-describing format, presented to `deserialize_any()` visitor
+// as a map with single entry
+struct VariantMapAccess<'a, R: TailReadBytes, P: SerializerParams, B> {
+    deserializer: &'a mut Deserializer<R, P, B>,
+    has_key: bool,
 }
-impl<'a, 'de: 'a, R: TailReadBytes, P: SerializerParams> serde::de::MapAccess<'de> for MapAccess<'a, R, P>
+impl<'a, 'de: 'a, R: TailReadBytes + ReadBytesBorrow<'de>, P: SerializerParams, B: ScratchBuffer> serde::de::MapAccess<'de> for VariantMapAccess<'a, R, P, B>
 {
     type Error = Error;
     fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
         where
             K: serde::de::DeserializeSeed<'de>,
     {
-        if self.len > 0 {
-            self.len -= 1;
-            let key = seed.deserialize(&mut *self.deserializer)?;
-            Ok(Some(key))
+        if self.has_key {
+            self.has_key = false;
+            seed.deserialize(&mut *self.deserializer).map(Some)
         } else {
             Ok(None)
         }
@@ -282,22 +844,49 @@ impl<'a, 'de: 'a, R: TailReadBytes, P: SerializerParams> serde::de::MapAccess<'d
         where
             V: serde::de::DeserializeSeed<'de>,
     {
-        let value = seed.deserialize(&mut *self.deserializer)?;
-        Ok(value)
+        seed.deserialize(&mut *self.deserializer)
     }
     fn size_hint(&self) -> Option<usize> {
-        Some(self.len)
+        Some(usize::from(self.has_key))
     }
 }
 
-impl<'a, 'de: 'a, R, P> serde::de::VariantAccess<'de> for &'a mut Deserializer<R, P>
-    where R: TailReadBytes,
+impl<'a, 'de: 'a, R, P, B> serde::de::EnumAccess<'de> for &'a mut Deserializer<R, P, B>
+    where
+        R: TailReadBytes + ReadBytesBorrow<'de>,
+        P: SerializerParams,
+        B: ScratchBuffer,
+{
+    type Error = Error;
+    type Variant = Self;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self::Variant)>
+        where
+            V: serde::de::DeserializeSeed<'de>,
+    {
+        if P::SELF_DESCRIBING {
+            let val = seed.deserialize(&mut *self)?;
+            return Ok((val, self));
+        }
+        let idx = self.read_len::<P::DiscriminantEncoder>()?;
+        let val: Result<_> = seed.deserialize(idx.into_deserializer());
+        Ok((val?, self))
+    }
+}
+
+impl<'a, 'de: 'a, R, P, B> serde::de::VariantAccess<'de> for &'a mut Deserializer<R, P, B>
+    where R: TailReadBytes + ReadBytesBorrow<'de>,
           P: SerializerParams,
+          B: ScratchBuffer,
 {
     type Error = Error;
 
     fn unit_variant(self) -> Result {
-        Ok(())
+        if P::SELF_DESCRIBING {
+            self.expect_tag(TypeTag::Unit)
+        } else {
+            Ok(())
+        }
     }
     fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value>
         where
@@ -317,7 +906,11 @@ impl<'a, 'de: 'a, R, P> serde::de::VariantAccess<'de> for &'a mut Deserializer<R
         where
             V: serde::de::Visitor<'de>,
     {
-        serde::Deserializer::deserialize_tuple(self, fields.len(), visitor)
+        if P::SELF_DESCRIBING {
+            serde::Deserializer::deserialize_any(self, visitor)
+        } else {
+            self.visit_struct(fields, visitor)
+        }
     }
 }
 
@@ -1,7 +1,11 @@
-use crate::{Error, FormatVersion, buf::TailWriteBytes, Result,
-            params::{SerializerParams, LengthEncoder }};
-use crate::params::{AscendingOrder, PortableBinary, NativeBinary};
+use crate::{Error, ErrorContext, FormatVersion, buf::{WriteBytes, TailWriteBytes, InvertingWriter, Counting, DeferringTail}, Result, bytes_esc,
+            params::{SerializerParams, LengthEncoder, SeqEncoding, IntEncoding, TypeTag, SEQ_ITEM_MARKER, SEQ_END_MARKER },
+            varint::{OrdVarUInt, OrdVarInt}};
+use crate::params::{AscendingOrder, PortableBinary, NativeBinary, EscapedAscending, CompactAscending, SelfDescribing};
 use crate::primitives::SerializableValue;
+use crate::desc::{ DESC_NEWTYPE_NAME, RUNTIME_VARIANT_NAME };
+use crate::errors::PathSegment;
+#[cfg(feature="std")] use crate::{ LayoutCalc, buf::DeVecWriter };
 use serde::{ser, Serialize};
 
 /// `serde` serializer for binary data format which may preserve lexicographic ordering of values
@@ -21,9 +25,20 @@ use serde::{ser, Serialize};
 /// serialized data. To know required buffer size in advance, please use `calc_size` with the same
 /// `SerializerParams`. Size calculation is cheap, for fixed-size structures it folds into
 /// compile-time constant.
+///
+/// The exception is sequences and maps of unknown length (e.g. from `collect_seq()` over
+/// filtered iterator, or structs with `#[serde(flatten)]` fields), which need `std` feature,
+/// otherwise [`Error::SerializeSequenceMustHaveLength`] is returned. When parameters write
+/// sequence lengths to the tail of the buffer (like [`AscendingOrder`]), only lengths of nested
+/// sequences are held back in memory until the item count is known; with other parameters,
+/// items are serialized into a temporary buffer first.
+///
+/// When serialization fails, location of the error is available with [`Serializer::error_context()`].
 pub struct Serializer<W, P> {
-    writer: W,
+    // inverts bytes inside `Desc` values
+    writer: InvertingWriter<Counting<DeferringTail<W>>>,
     params: P,
+    error_context: Option<ErrorContext>,
 }
 
 impl<W, P> Serializer<W, P>
@@ -31,21 +46,134 @@ impl<W, P> Serializer<W, P>
           P: SerializerParams,
 {
     pub fn new(writer: W, params: P) -> Self {
-        Self { writer, params }
+        Self { writer: InvertingWriter::with_mode(Counting::new(DeferringTail::new(writer)), false), params, error_context: None }
+    }
+    pub fn into_writer(self) -> W { self.writer.into_inner().inner.inner }
+
+    /// Location of the error, after serialization has failed
+    ///
+    /// Offsets are numbers of bytes written to the head and to the tail of the buffer before failure.
+    #[must_use]
+    pub fn error_context(&self) -> Option<&ErrorContext> {
+        self.error_context.as_ref()
+    }
+
+    // Record location of error while it propagates, `segment` is prepended to the path
+    #[cold]
+    fn error_at(&mut self, err: Error, segment: PathSegment) -> Error {
+        let (head, tail) = (self.writer.get_ref().head, self.writer.get_ref().tail);
+        self.error_context.get_or_insert_with(|| ErrorContext::new(head, tail)).push(segment);
+        err
+    }
+    // Take error context of nested serializer
+    #[cfg(feature="std")]
+    #[cold]
+    fn merge_error_context(&mut self, context: Option<ErrorContext>) {
+        if let Some(mut context) = context {
+            // offsets in nested buffer are meaningless, use offsets where the nested value starts or fails
+            context.set_offsets(self.writer.get_ref().head, self.writer.get_ref().tail);
+            self.error_context = Some(context);
+        }
+    }
+
+    // Number of bytes written so far
+    #[inline]
+    fn written(&self) -> usize {
+        let counting = self.writer.get_ref();
+        counting.head + counting.tail
+    }
+    // `calc_layout()` writer, when serializing into it, and numbers of bytes written to the head
+    // and to the tail so far
+    #[cfg(feature="std")]
+    #[inline]
+    fn layout(&mut self) -> Option<(&mut LayoutCalc, (usize, usize))> {
+        let counting = self.writer.get_mut();
+        let at = (counting.head, counting.tail);
+        counting.inner.inner.layout_calc().map(|layout| (layout, at))
+    }
+    // Record start of nested value for `calc_layout()`
+    #[inline]
+    #[cfg_attr(not(feature="std"), allow(unused_variables, clippy::unused_self))]
+    fn begin_layout(&mut self, segment: PathSegment) {
+        #[cfg(feature="std")]
+        if let Some((layout, at)) = self.layout() {
+            layout.begin(segment, at);
+        }
+    }
+    // Record end of nested value for `calc_layout()`
+    #[inline]
+    #[cfg_attr(not(feature="std"), allow(clippy::unused_self))]
+    fn end_layout(&mut self) {
+        #[cfg(feature="std")]
+        if let Some((layout, at)) = self.layout() {
+            layout.end(at);
+        }
+    }
+    // Record bytes written since `before` as taken by lengths, for `calc_layout()`
+    #[inline]
+    #[cfg_attr(not(feature="std"), allow(unused_variables, clippy::unused_self))]
+    fn add_len_overhead(&mut self, before: usize) {
+        #[cfg(feature="std")]
+        if let Some((layout, at)) = self.layout() {
+            layout.add_overhead(at.0 + at.1 - before);
+        }
     }
-    pub fn into_writer(self) -> W { self.writer }
 
     #[inline]
     fn write_len(&mut self, v: usize) -> Result {
-        P::SeqLenEncoder::write(&mut self.writer, v)
+        let before = self.written();
+        P::SeqLenEncoder::write(&mut self.writer, v)?;
+        self.add_len_overhead(before);
+        Ok(())
     }
     fn write_discr(&mut self, v: u32) -> Result {
         P::DiscriminantEncoder::write(&mut self.writer, v)
     }
+    #[inline]
+    fn write_seq_marker(&mut self, marker: u8) -> Result {
+        let before = self.written();
+        marker.to_writer(&mut self.writer, self.params)?;
+        self.add_len_overhead(before);
+        Ok(())
+    }
+    // Write type tag, for self-describing format only
+    #[inline]
+    fn write_tag(&mut self, tag: TypeTag) -> Result {
+        if P::SELF_DESCRIBING {
+            (tag as u8).to_writer(&mut self.writer, self.params)
+        } else {
+            Ok(())
+        }
+    }
+    // Write enum discriminant, or variant name for self-describing format
+    fn write_variant(&mut self, variant_index: u32, variant: &str) -> Result {
+        if P::SELF_DESCRIBING {
+            self.write_tag(TypeTag::Enum)?;
+            self.write_tag(TypeTag::Str)?;
+            self.write_bytes(variant.as_bytes())
+        } else {
+            self.write_discr(variant_index)
+        }
+    }
+    fn write_bytes(&mut self, v: &[u8]) -> Result {
+        match P::SEQ_ENCODING {
+            SeqEncoding::Length => {
+                self.write_len(v.len())?;
+                self.writer.write(v)
+            },
+            SeqEncoding::Escaped => {
+                // escaping and terminator are taken by length
+                let before = self.written() + v.len();
+                bytes_esc::serialize_bytes_ordered(&mut self.writer, v, self.params)?;
+                self.add_len_overhead(before);
+                Ok(())
+            },
+        }
+    }
 }
 
 impl<W> FormatVersion<AscendingOrder> for Serializer<W, AscendingOrder>  {
-    const VERSION: u32 = 1;
+    const VERSION: u32 = 2;
 }
 
 impl<W> FormatVersion<PortableBinary> for Serializer<W, PortableBinary>  {
@@ -56,14 +184,39 @@ impl<W> FormatVersion<NativeBinary> for Serializer<W, NativeBinary>  {
     const VERSION: u32 = 1;
 }
 
+impl<W> FormatVersion<EscapedAscending> for Serializer<W, EscapedAscending>  {
+    const VERSION: u32 = 1;
+}
+
+impl<W> FormatVersion<CompactAscending> for Serializer<W, CompactAscending>  {
+    const VERSION: u32 = 1;
+}
+
+impl<W> FormatVersion<SelfDescribing> for Serializer<W, SelfDescribing>  {
+    const VERSION: u32 = 1;
+}
+
 macro_rules! serialize_fn {
-    ($fn:ident, $t:ty) => {
+    ($fn:ident, $t:ty, $tag:ident) => {
         fn $fn(self, v: $t) -> Result {
+            self.write_tag(TypeTag::$tag)?;
             v.to_writer(&mut self.writer, self.params)
         }
     }
 }
 
+macro_rules! serialize_int_fn {
+    ($fn:ident, $t:ty, $tag:ident, $compact_fn:ident) => {
+        fn $fn(self, v: $t) -> Result {
+            self.write_tag(TypeTag::$tag)?;
+            match P::INT_ENCODING {
+                IntEncoding::Fixed => v.to_writer(&mut self.writer, self.params),
+                IntEncoding::Compact => v.$compact_fn(&mut self.writer),
+            }
+        }
+    }
+}
+
 impl<'a, W, P> ser::Serializer for &'a mut Serializer<W, P>
     where W: TailWriteBytes,
           P: SerializerParams,
@@ -79,120 +232,180 @@ impl<'a, W, P> ser::Serializer for &'a mut Serializer<W, P>
     type SerializeStruct = SerializeCompound<'a, W, P>;
     type SerializeStructVariant = SerializeCompound<'a, W, P>;
 
-    serialize_fn!(serialize_bool, bool);
-    serialize_fn!(serialize_u8,   u8);
-    serialize_fn!(serialize_u16,  u16);
-    serialize_fn!(serialize_u32,  u32);
-    serialize_fn!(serialize_u64,  u64);
-    serialize_fn!(serialize_i8,   i8);
-    serialize_fn!(serialize_i16,  i16);
-    serialize_fn!(serialize_i32,  i32);
-    serialize_fn!(serialize_i64,  i64);
-    serialize_fn!(serialize_f32,  f32);
-    serialize_fn!(serialize_f64,  f64);
-    serde_if_integer128! {
-        serialize_fn!(serialize_u128,  u128);
-        serialize_fn!(serialize_i128,  i128);
-    }
-    serialize_fn!(serialize_char, char);
+    serialize_fn!(serialize_bool, bool, Bool);
+    serialize_fn!(serialize_u8,   u8, U8);
+    serialize_fn!(serialize_u16,  u16, U16);
+    serialize_int_fn!(serialize_u32, u32, U32, ord_varu_to_writer);
+    serialize_int_fn!(serialize_u64, u64, U64, ord_varu_to_writer);
+    serialize_fn!(serialize_i8,   i8, I8);
+    serialize_fn!(serialize_i16,  i16, I16);
+    serialize_int_fn!(serialize_i32, i32, I32, ord_vari_to_writer);
+    serialize_int_fn!(serialize_i64, i64, I64, ord_vari_to_writer);
+    serialize_fn!(serialize_f32,  f32, F32);
+    serialize_fn!(serialize_f64,  f64, F64);
+    serialize_fn!(serialize_u128,  u128, U128);
+    serialize_fn!(serialize_i128,  i128, I128);
+    serialize_fn!(serialize_char, char, Char);
 
     fn serialize_str(self, v: &str) -> Result {
-        self.serialize_bytes(v.as_ref())
+        self.write_tag(TypeTag::Str)?;
+        self.write_bytes(v.as_ref())
     }
     fn serialize_bytes(self, v: &[u8]) -> Result {
-        self.write_len(v.len())?;
-        self.writer.write(&v)
+        self.write_tag(TypeTag::Bytes)?;
+        self.write_bytes(v)
     }
     fn serialize_none(self) -> Result {
-        self.serialize_u8(0)
+        if P::SELF_DESCRIBING {
+            self.write_tag(TypeTag::None)
+        } else {
+            0_u8.to_writer(&mut self.writer, self.params)
+        }
     }
     fn serialize_some<T>(self, value: &T) -> Result
         where T: ?Sized + Serialize,
     {
-        self.serialize_u8(1)?;
+        if P::SELF_DESCRIBING {
+            self.write_tag(TypeTag::Some)?;
+        } else {
+            1_u8.to_writer(&mut self.writer, self.params)?;
+        }
         value.serialize(self)
     }
-    fn serialize_unit(self) -> Result { Ok(()) }
-
+    fn serialize_unit(self) -> Result {
+        self.write_tag(TypeTag::Unit)
+    }
     fn serialize_unit_struct(self, _name: &'static str) -> Result {
         self.serialize_unit()
     }
     fn serialize_unit_variant(self, _name: &'static str, variant_index: u32,
-                              _variant: &'static str) -> Result {
-        self.write_discr(variant_index)
+                              variant: &'static str) -> Result {
+        self.write_variant(variant_index, variant)?;
+        self.write_tag(TypeTag::Unit)
     }
-    fn serialize_newtype_struct<T>(self, _name: &'static str, value: &T) -> Result
+    fn serialize_newtype_struct<T>(self, name: &'static str, value: &T) -> Result
         where T: ?Sized + Serialize,
     {
-        value.serialize(self)
+        // ordering is irrelevant for self-describing format
+        if name == DESC_NEWTYPE_NAME && !P::SELF_DESCRIBING {
+            let inverted = self.writer.is_inverted();
+            self.writer.set_inverted(!inverted);
+            let res = value.serialize(&mut *self);
+            self.writer.set_inverted(inverted);
+            res
+        } else {
+            value.serialize(self)
+        }
     }
-    fn serialize_newtype_variant<T: ?Sized>(self, _name: &'static str,
-                                            variant_index: u32, _variant: &'static str,
+    fn serialize_newtype_variant<T>(self, _name: &'static str,
+                                            variant_index: u32, variant: &'static str,
                                             value: &T) -> Result
-        where T: serde::ser::Serialize,
+        where T: ?Sized + serde::ser::Serialize,
     {
-        self.write_discr(variant_index)?;
+        self.write_variant(variant_index, variant)?;
         value.serialize(self)
     }
 
-    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple> {
-        Ok(SerializeCompound::new(self))
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple> {
+        SerializeCompound::new(self, TypeTag::Seq, len, "")
     }
 
     fn serialize_tuple_struct(
         self,
-        _name: &'static str,
-        _len: usize,
+        name: &'static str,
+        len: usize,
     ) -> Result<Self::SerializeTupleStruct> {
-        Ok(SerializeCompound::new(self))
+        // enum variant with name known at runtime only: variant name and value follow
+        if name == RUNTIME_VARIANT_NAME && P::SELF_DESCRIBING {
+            self.write_tag(TypeTag::Enum)?;
+            return Ok(SerializeCompound::Fields { ser: self, index: 0, name: "" });
+        }
+        SerializeCompound::new(self, TypeTag::Seq, len, "")
     }
 
     fn serialize_tuple_variant(
         self,
         _name: &'static str,
         variant_index: u32,
-        _variant: &'static str,
-        _len: usize,
+        variant: &'static str,
+        len: usize,
     ) -> Result<Self::SerializeTupleVariant> {
-        self.write_discr(variant_index)?;
-        Ok(SerializeCompound::new(self))
+        self.write_variant(variant_index, variant)?;
+        SerializeCompound::new(self, TypeTag::Seq, len, "")
     }
-    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct> {
-        Ok(SerializeCompound::new(self))
+    fn serialize_struct(self, name: &'static str, len: usize) -> Result<Self::SerializeStruct> {
+        SerializeCompound::new(self, TypeTag::Map, len, name)
     }
     fn serialize_struct_variant(
         self,
         _name: &'static str,
         variant_index: u32,
-        _variant: &'static str,
-        _len: usize,
+        variant: &'static str,
+        len: usize,
     ) -> Result<Self::SerializeStructVariant> {
-        self.write_discr(variant_index)?;
-        Ok(SerializeCompound::new(self))
+        self.write_variant(variant_index, variant)?;
+        SerializeCompound::new(self, TypeTag::Map, len, "")
     }
     // map and seq are variable-length sequences, use double encoding
     fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq> {
-        let len = len.ok_or(Error::SerializeSequenceMustHaveLength)?;
+        self.write_tag(TypeTag::Seq)?;
         SerializeCompoundSeq::new(len, self)
     }
     fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap> {
-        let len = len.ok_or(Error::SerializeSequenceMustHaveLength)?;
+        self.write_tag(TypeTag::Map)?;
         SerializeCompoundSeq::new(len, self)
     }
     #[cfg(not(feature = "std"))]
-    fn collect_str<T: ?Sized>(self, _value: &T) -> Result<Self::Ok, Self::Error> where
-        T: core::fmt::Display {
+    fn collect_str<T>(self, _value: &T) -> Result<Self::Ok, Self::Error> where
+        T: ?Sized + core::fmt::Display {
         Err(Error::CannotSerializeDisplayInNoStdContext)
     }
 }
 
-pub struct SerializeCompound<'a, W, P: SerializerParams> {
-    ser: &'a mut Serializer<W, P>,
+pub enum SerializeCompound<'a, W, P: SerializerParams> {
+    // fields follow each other; index of current field and struct name are kept for error context
+    Fields { ser: &'a mut Serializer<W, P>, index: usize, name: &'static str },
+    // self-describing format: tuples are encoded as sequences, structs as maps
+    Tagged(SerializeCompoundSeq<'a, W, P>),
 }
 
-impl <'a, W, P: SerializerParams> SerializeCompound<'a, W, P> {
-    fn new(ser: &'a mut Serializer<W, P>) -> Self {
-        Self { ser }
+impl <'a, W, P> SerializeCompound<'a, W, P>
+    where W: TailWriteBytes,
+          P: SerializerParams,
+{
+    fn new(ser: &'a mut Serializer<W, P>, tag: TypeTag, len: usize, name: &'static str) -> Result<Self> {
+        if P::SELF_DESCRIBING {
+            ser.write_tag(tag)?;
+            Ok(Self::Tagged(SerializeCompoundSeq::new(Some(len), ser)?))
+        } else {
+            Ok(Self::Fields { ser, index: 0, name })
+        }
+    }
+    fn serialize_field<T>(&mut self, value: &T, key: Option<&'static str>) -> Result
+        where T: ?Sized + serde::ser::Serialize,
+    {
+        match self {
+            Self::Fields { ser, index, name } => {
+                ser.begin_layout(key.map_or(PathSegment::Index(*index), PathSegment::Field));
+                let res = value.serialize(&mut **ser);
+                ser.end_layout();
+                *index += 1;
+                res.map_err(|e| {
+                    let e = ser.error_at(e, key.map_or(PathSegment::Index(*index - 1), PathSegment::Field));
+                    if name.is_empty() { e } else { ser.error_at(e, PathSegment::Type(name)) }
+                })
+            },
+            Self::Tagged(seq) => match key {
+                Some(key) => seq.serialize_struct_field(key, value),
+                None => ser::SerializeSeq::serialize_element(seq, value),
+            },
+        }
+    }
+    fn end(self) -> Result {
+        match self {
+            Self::Fields { .. } => Ok(()),
+            Self::Tagged(seq) => seq.end(),
+        }
     }
 }
 
@@ -205,19 +418,18 @@ macro_rules! seq_compound_impl {
             type Ok = ();
             type Error = Error;
 
-            fn $fn<T: ?Sized>(&mut self, value: &T) -> Result
-                where T: serde::ser::Serialize,
+            fn $fn<T>(&mut self, value: &T) -> Result
+                where T: ?Sized + serde::ser::Serialize,
             {
-                value.serialize(&mut *self.ser)
+                self.serialize_field(value, None)
             }
             fn end(self) -> Result {
-                Ok(())
+                SerializeCompound::end(self)
             }
         }
     }
 }
 
-seq_compound_impl!(SerializeSeq,   serialize_element);
 seq_compound_impl!(SerializeTuple, serialize_element);
 seq_compound_impl!(SerializeTupleStruct,  serialize_field);
 seq_compound_impl!(SerializeTupleVariant, serialize_field);
@@ -231,13 +443,13 @@ macro_rules! struct_compound_impl {
             type Ok = ();
             type Error = Error;
 
-            fn serialize_field<T: ?Sized>(&mut self, _key: &'static str, value: &T) -> Result
-                where T: serde::ser::Serialize,
+            fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result
+                where T: ?Sized + serde::ser::Serialize,
             {
-                value.serialize(&mut *self.ser)
+                SerializeCompound::serialize_field(self, value, Some(key))
             }
             fn end(self) -> Result {
-                Ok(())
+                SerializeCompound::end(self)
             }
         }
     }
@@ -248,47 +460,172 @@ struct_compound_impl!(SerializeStructVariant);
 
 pub struct SerializeCompoundSeq<'a, W, P: SerializerParams> {
     ser: &'a mut Serializer<W, P>,
+    // number of items started so far
+    index: usize,
+    #[cfg(feature="std")]
+    unknown_len: Option<UnknownLen<P>>,
+}
+
+// Sequence of unknown length is serialized while counting its items, and its length is written
+// in `end()`. When lengths are written to the tail, tail data of the items is held back until
+// then, because the length must precede it. Otherwise, the items are serialized into temporary
+// buffer, because the length must precede them in the head.
+#[cfg(feature="std")]
+enum UnknownLen<P> {
+    // tail data held back by enclosing sequences, see `DeferringTail::defer()`
+    DeferredTail(Option<Vec<u8>>),
+    Buffered(Serializer<DeVecWriter, P>),
+    // `calc_layout()` writer only counts bytes, so items are written in place, and their layout
+    // entries starting with this one are moved in `end()`, after the length
+    Recorded(usize),
+}
+
+#[cfg(feature="std")]
+impl<P> UnknownLen<P> where P: SerializerParams {
+    fn new<W: TailWriteBytes>(ser: &mut Serializer<W, P>) -> Self {
+        if let Some((layout, _)) = ser.layout() {
+            Self::Recorded(layout.next_entry())
+        } else if P::SeqLenEncoder::TAIL {
+            Self::DeferredTail(ser.writer.get_mut().inner.defer())
+        } else {
+            Self::Buffered(Serializer::new(DeVecWriter::new(), ser.params))
+        }
+    }
 }
 
 impl <'a, W, P> SerializeCompoundSeq<'a,  W, P>
     where W: TailWriteBytes,
           P: SerializerParams,
 {
-    fn new(len: usize, ser: &'a mut Serializer<W, P>) -> Result<Self> {
-        ser.write_len(len)?;
-        Ok(Self { ser })
+    fn new(len: Option<usize>, ser: &'a mut Serializer<W, P>) -> Result<Self> {
+        match (P::SEQ_ENCODING, len) {
+            (SeqEncoding::Length, Some(len)) => ser.write_len(len)?,
+            #[cfg(feature="std")]
+            (SeqEncoding::Length, None) => {
+                let unknown_len = Some(UnknownLen::new(ser));
+                return Ok(Self { ser, index: 0, unknown_len });
+            },
+            #[cfg(not(feature="std"))]
+            (SeqEncoding::Length, None) => return Err(Error::SerializeSequenceMustHaveLength),
+            (SeqEncoding::Escaped, _) => (),
+        }
+        Ok(Self { ser, index: 0, #[cfg(feature="std")] unknown_len: None })
     }
-}
-
-macro_rules! serialize_seqitem {
-    ($fn:ident) => {
-        fn $fn<T: ?Sized>(&mut self, value: &T) -> Result
-            where T: serde::ser::Serialize,
-        {
-            value.serialize(&mut *self.ser)
+    #[inline]
+    fn serialize_item<T>(&mut self, value: &T) -> Result
+        where T: ?Sized + serde::ser::Serialize,
+    {
+        #[cfg(feature="std")]
+        if let Some(UnknownLen::Buffered(buffered)) = &mut self.unknown_len {
+            let res = value.serialize(&mut *buffered);
+            if res.is_err() {
+                let context = buffered.error_context.take();
+                self.ser.merge_error_context(context);
+            }
+            return res;
+        }
+        value.serialize(&mut *self.ser)
+    }
+    // Start new item, serialize its key (for maps) or value
+    fn serialize_new_item<T>(&mut self, value: &T) -> Result
+        where T: ?Sized + serde::ser::Serialize,
+    {
+        let index = self.index;
+        self.index += 1;
+        self.ser.begin_layout(PathSegment::Index(index));
+        let res = self.write_item_marker().and_then(|()| self.serialize_item(value));
+        self.ser.end_layout();
+        res.map_err(|e| self.ser.error_at(e, PathSegment::Index(index)))
+    }
+    // Struct field in self-describing format, as map entry
+    fn serialize_struct_field<T>(&mut self, key: &'static str, value: &T) -> Result
+        where T: ?Sized + serde::ser::Serialize,
+    {
+        self.index += 1;
+        self.ser.begin_layout(PathSegment::Field(key));
+        let res = self.write_item_marker()
+            .and_then(|()| self.serialize_item(key))
+            .and_then(|()| self.serialize_item(value));
+        self.ser.end_layout();
+        res.map_err(|e| self.ser.error_at(e, PathSegment::Field(key)))
+    }
+    fn write_item_marker(&mut self) -> Result {
+        match P::SEQ_ENCODING {
+            SeqEncoding::Length => Ok(()),
+            SeqEncoding::Escaped => self.ser.write_seq_marker(SEQ_ITEM_MARKER),
+        }
+    }
+    #[cfg_attr(not(feature="std"), allow(unused_mut))]
+    fn end(mut self) -> Result {
+        #[cfg(feature="std")]
+        match self.unknown_len.take() {
+            Some(UnknownLen::DeferredTail(outer)) => {
+                let deferred = self.ser.writer.get_mut().inner.restore(outer);
+                self.ser.write_len(self.index)?;
+                // already inverted and counted
+                return self.ser.writer.get_mut().inner.write_deferred(deferred);
+            },
+            Some(UnknownLen::Buffered(buffered)) => {
+                self.ser.write_len(self.index)?;
+                return buffered.into_writer().write_to(&mut self.ser.writer);
+            },
+            Some(UnknownLen::Recorded(first)) => {
+                let counting = self.ser.writer.get_ref();
+                let end = (counting.head, counting.tail);
+                self.ser.write_len(self.index)?;
+                if let Some((layout, at)) = self.ser.layout() {
+                    layout.move_entries(first, at.0 - end.0, at.1 - end.1);
+                }
+                return Ok(());
+            },
+            None => (),
+        }
+        match P::SEQ_ENCODING {
+            SeqEncoding::Length => Ok(()),
+            SeqEncoding::Escaped => self.ser.write_seq_marker(SEQ_END_MARKER),
         }
     }
 }
 
-impl<'a, W, P> serde::ser::SerializeSeq for SerializeCompoundSeq<'a, W, P>
+impl<W, P> serde::ser::SerializeSeq for SerializeCompoundSeq<'_, W, P>
     where W: TailWriteBytes,
           P: SerializerParams,
 {
     type Ok = ();
     type Error = Error;
 
-    serialize_seqitem!(serialize_element);
-    fn end(self) -> Result { Ok(()) }
+    fn serialize_element<T>(&mut self, value: &T) -> Result
+        where T: ?Sized + serde::ser::Serialize,
+    {
+        self.serialize_new_item(value)
+    }
+    fn end(self) -> Result { SerializeCompoundSeq::end(self) }
 }
 
-impl<'a, W, P> serde::ser::SerializeMap for SerializeCompoundSeq<'a, W, P>
+impl<W, P> serde::ser::SerializeMap for SerializeCompoundSeq<'_, W, P>
     where W: TailWriteBytes,
           P: SerializerParams,
 {
     type Ok = ();
     type Error = Error;
 
-    serialize_seqitem!(serialize_key);
-    serialize_seqitem!(serialize_value);
-    fn end(self) -> Result { Ok(()) }
+    fn serialize_key<T>(&mut self, key: &T) -> Result
+        where T: ?Sized + serde::ser::Serialize,
+    {
+        self.serialize_new_item(key)
+    }
+    fn serialize_value<T>(&mut self, value: &T) -> Result
+        where T: ?Sized + serde::ser::Serialize,
+    {
+        let index = self.index.saturating_sub(1);
+        // map value extends layout entry of its key
+        #[cfg(feature="std")]
+        if let Some((layout, _)) = self.ser.layout() {
+            layout.reopen();
+        }
+        let res = self.serialize_item(value);
+        self.ser.end_layout();
+        res.map_err(|e| self.ser.error_at(e, PathSegment::Index(index)))
+    }
+    fn end(self) -> Result { SerializeCompoundSeq::end(self) }
 }
@@ -38,6 +38,84 @@ pub trait EncodingParams: Copy {
     const ENDIANNESS: Endianness;
 }
 
+/// Encoding of variable-length sequences: byte strings, sequences and maps
+#[derive(Copy, Clone)]
+pub enum SeqEncoding {
+    /// Sequence length is encoded with [`SerializerParams::SeqLenEncoder`], followed by its contents
+    Length,
+    /// Byte strings are encoded with order-preserving escaping
+    /// (see [`bytes_esc::serialize_bytes_ordered()`](crate::bytes_esc::serialize_bytes_ordered)),
+    /// every item of sequence or map is prefixed with [`SEQ_ITEM_MARKER`] byte, and sequence is
+    /// terminated with [`SEQ_END_MARKER`] byte. Sequence lengths are not stored,
+    /// [`SerializerParams::SeqLenEncoder`] is not used.
+    Escaped,
+}
+
+/// Encoding of integer values
+#[derive(Copy, Clone)]
+pub enum IntEncoding {
+    /// Fixed-size encoding, see [`primitives`](crate::primitives)
+    Fixed,
+    /// `u32`, `u64`, `i32` and `i64` values are encoded with order-preserving variable-length
+    /// encoding, see [`varint::OrdVarUInt`] and [`varint::OrdVarInt`]. Small values take 1-2 bytes.
+    /// Other integer types use fixed-size encoding.
+    Compact,
+}
+
+/// Byte which precedes every item of sequence or map with [`SeqEncoding::Escaped`]
+pub const SEQ_ITEM_MARKER: u8 = 1;
+
+/// Byte which terminates sequence or map with [`SeqEncoding::Escaped`]
+pub const SEQ_END_MARKER: u8 = 0;
+
+/// Type tags which precede every value with [`SerializerParams::SELF_DESCRIBING`]
+///
+/// Primitive values, byte strings, sequences and maps are encoded after the tag as usual.
+/// Tuples, tuple structs and structs are encoded as sequences and maps (with field names as keys)
+/// respectively. Enum values are encoded as variant name (tagged string), followed by
+/// tagged variant contents: unit, a value, a sequence or a map.
+/// `Option` values are encoded as [`None`](TypeTag::None) tag, or as [`Some`](TypeTag::Some) tag
+/// followed by tagged value. Newtype structs are transparent.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum TypeTag {
+    Unit = 0,
+    Bool,
+    U8,
+    U16,
+    U32,
+    U64,
+    U128,
+    I8,
+    I16,
+    I32,
+    I64,
+    I128,
+    F32,
+    F64,
+    Char,
+    Str,
+    Bytes,
+    None,
+    Some,
+    Seq,
+    Map,
+    Enum,
+}
+
+impl TypeTag {
+    const ALL: [TypeTag; 22] = [ TypeTag::Unit, TypeTag::Bool, TypeTag::U8, TypeTag::U16, TypeTag::U32,
+        TypeTag::U64, TypeTag::U128, TypeTag::I8, TypeTag::I16, TypeTag::I32, TypeTag::I64, TypeTag::I128,
+        TypeTag::F32, TypeTag::F64, TypeTag::Char, TypeTag::Str, TypeTag::Bytes, TypeTag::None,
+        TypeTag::Some, TypeTag::Seq, TypeTag::Map, TypeTag::Enum ];
+
+    /// Get tag from its byte value
+    #[must_use]
+    pub fn from_u8(v: u8) -> Option<Self> {
+        Self::ALL.get(v as usize).copied()
+    }
+}
+
 /// Parameters for implementations of `serde` serializer and deserializer
 pub trait SerializerParams: EncodingParams {
     /// Encoder for sequence lengths
@@ -45,6 +123,19 @@ pub trait SerializerParams: EncodingParams {
 
     /// Encoder for discriminant values
     type DiscriminantEncoder: LengthEncoder<Value=u32>;
+
+    /// Encoding of byte strings, sequences and maps
+    const SEQ_ENCODING: SeqEncoding = SeqEncoding::Length;
+
+    /// Encoding of integer values
+    const INT_ENCODING: IntEncoding = IntEncoding::Fixed;
+
+    /// Write [`TypeTag`] before every value, so that serialized data can be deserialized
+    /// without knowing its type (with `deserialize_any()`), and fields can be skipped
+    const SELF_DESCRIBING: bool = false;
+
+    /// Read struct fields missing at the end of input as absent, see [`Versioned`]
+    const VERSIONED_STRUCTS: bool = false;
 }
 
 /// Encoder for array lengths, enum discriminants etc.
@@ -52,6 +143,9 @@ pub trait LengthEncoder {
     /// Value type, may be `u32`, `u64` or usize
     type Value;
 
+    /// Values are written to the tail of the buffer
+    const TAIL: bool = false;
+
     /// Calculate serialized size for value
     fn calc_size(value: Self::Value) -> usize;
     fn read(reader: impl TailReadBytes) -> Result<Self::Value>;
@@ -66,9 +160,16 @@ impl<T> EncodingParams for &T where T: EncodingParams {
 impl <T> SerializerParams for &T where T: SerializerParams {
     type SeqLenEncoder = T::SeqLenEncoder;
     type DiscriminantEncoder = T::DiscriminantEncoder;
+    const SEQ_ENCODING: SeqEncoding = T::SEQ_ENCODING;
+    const INT_ENCODING: IntEncoding = T::INT_ENCODING;
+    const SELF_DESCRIBING: bool = T::SELF_DESCRIBING;
+    const VERSIONED_STRUCTS: bool = T::VERSIONED_STRUCTS;
 }
 
 /// Serializer parameters for lexicographic order-preserving serialization in ascending order
+///
+/// Enum discriminants are encoded with order-preserving [`varint::OrdVarIntDiscrEncoder`],
+/// so enums with any number of variants sort in order of variant indices.
 #[derive(Copy, Clone, Default)]
 pub struct AscendingOrder;
 
@@ -78,15 +179,121 @@ impl EncodingParams for AscendingOrder {
 }
 
 impl SerializerParams for AscendingOrder {
+    type SeqLenEncoder = varint::VarIntTailLenEncoder;
+    type DiscriminantEncoder = varint::OrdVarIntDiscrEncoder;
+}
+
+/// Serializer parameters for lexicographic order-preserving serialization of composite keys, in ascending order
+///
+/// With [`AscendingOrder`], contents of byte strings are written to the head of the buffer and their
+/// lengths to the tail, so for values like `(String, u32)` first string bytes are compared
+/// with bytes of the next field. This pre-set uses [`SeqEncoding::Escaped`] instead, so that
+/// serialized values sort exactly like tuples (or structs with derived `Ord`) they came from.
+/// The price is escaping overhead for zero bytes in byte strings and one marker byte per
+/// sequence item. Tail of the buffer is not used.
+#[derive(Copy, Clone, Default)]
+pub struct EscapedAscending;
+
+impl EncodingParams for EscapedAscending {
+    const ORDER: Order = Order::Ascending;
+    const ENDIANNESS: Endianness = Endianness::Big;
+}
+
+impl SerializerParams for EscapedAscending {
+    type SeqLenEncoder = varint::VarIntLenEncoder;
+    type DiscriminantEncoder = varint::OrdVarIntDiscrEncoder;
+    const SEQ_ENCODING: SeqEncoding = SeqEncoding::Escaped;
+}
+
+/// Serializer parameters for lexicographic order-preserving serialization in ascending order,
+/// with variable-length encoding of integers
+///
+/// Same as [`AscendingOrder`], but uses [`IntEncoding::Compact`]: values of `u32`, `u64`, `i32`
+/// and `i64` types take from 1 to 9 bytes depending on their magnitude, instead of 4 or 8 bytes.
+#[derive(Copy, Clone, Default)]
+pub struct CompactAscending;
+
+impl EncodingParams for CompactAscending {
+    const ORDER: Order = Order::Ascending;
+    const ENDIANNESS: Endianness = Endianness::Big;
+}
+
+impl SerializerParams for CompactAscending {
+    type SeqLenEncoder = varint::VarIntTailLenEncoder;
+    type DiscriminantEncoder = varint::OrdVarIntDiscrEncoder;
+    const INT_ENCODING: IntEncoding = IntEncoding::Compact;
+}
+
+/// Serializer parameters for self-describing format, for values rather than keys
+///
+/// Every value is preceded by [`TypeTag`], so data can be deserialized with `deserialize_any()`,
+/// which makes possible to use `#[serde(untagged)]` and internally tagged enums, and to skip
+/// unknown struct fields. Struct fields and enum variants are identified by name.
+/// Lexicographic ordering is not preserved.
+#[derive(Copy, Clone, Default)]
+pub struct SelfDescribing;
+
+impl EncodingParams for SelfDescribing {
+    const ORDER: Order = Order::Ascending;
+    const ENDIANNESS: Endianness = Endianness::Big;
+}
+
+impl SerializerParams for SelfDescribing {
     type SeqLenEncoder = varint::VarIntTailLenEncoder;
     type DiscriminantEncoder = varint::VarIntDiscrEncoder;
+    const SELF_DESCRIBING: bool = true;
+}
+
+/// Serializer parameters `P` with appendable structs, so that fields can be added to structs
+/// without migration of existing data
+///
+/// Encoding is the same as with `P`: structs (and struct variants of enums) are encoded as tuples
+/// of their fields, so data serialized with `P` and with `Versioned<P>` can be read with either.
+/// When deserializing with these parameters, struct fields which are missing because the end
+/// of input is reached are reported to `serde` as absent, so fields marked with `#[serde(default)]`
+/// get their default values. Readers of structs with fewer fields leave extra trailing fields
+/// unread, as with any parameters, see [`Deserializer`](crate::Deserializer).
+///
+/// End of input can only be detected for structs at the very end of serialized data: the top-level
+/// struct, or the last field of it, recursively. Fields should be appended only to such structs.
+/// Has no effect with [`SelfDescribing`], where struct fields are identified by name.
+/// ```
+/// # use ordcode::{ params::{ Versioned, AscendingOrder }, Deserializer, DeBytesReader, ser_to_vec_ordered, Order };
+/// # use serde::Deserialize;
+/// #[derive(serde_derive::Serialize)]
+/// struct RecordV1 { id: u32, name: String }
+///
+/// #[derive(serde_derive::Deserialize)]
+/// struct RecordV2 { id: u32, name: String, #[serde(default)] tags: Vec<String> }
+///
+/// let buf = ser_to_vec_ordered(&RecordV1 { id: 1, name: "a".to_string() }, Order::Ascending).unwrap();
+/// let mut deser = Deserializer::new(DeBytesReader::new(&buf), Versioned(AscendingOrder));
+/// let rec = RecordV2::deserialize(&mut deser).unwrap();
+/// assert!(rec.tags.is_empty());
+/// ```
+#[derive(Copy, Clone, Default)]
+pub struct Versioned<P>(pub P);
+
+impl<P> EncodingParams for Versioned<P> where P: EncodingParams {
+    const ORDER: Order = P::ORDER;
+    const ENDIANNESS: Endianness = P::ENDIANNESS;
+}
+
+impl<P> SerializerParams for Versioned<P> where P: SerializerParams {
+    type SeqLenEncoder = P::SeqLenEncoder;
+    type DiscriminantEncoder = P::DiscriminantEncoder;
+    const SEQ_ENCODING: SeqEncoding = P::SEQ_ENCODING;
+    const INT_ENCODING: IntEncoding = P::INT_ENCODING;
+    const SELF_DESCRIBING: bool = P::SELF_DESCRIBING;
+    const VERSIONED_STRUCTS: bool = true;
 }
 
 /// Encoding parameters for lexicographic order-preserving serialization in descending order
 ///
 /// Note: deliberately implements only [`EncodingParams`] trait, not [`SerializerParams`], so it can
 /// be used with serialization primitives, but not with [`Serializer`](crate::Serializer)
-/// or [`Deserializer`](crate::Deserializer).
+/// or [`Deserializer`](crate::Deserializer). To serialize only a part of value
+/// in descending order, wrap it in [`Desc`](crate::Desc).
 #[derive(Copy, Clone, Default)]
 pub struct DescendingOrder;
 
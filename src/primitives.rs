@@ -2,6 +2,7 @@
 //!
 //! If you need to serialize or deserialize a primitive type (e.g. for use as a key), it is better
 //! to use [`SerializableValue`] trait methods on primitive types directly, without using [`serde`].
+//! This also works for composite keys made of tuples, arrays, options and strings.
 //!
 //! Serialize method `to_write()` writes results to [`WriteBytes`] trait impl,
 //!  deserialize method `from_reader()` reads from [`ReadBytes`].
@@ -18,16 +19,50 @@
 //! ### Parameters
 //! Encoding parameters are passed via impl of `EncodingParams` (usually ZST struct).
 
-use crate::{Result, Error, buf::{ReadBytes, WriteBytes}, params::{EncodingParams, Order, Endianness}};
+use crate::{Result, Error, bytes_esc, buf::{ReadBytes, WriteBytes}, params::{EncodingParams, Order, Endianness}};
 use core::convert::TryInto;
 
 /// Serializable value
 ///
-/// This crate implements this trait for all primitive types. For complex types, use
-/// provided _serde_ serializer and deserializer.
-pub trait SerializableValue: Sized {
+/// This crate implements this trait for all primitive types, and for simple composite types which
+/// are useful for keys: tuples (up to 12 items), arrays, `Option`, byte strings and strings.
+/// Encoding of composite types is the same as with _serde_ serializer and
+/// [`params::EscapedAscending`](crate::params::EscapedAscending) pre-set:
+/// - tuple and array items are encoded one after another
+/// - `Option` is encoded as byte `0` for `None`, or byte `1` followed by the value
+/// - `[u8]`, `str` (serialization only), `Vec<u8>` and `String` are encoded with
+///   [`bytes_esc::serialize_bytes_ordered()`](crate::bytes_esc::serialize_bytes_ordered).
+///   Note that _serde_ serializes byte slices and vectors as sequences, not byte strings,
+///   unless `serde_bytes` is used.
+///
+/// For other types, use provided _serde_ serializer and deserializer.
+///
+/// Borrowed `str` and `[u8]` can't be tuple items, but can be written after them:
+///
+/// ```
+/// # use ordcode::{ primitives::SerializableValue, params, DeBytesReader };
+/// let key = (1_u32, Some(-2_i64), *b"ab", "name".to_string());
+/// let mut buf = Vec::new();
+/// key.to_writer(&mut buf, params::AscendingOrder).unwrap();
+/// let mut buf2 = Vec::new();
+/// (1_u32, Some(-2_i64), *b"ab").to_writer(&mut buf2, params::AscendingOrder).unwrap();
+/// "name".to_writer(&mut buf2, params::AscendingOrder).unwrap();
+/// assert_eq!(buf, buf2);
+/// # #[cfg(feature="serde")]
+/// # {
+/// # let mut esc = vec![0_u8; ordcode::calc_size(&key, params::EscapedAscending).unwrap()];
+/// # let mut writer = ordcode::DeBytesWriter::new(&mut esc);
+/// # serde::Serialize::serialize(&key, &mut ordcode::Serializer::new(&mut writer, params::EscapedAscending)).unwrap();
+/// # assert_eq!(buf, esc);
+/// # }
+/// let reader = DeBytesReader::new(&buf);
+/// let value = <(u32, Option<i64>, [u8; 2], String)>::from_reader(reader, params::AscendingOrder).unwrap();
+/// assert_eq!(value, (1, Some(-2), *b"ab", "name".to_string()));
+/// ```
+pub trait SerializableValue {
     fn to_writer<P: EncodingParams>(&self, writer: impl WriteBytes, params: P) -> Result;
-    fn from_reader<P: EncodingParams>(reader: impl ReadBytes, params: P) -> Result<Self>;
+    fn from_reader<P: EncodingParams>(reader: impl ReadBytes, params: P) -> Result<Self>
+        where Self: Sized;
 }
 
 /// Serialization data format version
@@ -81,6 +116,7 @@ macro_rules! serialize_int {
                 })
             }
         }
+        #[allow(clippy::cast_sign_loss, clippy::cast_possible_wrap)]
         impl SerializableValue for $it {
             #[inline]
             fn to_writer<P: EncodingParams>(&self, writer: impl WriteBytes, params: P) -> Result
@@ -106,7 +142,7 @@ serialize_int!(u128, i128);
 
 impl SerializableValue for bool {
     fn to_writer<P: EncodingParams>(&self, writer: impl WriteBytes, params: P) -> Result {
-        let v: u8 = if *self { 1 } else { 0 };
+        let v: u8 = u8::from(*self);
         v.to_writer(writer, params)
     }
 
@@ -129,6 +165,7 @@ impl SerializableValue for char {
 // Ordered serialization of floats
 macro_rules! serialize_float {
     ($ft:ty, $ift:ty, $uft:ty) => {
+        #[allow(clippy::cast_sign_loss, clippy::cast_possible_wrap)]
         impl SerializableValue for $ft {
             #[inline]
             fn to_writer<P: EncodingParams>(&self, mut writer: impl WriteBytes, _params: P) -> Result {
@@ -159,6 +196,110 @@ macro_rules! serialize_float {
 serialize_float!(f32, i32, u32);
 serialize_float!(f64, i64, u64);
 
+impl SerializableValue for [u8] {
+    fn to_writer<P: EncodingParams>(&self, writer: impl WriteBytes, params: P) -> Result {
+        bytes_esc::serialize_bytes_ordered(writer, self, params)
+    }
+}
+
+impl SerializableValue for str {
+    fn to_writer<P: EncodingParams>(&self, writer: impl WriteBytes, params: P) -> Result {
+        bytes_esc::serialize_bytes_ordered(writer, self.as_bytes(), params)
+    }
+}
+
+#[cfg(feature="std")]
+impl SerializableValue for Vec<u8> {
+    fn to_writer<P: EncodingParams>(&self, writer: impl WriteBytes, params: P) -> Result {
+        bytes_esc::serialize_bytes_ordered(writer, self, params)
+    }
+
+    fn from_reader<P: EncodingParams>(reader: impl ReadBytes, params: P) -> Result<Self> {
+        bytes_esc::deserialize_bytes_ordered_to_vec(reader, params)
+    }
+}
+
+#[cfg(feature="std")]
+impl SerializableValue for String {
+    fn to_writer<P: EncodingParams>(&self, writer: impl WriteBytes, params: P) -> Result {
+        bytes_esc::serialize_bytes_ordered(writer, self.as_bytes(), params)
+    }
+
+    fn from_reader<P: EncodingParams>(reader: impl ReadBytes, params: P) -> Result<Self> {
+        let bytes = bytes_esc::deserialize_bytes_ordered_to_vec(reader, params)?;
+        String::from_utf8(bytes).map_err(|_| Error::InvalidUtf8Encoding)
+    }
+}
+
+impl<T: SerializableValue> SerializableValue for Option<T> {
+    fn to_writer<P: EncodingParams>(&self, mut writer: impl WriteBytes, params: P) -> Result {
+        match self {
+            None => 0_u8.to_writer(writer, params),
+            Some(v) => {
+                1_u8.to_writer(&mut writer, params)?;
+                v.to_writer(writer, params)
+            },
+        }
+    }
+
+    fn from_reader<P: EncodingParams>(mut reader: impl ReadBytes, params: P) -> Result<Self> {
+        match <u8>::from_reader(&mut reader, params)? {
+            0 => Ok(None),
+            1 => T::from_reader(reader, params).map(Some),
+            _ => Err(Error::InvalidTagEncoding),
+        }
+    }
+}
+
+impl<T: SerializableValue, const N: usize> SerializableValue for [T; N] {
+    fn to_writer<P: EncodingParams>(&self, mut writer: impl WriteBytes, params: P) -> Result {
+        self.iter().try_for_each(|v| v.to_writer(&mut writer, params))
+    }
+
+    fn from_reader<P: EncodingParams>(mut reader: impl ReadBytes, params: P) -> Result<Self> {
+        let mut error = None;
+        let items: [Option<T>; N] = core::array::from_fn(|_| {
+            if error.is_some() {
+                return None;
+            }
+            T::from_reader(&mut reader, params).map_err(|e| error = Some(e)).ok()
+        });
+        match error {
+            Some(e) => Err(e),
+            None => Ok(items.map(|v| v.unwrap())),
+        }
+    }
+}
+
+macro_rules! serialize_tuple {
+    ($($t:ident $v:ident),*) => {
+        impl<$($t: SerializableValue),*> SerializableValue for ($($t,)*) {
+            fn to_writer<P: EncodingParams>(&self, mut writer: impl WriteBytes, params: P) -> Result {
+                let ($($v,)*) = self;
+                $($v.to_writer(&mut writer, params)?;)*
+                Ok(())
+            }
+
+            fn from_reader<P: EncodingParams>(mut reader: impl ReadBytes, params: P) -> Result<Self> {
+                Ok(($(<$t>::from_reader(&mut reader, params)?,)*))
+            }
+        }
+    };
+}
+
+serialize_tuple!(T0 v0);
+serialize_tuple!(T0 v0, T1 v1);
+serialize_tuple!(T0 v0, T1 v1, T2 v2);
+serialize_tuple!(T0 v0, T1 v1, T2 v2, T3 v3);
+serialize_tuple!(T0 v0, T1 v1, T2 v2, T3 v3, T4 v4);
+serialize_tuple!(T0 v0, T1 v1, T2 v2, T3 v3, T4 v4, T5 v5);
+serialize_tuple!(T0 v0, T1 v1, T2 v2, T3 v3, T4 v4, T5 v5, T6 v6);
+serialize_tuple!(T0 v0, T1 v1, T2 v2, T3 v3, T4 v4, T5 v5, T6 v6, T7 v7);
+serialize_tuple!(T0 v0, T1 v1, T2 v2, T3 v3, T4 v4, T5 v5, T6 v6, T7 v7, T8 v8);
+serialize_tuple!(T0 v0, T1 v1, T2 v2, T3 v3, T4 v4, T5 v5, T6 v6, T7 v7, T8 v8, T9 v9);
+serialize_tuple!(T0 v0, T1 v1, T2 v2, T3 v3, T4 v4, T5 v5, T6 v6, T7 v7, T8 v8, T9 v9, T10 v10);
+serialize_tuple!(T0 v0, T1 v1, T2 v2, T3 v3, T4 v4, T5 v5, T6 v6, T7 v7, T8 v8, T9 v9, T10 v10, T11 v11);
+
 /// Bitwise invert contents of a buffer
 pub fn invert_buffer(buf: &mut [u8])
 {
@@ -0,0 +1,143 @@
+//! Range bounds for scanning keys by prefix
+//!
+//! With [`params::EscapedAscending`](crate::params::EscapedAscending) pre-set (and with
+//! [`KeyBuilder`](crate::key::KeyBuilder) or [`SerializableValue`](crate::primitives::SerializableValue)),
+//! fields of tuples and structs are written one after another, and strings and byte strings
+//! are terminated. So encoding of the first fields of a key, a partial tuple, is a byte prefix of
+//! encodings of all keys which start with these field values, and only of them: encoded string
+//! `"ab"` is not a prefix of encoded `"abc"`.
+//!
+//! All keys with `prefix` are in the range `[prefix, successor)`, where successor is the smallest
+//! byte string which is greater than all strings starting with `prefix`: `prefix` with trailing
+//! `0xFF` bytes removed and the last byte incremented. If `prefix` consists of `0xFF` bytes only,
+//! there is no successor and the range is unbounded. This works the same for keys serialized in
+//! [`Order::Descending`](crate::Order::Descending), which are bitwise inverted as a whole, if the
+//! prefix is inverted too.
+//!
+//! [`PrefixRange`] implements `RangeBounds<Vec<u8>>`, so it can be passed directly to
+//! `BTreeMap::range()` for `Vec<u8>` keys or to `sled::Tree::range()`. For APIs which take
+//! `RangeBounds<&[u8]>`, like `redb`, use [`PrefixRange::as_slices()`]:
+//!
+//! ```
+//! # use std::collections::BTreeMap;
+//! # use ordcode::{ range::prefix_range, params, Order, Serializer, DeBytesWriter, calc_size };
+//! # use serde::Serialize;
+//! fn key<T: Serialize>(value: &T) -> Vec<u8> {
+//!     let mut buf = vec![0_u8; calc_size(value, params::EscapedAscending).unwrap()];
+//!     value.serialize(&mut Serializer::new(DeBytesWriter::new(&mut buf), params::EscapedAscending)).unwrap();
+//!     buf
+//! }
+//! let mut map = BTreeMap::new();
+//! for (tenant, table, id) in &[(1_u32, "a", 1_u64), (1, "ab", 2), (1, "a", 3), (2, "a", 4)] {
+//!     map.insert(key(&(tenant, table, id)), *id);
+//! }
+//! // all keys with (tenant, table) == (1, "a")
+//! let range = prefix_range(&(1_u32, "a"), Order::Ascending).unwrap();
+//! let ids: Vec<_> = map.range(range).map(|(_, id)| *id).collect();
+//! assert_eq!(ids, vec![1, 3]);
+//! ```
+
+#[cfg(feature="std")]
+use core::ops::{ Bound, RangeBounds };
+
+/// Compute successor of `prefix` in place, returns its length
+///
+/// Successor is the smallest byte string which is greater than all byte strings starting
+/// with `prefix`. Returns `None` if there is no successor (`prefix` is empty or consists
+/// of `0xFF` bytes only), in which case `prefix` is not modified.
+///
+/// ```
+/// # use ordcode::range::prefix_successor_in_place;
+/// let mut buf = [1_u8, 2, 0xFF, 0xFF];
+/// assert_eq!(prefix_successor_in_place(&mut buf), Some(2));
+/// assert_eq!(&buf[..2], &[1, 3]);
+/// assert_eq!(prefix_successor_in_place(&mut [0xFF, 0xFF]), None);
+/// ```
+pub fn prefix_successor_in_place(prefix: &mut [u8]) -> Option<usize> {
+    let pos = prefix.iter().rposition(|b| *b != 0xFF)?;
+    prefix[pos] += 1;
+    Some(pos + 1)
+}
+
+/// Successor of `prefix`, see [`prefix_successor_in_place()`]
+#[cfg(feature="std")]
+#[must_use]
+pub fn prefix_successor(prefix: &[u8]) -> Option<Vec<u8>> {
+    let mut successor = prefix.to_vec();
+    let len = prefix_successor_in_place(&mut successor)?;
+    successor.truncate(len);
+    Some(successor)
+}
+
+/// Range of keys which start with a byte prefix: `[prefix, successor)`
+///
+/// See [module documentation](self) for details.
+#[cfg(feature="std")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PrefixRange {
+    start: Vec<u8>,
+    end: Option<Vec<u8>>,
+}
+
+#[cfg(feature="std")]
+impl PrefixRange {
+    /// Range of keys which start with `prefix`
+    ///
+    /// Prefix should be already encoded (and inverted for descending order), for example with
+    /// [`KeyBuilder`](crate::key::KeyBuilder).
+    #[must_use]
+    pub fn new(prefix: Vec<u8>) -> Self {
+        let end = prefix_successor(&prefix);
+        Self { start: prefix, end }
+    }
+    /// Start of range (inclusive), which is the prefix itself
+    #[must_use]
+    pub fn start(&self) -> &[u8] { &self.start }
+
+    /// End of range (exclusive), `None` if range is unbounded
+    #[must_use]
+    pub fn end(&self) -> Option<&[u8]> { self.end.as_deref() }
+
+    /// Check if `key` is in range, that is if it starts with the prefix
+    #[must_use]
+    pub fn contains(&self, key: &[u8]) -> bool {
+        key.starts_with(&self.start)
+    }
+    /// Range bounds as byte slices, for APIs which take `RangeBounds<&[u8]>`
+    #[must_use]
+    pub fn as_slices(&self) -> (Bound<&[u8]>, Bound<&[u8]>) {
+        (Bound::Included(self.start()), self.end().map_or(Bound::Unbounded, Bound::Excluded))
+    }
+    /// Convert into range bounds
+    #[must_use]
+    pub fn into_bounds(self) -> (Bound<Vec<u8>>, Bound<Vec<u8>>) {
+        (Bound::Included(self.start), self.end.map_or(Bound::Unbounded, Bound::Excluded))
+    }
+}
+
+#[cfg(feature="std")]
+impl RangeBounds<Vec<u8>> for PrefixRange {
+    fn start_bound(&self) -> Bound<&Vec<u8>> {
+        Bound::Included(&self.start)
+    }
+    fn end_bound(&self) -> Bound<&Vec<u8>> {
+        self.end.as_ref().map_or(Bound::Unbounded, Bound::Excluded)
+    }
+}
+
+/// Range of keys which start with `prefix` value, which is usually a tuple of the first key fields
+///
+/// Prefix is serialized with [`params::EscapedAscending`](crate::params::EscapedAscending) and
+/// bitwise inverted for [`Order::Descending`](crate::Order::Descending), so keys should be serialized
+/// in the same way. See [module documentation](self) for details.
+#[cfg(all(feature="std", feature="serde"))]
+pub fn prefix_range<T>(prefix: &T, order: crate::Order) -> crate::Result<PrefixRange>
+    where T: ?Sized + serde::ser::Serialize,
+{
+    let mut buf = Vec::new();
+    prefix.serialize(&mut crate::Serializer::new(&mut buf, crate::params::EscapedAscending))?;
+    if matches!(order, crate::Order::Descending) {
+        crate::primitives::invert_buffer(&mut buf);
+    }
+    Ok(PrefixRange::new(buf))
+}
@@ -1,4 +1,6 @@
-use crate::{Error, Result, params::{SerializerParams, LengthEncoder}};
+use crate::{Error, Result, bytes_esc, params::{SerializerParams, LengthEncoder, SeqEncoding, IntEncoding, TypeTag},
+            varint::{OrdVarUInt, OrdVarInt}};
+use crate::desc::RUNTIME_VARIANT_NAME;
 use serde::{ser, Serialize };
 use core::mem::size_of;
 
@@ -34,6 +36,34 @@ impl<P> SizeCalc<P> where P: SerializerParams {
     fn add_discriminant_size(&mut self, v: u32) {
         self.size += P::DiscriminantEncoder::calc_size(v);
     }
+    // add size of type tag, for self-describing format only
+    #[inline]
+    fn add_tag(&mut self) {
+        if P::SELF_DESCRIBING {
+            self.add_ty::<TypeTag>();
+        }
+    }
+    // add size of enum discriminant, or of variant name for self-describing format
+    #[inline]
+    fn add_variant(&mut self, variant_index: u32, variant: &str) {
+        if P::SELF_DESCRIBING {
+            self.add_tag(); // enum tag
+            self.add_tag(); // string tag
+            self.add_bytes(variant.as_bytes());
+        } else {
+            self.add_discriminant_size(variant_index);
+        }
+    }
+    #[inline]
+    fn add_bytes(&mut self, v: &[u8]) {
+        match P::SEQ_ENCODING {
+            SeqEncoding::Length => {
+                self.add_seq_len(v.len());
+                self.size += v.len();
+            },
+            SeqEncoding::Escaped => self.size += bytes_esc::escaped_length_ordered(v),
+        }
+    }
 }
 
 impl<P> Default for SizeCalc<P> where P: SerializerParams {
@@ -44,12 +74,27 @@ macro_rules! serialize_fn {
     ($fn:ident, $t:ty) => {
         #[inline]
         fn $fn(self, _v: $t) -> Result {
+            self.add_tag();
             self.add_ty::<$t>();
             Ok(())
         }
     }
 }
 
+macro_rules! serialize_int_fn {
+    ($fn:ident, $t:ty, $compact_fn:ident) => {
+        #[inline]
+        fn $fn(self, v: $t) -> Result {
+            self.add_tag();
+            match P::INT_ENCODING {
+                IntEncoding::Fixed => self.add_ty::<$t>(),
+                IntEncoding::Compact => self.size += v.$compact_fn() as usize,
+            }
+            Ok(())
+        }
+    }
+}
+
 impl<'a, P> ser::Serializer for &'a mut SizeCalc<P>
     where P: SerializerParams,
 {
@@ -67,18 +112,16 @@ impl<'a, P> ser::Serializer for &'a mut SizeCalc<P>
     serialize_fn!(serialize_bool, bool);
     serialize_fn!(serialize_u8,   u8);
     serialize_fn!(serialize_u16,  u16);
-    serialize_fn!(serialize_u32,  u32);
-    serialize_fn!(serialize_u64,  u64);
+    serialize_int_fn!(serialize_u32, u32, ord_varu_encoded_len);
+    serialize_int_fn!(serialize_u64, u64, ord_varu_encoded_len);
     serialize_fn!(serialize_i8,   i8);
     serialize_fn!(serialize_i16,  i16);
-    serialize_fn!(serialize_i32,  i32);
-    serialize_fn!(serialize_i64,  i64);
+    serialize_int_fn!(serialize_i32, i32, ord_vari_encoded_len);
+    serialize_int_fn!(serialize_i64, i64, ord_vari_encoded_len);
     serialize_fn!(serialize_f32,  f32);
     serialize_fn!(serialize_f64,  f64);
-    serde_if_integer128! {
-        serialize_fn!(serialize_u128,  u128);
-        serialize_fn!(serialize_i128,  i128);
-    }
+    serialize_fn!(serialize_u128,  u128);
+    serialize_fn!(serialize_i128,  i128);
     serialize_fn!(serialize_char,  char);
     #[inline]
     fn serialize_str(self, v: &str) -> Result {
@@ -86,12 +129,13 @@ impl<'a, P> ser::Serializer for &'a mut SizeCalc<P>
     }
     #[inline]
     fn serialize_bytes(self, v: &[u8]) -> Result {
-        self.add_seq_len(v.len());
-        self.size += v.len();
+        self.add_tag();
+        self.add_bytes(v);
         Ok(())
     }
     #[inline]
     fn serialize_none(self) -> Result {
+        // option marker or type tag
         self.add_ty::<u8>();
         Ok(())
     }
@@ -103,7 +147,10 @@ impl<'a, P> ser::Serializer for &'a mut SizeCalc<P>
         value.serialize(self)
     }
     #[inline]
-    fn serialize_unit(self) -> Result { Ok(()) }
+    fn serialize_unit(self) -> Result {
+        self.add_tag();
+        Ok(())
+    }
 
     #[inline]
     fn serialize_unit_struct(self, _name: &'static str) -> Result {
@@ -111,85 +158,140 @@ impl<'a, P> ser::Serializer for &'a mut SizeCalc<P>
     }
     #[inline]
     fn serialize_unit_variant(self, _name: &'static str, variant_index: u32,
-                              _variant: &'static str) -> Result {
-        self.add_discriminant_size(variant_index);
+                              variant: &'static str) -> Result {
+        self.add_variant(variant_index, variant);
+        self.add_tag();
         Ok(())
     }
     #[inline]
     fn serialize_newtype_struct<T>(self, _name: &'static str, value: &T) -> Result
         where T: ?Sized + Serialize,
     {
+        // values in `Desc` wrapper are inverted, but have the same size
         value.serialize(self)
     }
     #[inline]
-    fn serialize_newtype_variant<T: ?Sized>(self, _name: &'static str,
-                                            variant_index: u32, _variant: &'static str,
+    fn serialize_newtype_variant<T>(self, _name: &'static str,
+                                            variant_index: u32, variant: &'static str,
                                             value: &T) -> Result
-        where T: serde::ser::Serialize,
+        where T: ?Sized + serde::ser::Serialize,
     {
-        self.add_discriminant_size(variant_index);
+        self.add_variant(variant_index, variant);
         value.serialize(self)
     }
 
     #[inline]
-    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple> {
-        Ok(SerializeCompound { ser: self })
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple> {
+        SerializeCompound::new_fields(len, self)
     }
     #[inline]
     fn serialize_tuple_struct(
         self,
-        _name: &'static str,
-        _len: usize,
+        name: &'static str,
+        len: usize,
     ) -> Result<Self::SerializeTupleStruct> {
-        Ok(SerializeCompound { ser: self })
+        if name == RUNTIME_VARIANT_NAME && P::SELF_DESCRIBING {
+            self.add_tag(); // enum tag
+            return Ok(SerializeCompound::new(self));
+        }
+        SerializeCompound::new_fields(len, self)
     }
     #[inline]
     fn serialize_tuple_variant(
         self,
         _name: &'static str,
         variant_index: u32,
-        _variant: &'static str,
-        _len: usize,
+        variant: &'static str,
+        len: usize,
     ) -> Result<Self::SerializeTupleVariant> {
-        self.add_discriminant_size(variant_index);
-        Ok(SerializeCompound { ser: self })
+        self.add_variant(variant_index, variant);
+        SerializeCompound::new_fields(len, self)
     }
     #[inline]
-    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct> {
-        Ok(SerializeCompound { ser: self })
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<Self::SerializeStruct> {
+        SerializeCompound::new_fields(len, self)
     }
     #[inline]
     fn serialize_struct_variant(
         self,
         _name: &'static str,
         variant_index: u32,
-        _variant: &'static str,
-        _len: usize,
+        variant: &'static str,
+        len: usize,
     ) -> Result<Self::SerializeStructVariant> {
-        self.add_discriminant_size(variant_index);
-        Ok(SerializeCompound { ser: self })
+        self.add_variant(variant_index, variant);
+        SerializeCompound::new_fields(len, self)
     }
     #[inline]
     fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq> {
-        let len = len.ok_or(Error::SerializeSequenceMustHaveLength)?;
-        self.add_seq_len(len);
-        Ok(SerializeCompound { ser: self })
+        self.add_tag();
+        SerializeCompound::new_seq(len, self)
     }
     #[inline]
     fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap> {
-        let len = len.ok_or(Error::SerializeSequenceMustHaveLength)?;
-        self.add_seq_len(len);
-        Ok(SerializeCompound { ser: self })
+        self.add_tag();
+        SerializeCompound::new_seq(len, self)
     }
     #[cfg(not(feature = "std"))]
-    fn collect_str<T: ?Sized>(self, _value: &T) -> Result<Self::Ok, Self::Error> where
-        T: core::fmt::Display {
+    fn collect_str<T>(self, _value: &T) -> Result<Self::Ok, Self::Error> where
+        T: ?Sized + core::fmt::Display {
             Err(Error::CannotSerializeDisplayInNoStdContext)
     }
 }
 
 pub struct SerializeCompound<'a, P> {
     ser: &'a mut SizeCalc<P>,
+    // number of items in sequence of unknown length, its length size is added in `end()`
+    count: Option<usize>,
+    // tuple or struct fields are encoded as sequence or map items (self-describing format)
+    tagged: bool,
+}
+
+impl<'a, P> SerializeCompound<'a, P> where P: SerializerParams {
+    #[inline]
+    fn new(ser: &'a mut SizeCalc<P>) -> Self {
+        Self { ser, count: None, tagged: false }
+    }
+    #[inline]
+    fn new_fields(len: usize, ser: &'a mut SizeCalc<P>) -> Result<Self> {
+        if P::SELF_DESCRIBING {
+            ser.add_tag();
+            let mut compound = Self::new_seq(Some(len), ser)?;
+            compound.tagged = true;
+            Ok(compound)
+        } else {
+            Ok(Self::new(ser))
+        }
+    }
+    #[inline]
+    #[allow(clippy::unnecessary_wraps)] // fails only without `std`
+    fn new_seq(len: Option<usize>, ser: &'a mut SizeCalc<P>) -> Result<Self> {
+        match (P::SEQ_ENCODING, len) {
+            (SeqEncoding::Length, Some(len)) => ser.add_seq_len(len),
+            #[cfg(feature="std")]
+            (SeqEncoding::Length, None) => return Ok(Self { ser, count: Some(0), tagged: false }),
+            #[cfg(not(feature="std"))]
+            (SeqEncoding::Length, None) => return Err(Error::SerializeSequenceMustHaveLength),
+            (SeqEncoding::Escaped, _) => ser.add_ty::<u8>(), // end marker
+        }
+        Ok(Self::new(ser))
+    }
+    // add size of sequence item marker, count items of sequence of unknown length
+    #[inline]
+    fn add_item(&mut self) {
+        if let SeqEncoding::Escaped = P::SEQ_ENCODING {
+            self.ser.add_ty::<u8>();
+        }
+        if let Some(count) = &mut self.count {
+            *count += 1;
+        }
+    }
+    #[inline]
+    fn end_seq(self) {
+        if let Some(count) = self.count {
+            self.ser.add_seq_len(count);
+        }
+    }
 }
 
 macro_rules! seq_compound_impl {
@@ -201,9 +303,12 @@ macro_rules! seq_compound_impl {
             type Error = Error;
 
             #[inline]
-            fn $fn<T: ?Sized>(&mut self, value: &T) -> Result
-                where T: serde::ser::Serialize,
+            fn $fn<T>(&mut self, value: &T) -> Result
+                where T: ?Sized + serde::ser::Serialize,
             {
+                if self.tagged {
+                    self.add_item();
+                }
                 value.serialize(&mut *self.ser)
             }
             #[inline]
@@ -214,7 +319,6 @@ macro_rules! seq_compound_impl {
     }
 }
 
-seq_compound_impl!(SerializeSeq,   serialize_element);
 seq_compound_impl!(SerializeTuple, serialize_element);
 seq_compound_impl!(SerializeTupleStruct,  serialize_field);
 seq_compound_impl!(SerializeTupleVariant, serialize_field);
@@ -228,9 +332,13 @@ macro_rules! struct_compound_impl {
             type Error = Error;
 
             #[inline]
-            fn serialize_field<T: ?Sized>(&mut self, _key: &'static str, value: &T) -> Result
-                where T: serde::ser::Serialize,
+            fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result
+                where T: ?Sized + serde::ser::Serialize,
             {
+                if self.tagged {
+                    self.add_item();
+                    ser::Serializer::serialize_str(&mut *self.ser, key)?;
+                }
                 value.serialize(&mut *self.ser)
             }
             #[inline]
@@ -247,22 +355,51 @@ struct_compound_impl!(SerializeStructVariant);
 macro_rules! serialize_mapitem {
     ($fn:ident) => {
         #[inline]
-        fn $fn<T: ?Sized>(&mut self, value: &T) -> Result
-            where T: serde::ser::Serialize,
+        fn $fn<T>(&mut self, value: &T) -> Result
+            where T: ?Sized + serde::ser::Serialize,
         {
             value.serialize(&mut *self.ser)
         }
     }
 }
 
-impl<'a, P> serde::ser::SerializeMap for SerializeCompound<'a, P>
+impl<P> serde::ser::SerializeSeq for SerializeCompound<'_, P>
+    where P: SerializerParams,
+{
+    type Ok = ();
+    type Error = Error;
+
+    #[inline]
+    fn serialize_element<T>(&mut self, value: &T) -> Result
+        where T: ?Sized + serde::ser::Serialize,
+    {
+        self.add_item();
+        value.serialize(&mut *self.ser)
+    }
+    #[inline]
+    fn end(self) -> Result {
+        self.end_seq();
+        Ok(())
+    }
+}
+
+impl<P> serde::ser::SerializeMap for SerializeCompound<'_, P>
     where P: SerializerParams,
 {
     type Ok = ();
     type Error = Error;
 
-    serialize_mapitem!(serialize_key);
+    #[inline]
+    fn serialize_key<T>(&mut self, value: &T) -> Result
+        where T: ?Sized + serde::ser::Serialize,
+    {
+        self.add_item();
+        value.serialize(&mut *self.ser)
+    }
     serialize_mapitem!(serialize_value);
     #[inline]
-    fn end(self) -> Result { Ok(()) }
+    fn end(self) -> Result {
+        self.end_seq();
+        Ok(())
+    }
 }
\ No newline at end of file
@@ -0,0 +1,684 @@
+//! Dynamic values described by a schema known at runtime
+//!
+//! [`Value`] holds a value of a type which is known only at runtime, for example for tools which
+//! read and write keys of any database table. Its type is described by a [`Schema`]. Values are
+//! serialized with [`Serializer`](crate::Serializer) and deserialized with
+//! [`Deserializer`](crate::Deserializer) with any serializer parameters, and encoded bytes are
+//! identical to those of the corresponding Rust type with derived `Serialize` and `Deserialize`.
+//!
+//! Schema for a Rust type maps one to one:
+//! - structs are described by [`Schema::Struct`] with field names, their values are [`Value::Tuple`]
+//!   with fields in the same order (names matter only for
+//!   [`params::SelfDescribing`](crate::params::SelfDescribing) pre-set)
+//! - enums are described by [`Schema::Enum`] with variant names and schemas of variant values:
+//!   [`Schema::Unit`] for unit variants, [`Schema::Tuple`] or [`Schema::Struct`] for tuple and
+//!   struct variants; their values are [`Value::Enum`] with variant index
+//! - [`Desc<T>`](crate::Desc) fields are described by [`Schema::Desc`] with schema of `T`, their
+//!   values are values of `T`
+//!
+//! ```
+//! # use ordcode::{ params, value::{ Schema, Value } };
+//! #[derive(serde_derive::Serialize)]
+//! enum Kind { Table, Index(String) }
+//!
+//! let schema = Schema::Tuple(vec![
+//!     Schema::U32,
+//!     Schema::Enum(vec![("Table".to_string(), Schema::Unit), ("Index".to_string(), Schema::Str)]),
+//! ]);
+//! let value = Value::Tuple(vec![Value::U32(7), Value::Enum(1, Box::new(Value::Str("ts".to_string())))]);
+//!
+//! let bytes = value.to_vec(&schema, params::AscendingOrder).unwrap();
+//! assert_eq!(bytes, ordcode::ser_to_vec_ordered(&(7_u32, Kind::Index("ts".to_string())), ordcode::Order::Ascending).unwrap());
+//! assert_eq!(Value::from_bytes(&bytes, &schema, params::AscendingOrder).unwrap(), value);
+//! ```
+//!
+//! For encoding with [`Serializer`](crate::Serializer) or calculating size with
+//! [`SizeCalc`](crate::SizeCalc), use [`Value::with_schema()`], which implements `Serialize`.
+//! For decoding with [`Deserializer`](crate::Deserializer), use `&Schema` as
+//! [`serde::de::DeserializeSeed`].
+//!
+//! `Schema` can be parsed from text (and formatted to text) with syntax similar to Rust types:
+//! primitive types are `bool`, `u8`...`u128`, `i8`...`i128`, `f32`, `f64`, `char`, `str`, `bytes`
+//! and `()`; `option<T>`, `seq<T>` and `map<K, V>` are options, sequences and maps; `(T1, T2)` are
+//! tuples, `{a: T1, b: T2}` are structs, `enum{A, B(T), C(T1, T2), D{a: T}}` are enums and `desc<T>`
+//! are values in descending order:
+//!
+//! ```
+//! # use ordcode::value::Schema;
+//! let schema: Schema = "(u32, str, enum{Table, Index{name: str, unique: bool}})".parse().unwrap();
+//! assert_eq!(schema.to_string(), "(u32, str, enum{Table, Index{name: str, unique: bool}})");
+//! ```
+
+use core::convert::TryFrom;
+use core::fmt;
+use core::marker::PhantomData;
+use serde::{ ser, de };
+use serde::ser::{ SerializeMap, SerializeSeq, SerializeTuple, SerializeTupleStruct };
+use crate::{ Result, DeBytesReader, DeBytesWriter, Deserializer, Serializer, params::SerializerParams };
+use crate::buf::ReadBytes;
+use crate::desc::{ DESC_NEWTYPE_NAME, RUNTIME_VARIANT_NAME };
+
+/// Description of a type of [`Value`], see [module documentation](self)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Schema {
+    Unit,
+    Bool,
+    U8,
+    U16,
+    U32,
+    U64,
+    U128,
+    I8,
+    I16,
+    I32,
+    I64,
+    I128,
+    F32,
+    F64,
+    Char,
+    Str,
+    Bytes,
+    Option(Box<Schema>),
+    /// Sequence (like `Vec<T>`) of values
+    Seq(Box<Schema>),
+    /// Map (like `BTreeMap<K, V>`) with key and value schemas
+    Map(Box<Schema>, Box<Schema>),
+    Tuple(Vec<Schema>),
+    /// Struct with names and schemas of fields
+    Struct(Vec<(String, Schema)>),
+    /// Enum with names and schemas of values of variants
+    Enum(Vec<(String, Schema)>),
+    /// Value in descending order (like [`Desc<T>`](crate::Desc)), its value is value of `T`
+    Desc(Box<Schema>),
+}
+
+/// Value of a type described by [`Schema`], see [module documentation](self)
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Unit,
+    Bool(bool),
+    U8(u8),
+    U16(u16),
+    U32(u32),
+    U64(u64),
+    U128(u128),
+    I8(i8),
+    I16(i16),
+    I32(i32),
+    I64(i64),
+    I128(i128),
+    F32(f32),
+    F64(f64),
+    Char(char),
+    Str(String),
+    Bytes(Vec<u8>),
+    Option(Option<Box<Value>>),
+    Seq(Vec<Value>),
+    Map(Vec<(Value, Value)>),
+    /// Value of tuple or struct, fields are in schema order
+    Tuple(Vec<Value>),
+    /// Enum variant index and variant value
+    Enum(u32, Box<Value>),
+}
+
+impl Value {
+    /// Value with its schema, which implements `Serialize` for serializer parameters `P`
+    #[must_use]
+    pub fn with_schema<'a, P>(&'a self, schema: &'a Schema, _params: P) -> SchemaValue<'a, P>
+        where P: SerializerParams,
+    {
+        SchemaValue { schema, value: self, _marker: PhantomData }
+    }
+    /// Calculate encoded size of value with `params`
+    pub fn calc_size<P>(&self, schema: &Schema, params: P) -> Result<usize>
+        where P: SerializerParams,
+    {
+        crate::calc_size(&self.with_schema(schema, params), params)
+    }
+    /// Encode value with `params`
+    pub fn to_vec<P>(&self, schema: &Schema, params: P) -> Result<Vec<u8>>
+        where P: SerializerParams,
+    {
+        let value = self.with_schema(schema, params);
+        let mut buf = vec![0_u8; crate::calc_size(&value, params)?];
+        let mut writer = DeBytesWriter::new(&mut buf);
+        ser::Serialize::serialize(&value, &mut Serializer::new(&mut writer, params))?;
+        writer.is_complete()?;
+        Ok(buf)
+    }
+    /// Decode value encoded with `params`, fails if input is not fully consumed
+    pub fn from_bytes<P>(input: &[u8], schema: &Schema, params: P) -> Result<Self>
+        where P: SerializerParams,
+    {
+        let mut reader = DeBytesReader::new(input);
+        let value = de::DeserializeSeed::deserialize(schema, &mut Deserializer::new(&mut reader, params))?;
+        reader.is_complete()?;
+        Ok(value)
+    }
+}
+
+/// [`Value`] with its [`Schema`], implements `Serialize` for serializer parameters `P`
+///
+/// Created with [`Value::with_schema()`]. Serialization fails if value does not match schema.
+pub struct SchemaValue<'a, P> {
+    schema: &'a Schema,
+    value: &'a Value,
+    _marker: PhantomData<P>,
+}
+
+impl<'a, P> SchemaValue<'a, P> {
+    fn nested(schema: &'a Schema, value: &'a Value) -> Self {
+        Self { schema, value, _marker: PhantomData }
+    }
+    fn mismatch<E: ser::Error>() -> E {
+        E::custom("value does not match schema")
+    }
+}
+
+impl<P> ser::Serialize for SchemaValue<'_, P>
+    where P: SerializerParams,
+{
+    fn serialize<S>(&self, serializer: S) -> core::result::Result<S::Ok, S::Error>
+        where S: ser::Serializer,
+    {
+        match (self.schema, self.value) {
+            (Schema::Unit, Value::Unit) => serializer.serialize_unit(),
+            (Schema::Bool, Value::Bool(v)) => serializer.serialize_bool(*v),
+            (Schema::U8, Value::U8(v)) => serializer.serialize_u8(*v),
+            (Schema::U16, Value::U16(v)) => serializer.serialize_u16(*v),
+            (Schema::U32, Value::U32(v)) => serializer.serialize_u32(*v),
+            (Schema::U64, Value::U64(v)) => serializer.serialize_u64(*v),
+            (Schema::U128, Value::U128(v)) => serializer.serialize_u128(*v),
+            (Schema::I8, Value::I8(v)) => serializer.serialize_i8(*v),
+            (Schema::I16, Value::I16(v)) => serializer.serialize_i16(*v),
+            (Schema::I32, Value::I32(v)) => serializer.serialize_i32(*v),
+            (Schema::I64, Value::I64(v)) => serializer.serialize_i64(*v),
+            (Schema::I128, Value::I128(v)) => serializer.serialize_i128(*v),
+            (Schema::F32, Value::F32(v)) => serializer.serialize_f32(*v),
+            (Schema::F64, Value::F64(v)) => serializer.serialize_f64(*v),
+            (Schema::Char, Value::Char(v)) => serializer.serialize_char(*v),
+            (Schema::Str, Value::Str(v)) => serializer.serialize_str(v),
+            (Schema::Bytes, Value::Bytes(v)) => serializer.serialize_bytes(v),
+            (Schema::Option(_), Value::Option(None)) => serializer.serialize_none(),
+            (Schema::Option(schema), Value::Option(Some(v))) => serializer.serialize_some(&Self::nested(schema, v)),
+            (Schema::Seq(schema), Value::Seq(items)) => {
+                let mut seq = serializer.serialize_seq(Some(items.len()))?;
+                for item in items {
+                    seq.serialize_element(&Self::nested(schema, item))?;
+                }
+                seq.end()
+            },
+            (Schema::Map(key_schema, value_schema), Value::Map(entries)) => {
+                let mut map = serializer.serialize_map(Some(entries.len()))?;
+                for (k, v) in entries {
+                    map.serialize_entry(&Self::nested(key_schema, k), &Self::nested(value_schema, v))?;
+                }
+                map.end()
+            },
+            (Schema::Tuple(schemas), Value::Tuple(fields)) if schemas.len() == fields.len() => {
+                let mut tuple = serializer.serialize_tuple(fields.len())?;
+                for (schema, field) in schemas.iter().zip(fields) {
+                    tuple.serialize_element(&Self::nested(schema, field))?;
+                }
+                tuple.end()
+            },
+            // field names are `&'static str` in `serde` API, self-describing format encodes
+            // structs same as maps with string keys, and other formats same as tuples
+            (Schema::Struct(schemas), Value::Tuple(fields)) if schemas.len() == fields.len() => {
+                if P::SELF_DESCRIBING {
+                    let mut map = serializer.serialize_map(Some(fields.len()))?;
+                    for ((name, schema), field) in schemas.iter().zip(fields) {
+                        map.serialize_entry(name.as_str(), &Self::nested(schema, field))?;
+                    }
+                    map.end()
+                } else {
+                    let mut tuple = serializer.serialize_tuple(fields.len())?;
+                    for ((_, schema), field) in schemas.iter().zip(fields) {
+                        tuple.serialize_element(&Self::nested(schema, field))?;
+                    }
+                    tuple.end()
+                }
+            },
+            (Schema::Enum(variants), Value::Enum(index, value)) => {
+                let (name, schema) = usize::try_from(*index).ok()
+                    .and_then(|i| variants.get(i))
+                    .ok_or_else(Self::mismatch::<S::Error>)?;
+                // values of unit, tuple and struct variants are encoded same as unit, tuples and
+                // structs, so all variants can be serialized as newtype variants
+                if P::SELF_DESCRIBING {
+                    let mut variant = serializer.serialize_tuple_struct(RUNTIME_VARIANT_NAME, 2)?;
+                    variant.serialize_field(name.as_str())?;
+                    variant.serialize_field(&Self::nested(schema, value))?;
+                    variant.end()
+                } else {
+                    serializer.serialize_newtype_variant("", *index, "", &Self::nested(schema, value))
+                }
+            },
+            (Schema::Desc(schema), value) => crate::desc::serialize(&Self::nested(schema, value), serializer),
+            _ => Err(Self::mismatch()),
+        }
+    }
+}
+
+impl<'de> de::DeserializeSeed<'de> for &Schema {
+    type Value = Value;
+
+    fn deserialize<D>(self, deserializer: D) -> core::result::Result<Value, D::Error>
+        where D: de::Deserializer<'de>,
+    {
+        let visitor = SchemaVisitor(self);
+        match self {
+            Schema::Unit => deserializer.deserialize_unit(visitor),
+            Schema::Bool => deserializer.deserialize_bool(visitor),
+            Schema::U8 => deserializer.deserialize_u8(visitor),
+            Schema::U16 => deserializer.deserialize_u16(visitor),
+            Schema::U32 => deserializer.deserialize_u32(visitor),
+            Schema::U64 => deserializer.deserialize_u64(visitor),
+            Schema::U128 => deserializer.deserialize_u128(visitor),
+            Schema::I8 => deserializer.deserialize_i8(visitor),
+            Schema::I16 => deserializer.deserialize_i16(visitor),
+            Schema::I32 => deserializer.deserialize_i32(visitor),
+            Schema::I64 => deserializer.deserialize_i64(visitor),
+            Schema::I128 => deserializer.deserialize_i128(visitor),
+            Schema::F32 => deserializer.deserialize_f32(visitor),
+            Schema::F64 => deserializer.deserialize_f64(visitor),
+            Schema::Char => deserializer.deserialize_char(visitor),
+            Schema::Str => deserializer.deserialize_string(visitor),
+            Schema::Bytes => deserializer.deserialize_byte_buf(visitor),
+            Schema::Option(_) => deserializer.deserialize_option(visitor),
+            Schema::Seq(_) => deserializer.deserialize_seq(visitor),
+            Schema::Map(_, _) => deserializer.deserialize_map(visitor),
+            Schema::Tuple(fields) => deserializer.deserialize_tuple(fields.len(), visitor),
+            // same as with serialization, structs are tuples or maps (self-describing format)
+            Schema::Struct(fields) => deserializer.deserialize_tuple(fields.len(), visitor),
+            Schema::Enum(_) => deserializer.deserialize_enum("", &[], visitor),
+            Schema::Desc(_) => deserializer.deserialize_newtype_struct(DESC_NEWTYPE_NAME, visitor),
+        }
+    }
+}
+
+struct SchemaVisitor<'a>(&'a Schema);
+
+macro_rules! visit_fn {
+    ($fn:ident, $t:ty, $variant:ident, $unexpected:expr) => {
+        fn $fn<E: de::Error>(self, v: $t) -> core::result::Result<Value, E> {
+            match self.0 {
+                Schema::$variant => Ok(Value::$variant(v)),
+                _ => Err(E::invalid_type($unexpected(v), &self)),
+            }
+        }
+    };
+}
+
+impl<'de> de::Visitor<'de> for SchemaVisitor<'_> {
+    type Value = Value;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "value of schema {:?}", self.0)
+    }
+
+    visit_fn!(visit_bool, bool, Bool, de::Unexpected::Bool);
+    visit_fn!(visit_u8, u8, U8, |v| de::Unexpected::Unsigned(u64::from(v)));
+    visit_fn!(visit_u16, u16, U16, |v| de::Unexpected::Unsigned(u64::from(v)));
+    visit_fn!(visit_u32, u32, U32, |v| de::Unexpected::Unsigned(u64::from(v)));
+    visit_fn!(visit_u64, u64, U64, de::Unexpected::Unsigned);
+    visit_fn!(visit_u128, u128, U128, |_| de::Unexpected::Other("u128"));
+    visit_fn!(visit_i8, i8, I8, |v| de::Unexpected::Signed(i64::from(v)));
+    visit_fn!(visit_i16, i16, I16, |v| de::Unexpected::Signed(i64::from(v)));
+    visit_fn!(visit_i32, i32, I32, |v| de::Unexpected::Signed(i64::from(v)));
+    visit_fn!(visit_i64, i64, I64, de::Unexpected::Signed);
+    visit_fn!(visit_i128, i128, I128, |_| de::Unexpected::Other("i128"));
+    visit_fn!(visit_f32, f32, F32, |v| de::Unexpected::Float(f64::from(v)));
+    visit_fn!(visit_f64, f64, F64, de::Unexpected::Float);
+    visit_fn!(visit_char, char, Char, de::Unexpected::Char);
+    visit_fn!(visit_string, String, Str, |v: String| de::Unexpected::Other(if v.is_empty() { "empty string" } else { "string" }));
+    visit_fn!(visit_byte_buf, Vec<u8>, Bytes, |_| de::Unexpected::Other("byte array"));
+
+    fn visit_str<E: de::Error>(self, v: &str) -> core::result::Result<Value, E> {
+        self.visit_string(v.to_string())
+    }
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> core::result::Result<Value, E> {
+        self.visit_byte_buf(v.to_vec())
+    }
+    fn visit_unit<E: de::Error>(self) -> core::result::Result<Value, E> {
+        match self.0 {
+            Schema::Unit => Ok(Value::Unit),
+            _ => Err(E::invalid_type(de::Unexpected::Unit, &self)),
+        }
+    }
+    fn visit_none<E: de::Error>(self) -> core::result::Result<Value, E> {
+        match self.0 {
+            Schema::Option(_) => Ok(Value::Option(None)),
+            _ => Err(E::invalid_type(de::Unexpected::Option, &self)),
+        }
+    }
+    fn visit_some<D>(self, deserializer: D) -> core::result::Result<Value, D::Error>
+        where D: de::Deserializer<'de>,
+    {
+        match self.0 {
+            Schema::Option(schema) => Ok(Value::Option(Some(Box::new(de::DeserializeSeed::deserialize(&**schema, deserializer)?)))),
+            _ => Err(de::Error::invalid_type(de::Unexpected::Option, &self)),
+        }
+    }
+    fn visit_newtype_struct<D>(self, deserializer: D) -> core::result::Result<Value, D::Error>
+        where D: de::Deserializer<'de>,
+    {
+        match self.0 {
+            Schema::Desc(schema) => de::DeserializeSeed::deserialize(&**schema, deserializer),
+            _ => Err(de::Error::invalid_type(de::Unexpected::NewtypeStruct, &self)),
+        }
+    }
+    fn visit_seq<A>(self, mut seq: A) -> core::result::Result<Value, A::Error>
+        where A: de::SeqAccess<'de>,
+    {
+        match self.0 {
+            Schema::Seq(schema) => {
+                let mut items = Vec::new();
+                while let Some(item) = seq.next_element_seed(&**schema)? {
+                    items.push(item);
+                }
+                Ok(Value::Seq(items))
+            },
+            Schema::Tuple(schemas) => {
+                let mut fields = Vec::with_capacity(schemas.len());
+                for (i, schema) in schemas.iter().enumerate() {
+                    fields.push(seq.next_element_seed(schema)?.ok_or_else(|| de::Error::invalid_length(i, &self))?);
+                }
+                Ok(Value::Tuple(fields))
+            },
+            Schema::Struct(schemas) => {
+                let mut fields = Vec::with_capacity(schemas.len());
+                for (i, (_, schema)) in schemas.iter().enumerate() {
+                    fields.push(seq.next_element_seed(schema)?.ok_or_else(|| de::Error::invalid_length(i, &self))?);
+                }
+                Ok(Value::Tuple(fields))
+            },
+            _ => Err(de::Error::invalid_type(de::Unexpected::Seq, &self)),
+        }
+    }
+    fn visit_map<A>(self, mut map: A) -> core::result::Result<Value, A::Error>
+        where A: de::MapAccess<'de>,
+    {
+        match self.0 {
+            Schema::Map(key_schema, value_schema) => {
+                let mut entries = Vec::new();
+                while let Some(key) = map.next_key_seed(&**key_schema)? {
+                    entries.push((key, map.next_value_seed(&**value_schema)?));
+                }
+                Ok(Value::Map(entries))
+            },
+            // struct in self-describing format, fields may be in any order, unknown fields are skipped
+            Schema::Struct(schemas) => {
+                let mut fields = vec![None; schemas.len()];
+                while let Some(name) = map.next_key::<String>()? {
+                    match schemas.iter().position(|(field, _)| *field == name) {
+                        Some(i) if fields[i].is_none() => fields[i] = Some(map.next_value_seed(&schemas[i].1)?),
+                        Some(_) => return Err(de::Error::custom(format_args!("duplicate field `{name}`"))),
+                        None => { map.next_value::<de::IgnoredAny>()?; },
+                    }
+                }
+                fields.into_iter().zip(schemas).map(|(field, (name, _))| {
+                    field.ok_or_else(|| de::Error::custom(format_args!("missing field `{name}`")))
+                }).collect::<core::result::Result<_, _>>().map(Value::Tuple)
+            },
+            _ => Err(de::Error::invalid_type(de::Unexpected::Map, &self)),
+        }
+    }
+    fn visit_enum<A>(self, data: A) -> core::result::Result<Value, A::Error>
+        where A: de::EnumAccess<'de>,
+    {
+        match self.0 {
+            Schema::Enum(variants) => {
+                let (index, variant) = data.variant_seed(VariantSeed(variants))?;
+                let schema = variants.get(index as usize).map(|(_, schema)| schema)
+                    .ok_or_else(|| de::Error::custom("invalid variant index"))?;
+                // see `SchemaValue::serialize()`
+                let value = de::VariantAccess::newtype_variant_seed(variant, schema)?;
+                Ok(Value::Enum(index, Box::new(value)))
+            },
+            _ => Err(de::Error::invalid_type(de::Unexpected::Enum, &self)),
+        }
+    }
+}
+
+// Enum variant identifier: index, or name for self-describing format
+struct VariantSeed<'a>(&'a [(String, Schema)]);
+
+impl<'de> de::DeserializeSeed<'de> for VariantSeed<'_> {
+    type Value = u32;
+
+    fn deserialize<D>(self, deserializer: D) -> core::result::Result<u32, D::Error>
+        where D: de::Deserializer<'de>,
+    {
+        deserializer.deserialize_identifier(self)
+    }
+}
+
+impl de::Visitor<'_> for VariantSeed<'_> {
+    type Value = u32;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("enum variant index or name")
+    }
+    fn visit_u32<E: de::Error>(self, v: u32) -> core::result::Result<u32, E> {
+        match usize::try_from(v) {
+            Ok(index) if index < self.0.len() => Ok(v),
+            _ => Err(E::invalid_value(de::Unexpected::Unsigned(u64::from(v)), &self)),
+        }
+    }
+    fn visit_u64<E: de::Error>(self, v: u64) -> core::result::Result<u32, E> {
+        let index = u32::try_from(v).map_err(|_| E::invalid_value(de::Unexpected::Unsigned(v), &self))?;
+        self.visit_u32(index)
+    }
+    fn visit_str<E: de::Error>(self, v: &str) -> core::result::Result<u32, E> {
+        self.0.iter().position(|(name, _)| name == v)
+            .and_then(|index| u32::try_from(index).ok())
+            .ok_or_else(|| E::unknown_variant(v, &[]))
+    }
+}
+
+// Text syntax of schema, see module documentation
+
+macro_rules! primitive_names {
+    ($($name:literal $variant:ident),*) => {
+        fn primitive_schema(name: &str) -> Option<Schema> {
+            match name {
+                $($name => Some(Schema::$variant),)*
+                _ => None,
+            }
+        }
+        fn primitive_name(schema: &Schema) -> Option<&'static str> {
+            match schema {
+                $(Schema::$variant => Some($name),)*
+                _ => None,
+            }
+        }
+    };
+}
+
+primitive_names!("bool" Bool, "u8" U8, "u16" U16, "u32" U32, "u64" U64, "u128" U128,
+    "i8" I8, "i16" I16, "i32" I32, "i64" I64, "i128" I128, "f32" F32, "f64" F64,
+    "char" Char, "str" Str, "bytes" Bytes);
+
+impl core::str::FromStr for Schema {
+    type Err = crate::Error;
+
+    /// Parse schema from text, see [module documentation](self)
+    fn from_str(s: &str) -> Result<Self> {
+        let mut parser = SchemaParser { input: s, pos: 0 };
+        let schema = parser.schema()?;
+        if parser.peek().is_some() {
+            return Err(parser.error("unexpected input"));
+        }
+        Ok(schema)
+    }
+}
+
+struct SchemaParser<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl SchemaParser<'_> {
+    fn error(&self, msg: &str) -> crate::Error {
+        crate::Error::Custom(crate::ErrorMessage::new(&format_args!("invalid schema: {msg} at position {}", self.pos)))
+    }
+    // Next non-whitespace character
+    fn peek(&mut self) -> Option<char> {
+        let rest = &self.input[self.pos..];
+        self.pos += rest.len() - rest.trim_start().len();
+        self.input[self.pos..].chars().next()
+    }
+    fn accept(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += c.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+    fn expect(&mut self, c: char) -> Result {
+        if self.accept(c) { Ok(()) } else { Err(self.error(&format!("expected `{c}`"))) }
+    }
+    fn ident(&mut self) -> Result<&str> {
+        self.peek();
+        let start = self.pos;
+        let rest = &self.input[start..];
+        let len = rest.find(|c: char| !(c.is_alphanumeric() || c == '_')).unwrap_or(rest.len());
+        if len == 0 {
+            return Err(self.error("expected name"));
+        }
+        self.pos += len;
+        Ok(&self.input[start..self.pos])
+    }
+    // Comma-separated items until `close`, trailing comma is allowed
+    fn list<T>(&mut self, close: char, mut item: impl FnMut(&mut Self) -> Result<T>) -> Result<Vec<T>> {
+        let mut items = Vec::new();
+        while !self.accept(close) {
+            items.push(item(self)?);
+            if !self.accept(',') {
+                self.expect(close)?;
+                break;
+            }
+        }
+        Ok(items)
+    }
+    fn fields(&mut self) -> Result<Vec<(String, Schema)>> {
+        self.list('}', |p| {
+            let name = p.ident()?.to_string();
+            p.expect(':')?;
+            Ok((name, p.schema()?))
+        })
+    }
+    fn schema(&mut self) -> Result<Schema> {
+        if self.accept('(') {
+            let items = self.list(')', Self::schema)?;
+            return Ok(if items.is_empty() { Schema::Unit } else { Schema::Tuple(items) });
+        }
+        if self.accept('{') {
+            return Ok(Schema::Struct(self.fields()?));
+        }
+        let start = self.pos;
+        let name = self.ident()?;
+        if let Some(schema) = primitive_schema(name) {
+            return Ok(schema);
+        }
+        match name {
+            "option" | "seq" | "desc" => {
+                let name = name.to_string();
+                self.expect('<')?;
+                let item = Box::new(self.schema()?);
+                self.expect('>')?;
+                Ok(match name.as_str() {
+                    "option" => Schema::Option(item),
+                    "seq" => Schema::Seq(item),
+                    _ => Schema::Desc(item),
+                })
+            },
+            "map" => {
+                self.expect('<')?;
+                let key = Box::new(self.schema()?);
+                self.expect(',')?;
+                let value = Box::new(self.schema()?);
+                self.expect('>')?;
+                Ok(Schema::Map(key, value))
+            },
+            "enum" => {
+                self.expect('{')?;
+                Ok(Schema::Enum(self.list('}', Self::variant)?))
+            },
+            _ => {
+                self.pos = start;
+                Err(self.error("unknown type"))
+            },
+        }
+    }
+    fn variant(&mut self) -> Result<(String, Schema)> {
+        let name = self.ident()?.to_string();
+        let schema = if self.accept('(') {
+            let mut items = self.list(')', Self::schema)?;
+            match items.len() {
+                0 => Schema::Unit,
+                1 => items.remove(0),
+                _ => Schema::Tuple(items),
+            }
+        } else if self.accept('{') {
+            Schema::Struct(self.fields()?)
+        } else {
+            Schema::Unit
+        };
+        Ok((name, schema))
+    }
+}
+
+fn write_list<T>(f: &mut fmt::Formatter<'_>, items: &[T], mut item: impl FnMut(&mut fmt::Formatter<'_>, &T) -> fmt::Result) -> fmt::Result {
+    for (i, v) in items.iter().enumerate() {
+        if i > 0 {
+            f.write_str(", ")?;
+        }
+        item(f, v)?;
+    }
+    Ok(())
+}
+
+fn write_fields(f: &mut fmt::Formatter<'_>, fields: &[(String, Schema)]) -> fmt::Result {
+    f.write_str("{")?;
+    write_list(f, fields, |f, (name, schema)| write!(f, "{name}: {schema}"))?;
+    f.write_str("}")
+}
+
+impl fmt::Display for Schema {
+    /// Format schema in text syntax, see [module documentation](self)
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(name) = primitive_name(self) {
+            return f.write_str(name);
+        }
+        match self {
+            Schema::Option(item) => write!(f, "option<{item}>"),
+            Schema::Seq(item) => write!(f, "seq<{item}>"),
+            Schema::Desc(item) => write!(f, "desc<{item}>"),
+            Schema::Map(key, value) => write!(f, "map<{key}, {value}>"),
+            Schema::Tuple(items) => {
+                f.write_str("(")?;
+                write_list(f, items, |f, item| write!(f, "{item}"))?;
+                f.write_str(")")
+            },
+            Schema::Struct(fields) => write_fields(f, fields),
+            Schema::Enum(variants) => {
+                f.write_str("enum{")?;
+                write_list(f, variants, |f, (name, schema)| {
+                    f.write_str(name)?;
+                    match schema {
+                        Schema::Unit => Ok(()),
+                        Schema::Struct(fields) => write_fields(f, fields),
+                        // tuple variant, parenthesized single-item tuple is tuple of one item
+                        Schema::Tuple(items) if items.len() > 1 => write!(f, "{schema}"),
+                        schema => write!(f, "({schema})"),
+                    }
+                })?;
+                f.write_str("}")
+            },
+            _ => f.write_str("()"),
+        }
+    }
+}
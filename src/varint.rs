@@ -2,6 +2,21 @@
 //! Fast variable length serialization of unsigned integers with [`VarUInt`] trait.
 //!
 //! This trait is implemented by this crate for [`u32`], [`u64`] integer types.
+//!
+//! [`VarUInt`] encoding is little-endian and does not preserve lexicographic ordering of values.
+//! [`OrdVarUInt`] trait provides big-endian, order-preserving encoding (same as in SQLite4):
+//! first byte of encoded value determines its length, and encoded values compare
+//! lexicographically in the same order as the integers they represent.
+//!
+//! | First byte  | Length | Value                                   |
+//! |-------------|--------|-----------------------------------------|
+//! | 0..=240     | 1      | `A0`                                    |
+//! | 241..=248   | 2      | `240 + 256 * (A0 - 241) + A1`           |
+//! | 249         | 3      | `2288 + 256 * A1 + A2`                  |
+//! | 250..=255   | 4..=9  | `A0 - 247` bytes which follow, big-endian |
+//!
+//! [`OrdVarInt`] trait provides order-preserving encoding for signed integers.
+use core::convert::TryFrom;
 use crate::{buf::{ReadBytes, WriteBytes, TailReadBytes, TailWriteBytes, WriteToTail, ReadFromTail},
             params::LengthEncoder, Result, Error};
 
@@ -152,7 +167,7 @@ fn varu64_decode(varu_encoded_length: u8, first_byte: u8, bytes: &[u8]) -> Resul
     } else {
         encoded[0] = first_byte;
         let len = varu_encoded_length as usize;
-        encoded[1..len as usize].copy_from_slice(&bytes[..len-1]);
+        encoded[1..len].copy_from_slice(&bytes[..len-1]);
         u64::from_le_bytes(encoded) >> varu_encoded_length
     };
     #[cfg(debug_assertions)]
@@ -187,6 +202,289 @@ fn varu32_decode(varu_encoded_length: u8, first_byte: u8, bytes: &[u8]) -> Resul
     Ok(result)
 }
 
+/// Methods for variable length, order-preserving serialization of unsigned integers
+///
+/// See [module documentation](self) for encoding details.
+pub trait OrdVarUInt: Sized {
+    /// Get the length of an varint-encoded value in bytes
+    fn ord_varu_encoded_len(&self) -> u8;
+
+    /// Get the byte length of varint-encoded value from the first byte
+    fn ord_varu_decoded_len(first_byte: u8) -> u8;
+
+    /// Encode as variable length integer to `writer`
+    fn ord_varu_to_writer(&self, writer: impl WriteBytes) -> Result;
+
+    /// Read variable length integer from `reader`
+    fn ord_varu_from_reader(reader: impl ReadBytes) -> Result<Self>;
+
+    /// Decode variable length integer from slice
+    fn ord_varu_from_slice(bytes: &[u8]) -> Result<(Self, u8)>;
+
+    /// Encode variable length integer into slice
+    ///
+    /// Slice must be of enough length, and can be calculated with `ord_varu_encoded_len()`.
+    /// Returns actual length of encoded varint.
+    fn ord_varu_to_slice(&self, bytes: &mut [u8]) -> u8;
+}
+
+impl OrdVarUInt for u64 {
+    #[inline]
+    fn ord_varu_encoded_len(&self) -> u8 {
+        #![allow(clippy::cast_possible_truncation)]
+        match *self {
+            0..=240 => 1,
+            241..=2287 => 2,
+            2288..=67823 => 3,
+            v => (9 - v.leading_zeros() / 8) as u8,
+        }
+    }
+    #[inline]
+    fn ord_varu_decoded_len(first_byte: u8) -> u8 {
+        match first_byte {
+            0..=240 => 1,
+            241..=248 => 2,
+            249 => 3,
+            b => b - 246,
+        }
+    }
+    #[inline]
+    fn ord_varu_to_writer(&self, mut writer: impl WriteBytes) -> Result {
+        let mut bytes = [0_u8; 9];
+        let length = self.ord_varu_to_slice(&mut bytes);
+        writer.write(&[bytes[0]])?;
+        writer.write(&bytes[1..length as usize])
+    }
+    #[inline]
+    fn ord_varu_from_reader(mut reader: impl ReadBytes) -> Result<Self> {
+        let (first_byte, decoded_len) = reader.read(1, |buf| {
+            Ok((buf[0], Self::ord_varu_decoded_len(buf[0])))
+        })?;
+        reader.read((decoded_len - 1) as usize, |buf| {
+            ord_varu64_decode(decoded_len, first_byte, buf)
+        })
+    }
+    #[inline]
+    fn ord_varu_from_slice(bytes: &[u8]) -> Result<(Self, u8)> {
+        if bytes.is_empty() {
+            return Err(Error::PrematureEndOfInput);
+        }
+        let decoded_len = Self::ord_varu_decoded_len(bytes[0]);
+        Ok((ord_varu64_decode(decoded_len, bytes[0], &bytes[1..])?, decoded_len))
+    }
+    #[inline]
+    #[allow(clippy::cast_possible_truncation)]
+    fn ord_varu_to_slice(&self, bytes: &mut [u8]) -> u8 {
+        let length = self.ord_varu_encoded_len();
+        match length {
+            1 => bytes[0] = *self as u8,
+            2 => {
+                let v = *self - 240;
+                bytes[0] = (v >> 8) as u8 + 241;
+                bytes[1] = v as u8;
+            },
+            3 => {
+                let v = *self - 2288;
+                bytes[0] = 249;
+                bytes[1] = (v >> 8) as u8;
+                bytes[2] = v as u8;
+            },
+            _ => {
+                let len = length as usize;
+                bytes[0] = 246 + length;
+                bytes[1..len].copy_from_slice(&self.to_be_bytes()[9 - len..]);
+            },
+        }
+        length
+    }
+}
+
+impl OrdVarUInt for u32 {
+    #[inline]
+    fn ord_varu_encoded_len(&self) -> u8 {
+        u64::from(*self).ord_varu_encoded_len()
+    }
+    #[inline]
+    fn ord_varu_decoded_len(first_byte: u8) -> u8 {
+        <u64>::ord_varu_decoded_len(first_byte)
+    }
+    #[inline]
+    fn ord_varu_to_writer(&self, writer: impl WriteBytes) -> Result {
+        u64::from(*self).ord_varu_to_writer(writer)
+    }
+    #[inline]
+    fn ord_varu_from_reader(mut reader: impl ReadBytes) -> Result<Self> {
+        let (first_byte, decoded_len) = reader.read(1, |buf| {
+            Ok((buf[0], Self::ord_varu_decoded_len(buf[0])))
+        })?;
+        if decoded_len <= 5 {
+            reader.read((decoded_len - 1) as usize, |buf| {
+                ord_varu32_decode(decoded_len, first_byte, buf)
+            })
+        } else {
+            Err(Error::InvalidVarintEncoding)
+        }
+    }
+    #[inline]
+    fn ord_varu_from_slice(bytes: &[u8]) -> Result<(Self, u8)> {
+        if bytes.is_empty() {
+            return Err(Error::PrematureEndOfInput);
+        }
+        let decoded_len = Self::ord_varu_decoded_len(bytes[0]);
+        if decoded_len <= 5 {
+            Ok((ord_varu32_decode(decoded_len, bytes[0], &bytes[1..])?, decoded_len))
+        } else {
+            Err(Error::InvalidVarintEncoding)
+        }
+    }
+    #[inline]
+    fn ord_varu_to_slice(&self, bytes: &mut [u8]) -> u8 {
+        u64::from(*self).ord_varu_to_slice(bytes)
+    }
+}
+
+// Decode order-preserving variable length bytes into `u64`, when decoded length is known
+// from previous call of `ord_varu_decoded_len()`
+#[inline]
+fn ord_varu64_decode(encoded_len: u8, first_byte: u8, bytes: &[u8]) -> Result<u64> {
+    let len = encoded_len as usize;
+    if bytes.len() + 1 < len {
+        return Err(Error::PrematureEndOfInput);
+    }
+    let result = match encoded_len {
+        1 => u64::from(first_byte),
+        2 => 240 + (u64::from(first_byte - 241) << 8) + u64::from(bytes[0]),
+        3 => 2288 + (u64::from(bytes[0]) << 8) + u64::from(bytes[1]),
+        _ => {
+            let mut encoded = [0_u8; 8];
+            encoded[9 - len..].copy_from_slice(&bytes[..len - 1]);
+            u64::from_be_bytes(encoded)
+        },
+    };
+    // unlike `VarUInt`, overlong encodings are rejected in release builds too
+    if (encoded_len == 4 && result < 67824) || (encoded_len > 4 && result < 1 << (8 * (len - 2))) {
+        return Err(Error::InvalidVarintEncoding);
+    }
+    Ok(result)
+}
+
+// Decode order-preserving variable length bytes into `u32`, `encoded_len` must be less or equal to 5.
+#[inline]
+#[allow(clippy::cast_possible_truncation)] // can't happen because of encoded length
+fn ord_varu32_decode(encoded_len: u8, first_byte: u8, bytes: &[u8]) -> Result<u32> {
+    ord_varu64_decode(encoded_len, first_byte, bytes).map(|v| v as u32)
+}
+
+/// Methods for variable length, order-preserving serialization of signed integers
+///
+/// Non-negative values are encoded with the first byte in range `0x80..=0xFF`: values `0..=119`
+/// take single byte `0x80 + v`, larger values are encoded as byte `0xF7 + n` followed by `n` bytes
+/// of big-endian offset from the smallest value which takes `n` bytes. Negative value `v` is encoded
+/// as bitwise complement of encoding of `!v` (that is, `-v - 1`), so it takes as much space
+/// as its absolute value, and sorts before all non-negative values.
+pub trait OrdVarInt: Sized {
+    /// Get the length of an varint-encoded value in bytes
+    fn ord_vari_encoded_len(&self) -> u8;
+
+    /// Get the byte length of varint-encoded value from the first byte
+    fn ord_vari_decoded_len(first_byte: u8) -> u8;
+
+    /// Encode as variable length integer to `writer`
+    fn ord_vari_to_writer(&self, writer: impl WriteBytes) -> Result;
+
+    /// Read variable length integer from `reader`
+    fn ord_vari_from_reader(reader: impl ReadBytes) -> Result<Self>;
+}
+
+// Smallest absolute values which are encoded with `n` bytes following the first byte
+const ORD_VARI_LOWER: [u64; 9] = [ 0, 120, 376, 65_912, 16_843_128, 4_311_810_424, 1_103_823_438_200,
+    282_578_800_148_856, 72_340_172_838_076_792 ];
+
+// Split value into absolute value and sign, as encoded by `OrdVarInt`
+#[inline]
+#[allow(clippy::cast_sign_loss)]
+fn ord_vari_split(v: i64) -> (u64, bool) {
+    if v < 0 { (!v as u64, true) } else { (v as u64, false) }
+}
+
+// Number of bytes which follow the first byte
+#[inline]
+fn ord_vari_tail_len(abs: u64) -> usize {
+    ORD_VARI_LOWER[1..].iter().take_while(|lo| abs >= **lo).count()
+}
+
+impl OrdVarInt for i64 {
+    #[inline]
+    #[allow(clippy::cast_possible_truncation)]
+    fn ord_vari_encoded_len(&self) -> u8 {
+        ord_vari_tail_len(ord_vari_split(*self).0) as u8 + 1
+    }
+    #[inline]
+    fn ord_vari_decoded_len(first_byte: u8) -> u8 {
+        let b = if first_byte & 0x80 == 0 { !first_byte } else { first_byte };
+        if b < 0xF8 { 1 } else { b - 0xF6 }
+    }
+    #[inline]
+    #[allow(clippy::cast_possible_truncation)]
+    fn ord_vari_to_writer(&self, mut writer: impl WriteBytes) -> Result {
+        let (abs, negative) = ord_vari_split(*self);
+        let n = ord_vari_tail_len(abs);
+        let mut bytes = [0_u8; 9];
+        if n == 0 {
+            bytes[0] = 0x80 | abs as u8;
+        } else {
+            bytes[0] = 0xF7 + n as u8;
+            bytes[1..=n].copy_from_slice(&(abs - ORD_VARI_LOWER[n]).to_be_bytes()[8 - n..]);
+        }
+        if negative {
+            crate::primitives::invert_buffer(&mut bytes[..=n]);
+        }
+        writer.write(&[bytes[0]])?;
+        writer.write(&bytes[1..=n])
+    }
+    #[inline]
+    fn ord_vari_from_reader(mut reader: impl ReadBytes) -> Result<Self> {
+        let (first_byte, negative) = reader.read(1, |buf| Ok((buf[0], buf[0] & 0x80 == 0)))?;
+        let first_byte = if negative { !first_byte } else { first_byte };
+        let abs = if first_byte < 0xF8 {
+            u64::from(first_byte & 0x7F)
+        } else {
+            let n = (first_byte - 0xF7) as usize;
+            let offset = reader.read(n, |buf| {
+                let mut encoded = [0_u8; 8];
+                encoded[8 - n..].copy_from_slice(buf);
+                if negative {
+                    crate::primitives::invert_buffer(&mut encoded[8 - n..]);
+                }
+                Ok(u64::from_be_bytes(encoded))
+            })?;
+            ORD_VARI_LOWER[n].checked_add(offset).ok_or(Error::InvalidVarintEncoding)?
+        };
+        let abs = i64::try_from(abs).map_err(|_| Error::InvalidVarintEncoding)?;
+        Ok(if negative { !abs } else { abs })
+    }
+}
+
+impl OrdVarInt for i32 {
+    #[inline]
+    fn ord_vari_encoded_len(&self) -> u8 {
+        i64::from(*self).ord_vari_encoded_len()
+    }
+    #[inline]
+    fn ord_vari_decoded_len(first_byte: u8) -> u8 {
+        <i64>::ord_vari_decoded_len(first_byte)
+    }
+    #[inline]
+    fn ord_vari_to_writer(&self, writer: impl WriteBytes) -> Result {
+        i64::from(*self).ord_vari_to_writer(writer)
+    }
+    #[inline]
+    fn ord_vari_from_reader(reader: impl ReadBytes) -> Result<Self> {
+        let value = <i64>::ord_vari_from_reader(reader)?;
+        Self::try_from(value).map_err(|_| Error::InvalidVarintEncoding)
+    }
+}
+
 // Note the 32 and 64 bit versions below are binary compatible: 64-bit version can read
 // data written by 32-bit encoder, but not vice versa
 
@@ -196,6 +494,7 @@ pub struct VarIntTailLenEncoder;
 #[cfg(target_pointer_width = "64")]
 impl LengthEncoder for VarIntTailLenEncoder {
     type Value = usize;
+    const TAIL: bool = true;
 
     #[inline]
     fn calc_size(value: Self::Value) -> usize {
@@ -216,6 +515,7 @@ impl LengthEncoder for VarIntTailLenEncoder {
 #[allow(clippy::cast_possible_truncation)] // can't happen because of cfg
 impl LengthEncoder for VarIntTailLenEncoder {
     type Value = usize;
+    const TAIL: bool = true;
 
     #[inline]
     fn calc_size(value: Self::Value) -> usize {
@@ -293,4 +593,24 @@ impl LengthEncoder for VarIntDiscrEncoder {
     fn write(writer: impl TailWriteBytes, value: Self::Value) -> Result {
         value.varu_to_writer(writer)
     }
-}
\ No newline at end of file
+}
+/// Order-preserving variable-length encoding for enum discriminants, see [`OrdVarUInt`]
+pub struct OrdVarIntDiscrEncoder;
+
+impl LengthEncoder for OrdVarIntDiscrEncoder {
+    type Value = u32;
+
+    #[inline]
+    fn calc_size(value: Self::Value) -> usize {
+        value.ord_varu_encoded_len() as usize
+    }
+    #[inline]
+    fn read(reader: impl TailReadBytes) -> Result<Self::Value> {
+        <u32>::ord_varu_from_reader(reader)
+    }
+    #[inline]
+    fn write(writer: impl TailWriteBytes, value: Self::Value) -> Result {
+        value.ord_varu_to_writer(writer)
+    }
+}
+
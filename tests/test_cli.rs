@@ -0,0 +1,49 @@
+#![cfg(feature = "cli")]
+
+use std::process::{ Command, Output };
+
+fn ordcode(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_ordcode")).args(args).output().unwrap()
+}
+
+fn stdout(args: &[&str]) -> String {
+    let output = ordcode(args);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn test_cli_roundtrip() {
+    let schema = "(u32, str, seq<u8>)";
+    assert_eq!(stdout(&["encode", "-s", schema, r#"[7, "ab", [1, 2]]"#]), "00000007616201020505\n");
+    assert_eq!(stdout(&["decode", "-s", schema, "00000007616201020505"]), "[7,\"ab\",[1,2]]\n");
+
+    let key = stdout(&["encode", "-s", schema, "-o", "desc", "-f", "base64", r#"[7, "ab", [1, 2]]"#]);
+    assert_eq!(stdout(&["decode", "-s", schema, "-o", "desc", "-f", "base64", key.trim()]), "[7,\"ab\",[1,2]]\n");
+
+    let schema = "{a: u8, b: option<map<str, i64>>}";
+    for preset in &["ascending", "portable", "native"] {
+        let key = stdout(&["encode", "-s", schema, "-p", preset, r#"{"a": 1, "b": {"x": -1}}"#]);
+        assert_eq!(stdout(&["decode", "-s", schema, "-p", preset, key.trim()]), "{\"a\":1,\"b\":{\"x\":-1}}\n");
+    }
+}
+
+#[test]
+fn test_cli_layout() {
+    let layout = stdout(&["layout", "-s", "(u32, str, seq<u8>)", "00000007616201020505"]);
+    let lines: Vec<_> = layout.lines().map(str::split_whitespace).map(Iterator::collect::<Vec<_>>).collect();
+    assert_eq!(lines[0], ["[7,\"ab\",[1,2]]"]);
+    assert_eq!(lines[1], ["<root>", "0..8", "8..10", "0000000761620102", "0505"]);
+    assert_eq!(lines[2], ["[0]", "0..4", "-", "00000007"]);
+    assert_eq!(lines[3], ["[1]", "4..6", "9..10", "6162", "05"]);
+    assert_eq!(lines[4], ["[2]", "6..8", "8..9", "0102", "05"]);
+    assert_eq!(lines[6], ["[2][1]", "7..8", "-", "02"]);
+    assert_eq!(lines[7], ["head", "8", "bytes,", "tail", "2", "bytes"]);
+}
+
+#[test]
+fn test_cli_errors() {
+    assert!(!ordcode(&["decode", "-s", "(u32", "00"]).status.success());
+    assert!(!ordcode(&["decode", "-s", "u32", "000000"]).status.success());
+    assert!(!ordcode(&["encode", "-s", "u8", "256"]).status.success());
+}
@@ -0,0 +1,73 @@
+use std::collections::{ BTreeMap, HashSet };
+use ordcode::{ *, encoded::INLINE_CAPACITY };
+
+type Key = (u32, String, Desc<i64>);
+
+fn keys() -> Vec<Key> {
+    let mut keys = Vec::new();
+    for id in &[0_u32, 1, 0xFFFF_FFFF] {
+        for name in &["", "a", "a\0", "ab", "b", &"x".repeat(40)] {
+            for ts in &[i64::MIN, -1, 0, 1, i64::MAX] {
+                keys.push((*id, (*name).to_string(), Desc(*ts)));
+            }
+        }
+    }
+    keys
+}
+
+#[test]
+fn test_encoded_order() {
+    let keys = keys();
+    let map: BTreeMap<_, _> = keys.iter()
+        .map(|k| (Encoded::<Key, params::EscapedAscending>::new(k).unwrap(), k.clone()))
+        .collect();
+    assert_eq!(map.len(), keys.len());
+    let mut sorted = keys.clone();
+    sorted.sort();
+    assert_eq!(map.values().cloned().collect::<Vec<_>>(), sorted);
+    for (encoded, key) in &map {
+        assert_eq!(&encoded.decode().unwrap(), key);
+        assert_eq!(encoded.as_bytes(), ser_to_vec_escaped(key).as_slice());
+    }
+}
+
+fn ser_to_vec_escaped(key: &Key) -> Vec<u8> {
+    let mut buf = vec![0_u8; calc_size(key, params::EscapedAscending).unwrap()];
+    serde::Serialize::serialize(key, &mut Serializer::new(DeBytesWriter::new(&mut buf), params::EscapedAscending)).unwrap();
+    buf
+}
+
+#[test]
+fn test_encoded_storage() {
+    let small = Encoded::<(u64, u64, u32)>::new(&(1, 2, 3)).unwrap();
+    assert_eq!(small.as_bytes().len(), INLINE_CAPACITY - 2);
+    let large = Encoded::<String>::new(&"x".repeat(100)).unwrap();
+    assert_eq!(large.decode().unwrap(), "x".repeat(100));
+
+    // same bytes compare and hash equal regardless of storage
+    let heap = Encoded::<(u64, u64, u32)>::from_bytes(small.as_bytes().to_vec());
+    let copied = Encoded::<(u64, u64, u32)>::from_slice(small.as_bytes());
+    assert_eq!(heap, small);
+    assert_eq!(copied, small);
+    let set: HashSet<_> = vec![heap, copied, small.clone()].into_iter().collect();
+    assert_eq!(set.len(), 1);
+    assert_eq!(small.clone().into_bytes(), small.as_bytes());
+    assert_eq!(large.clone().into_bytes(), large.as_bytes());
+    assert_eq!(small.as_encoded_ref().to_encoded(), small);
+}
+
+#[test]
+fn test_encoded_decode() {
+    let encoded = Encoded::<(u16, String)>::new(&(1, "abc".to_string())).unwrap();
+    assert_eq!(encoded.as_bytes(), &[0, 1, b'a', b'b', b'c', 7]);
+
+    let borrowed = EncodedRef::<(u16, &str)>::new(encoded.as_bytes());
+    assert_eq!(borrowed.decode().unwrap(), (1, "abc"));
+
+    let mut bytes = encoded.into_bytes();
+    bytes.insert(2, 0);
+    assert!(Encoded::<(u16, String)>::from_bytes(bytes).decode().is_err());
+    assert!(Encoded::<u32>::from_slice(&[1, 2, 3]).decode().is_err());
+    assert!(Encoded::<u16>::from_slice(&[1, 2, 3]).decode().is_err());
+    assert_eq!(Encoded::<u16>::from_slice(&[1, 2]).decode().unwrap(), 0x0102);
+}
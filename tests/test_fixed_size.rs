@@ -0,0 +1,80 @@
+#![allow(clippy::unreadable_literal)]
+
+use ordcode::{ calc_size, params, Desc, fixed_size::FixedSize };
+use ordcode::params::LengthEncoder;
+use ordcode::varint::OrdVarIntDiscrEncoder;
+use ordcode_derive::FixedSize;
+use serde_derive::Serialize;
+use serde::ser::Serialize;
+
+fn check_size<T: FixedSize + Serialize>(v: &T) {
+    let size = calc_size(v, params::AscendingOrder).unwrap();
+    assert!(size >= T::MIN_ENCODED_SIZE && size <= T::MAX_ENCODED_SIZE);
+    assert_eq!(calc_size(v, params::EscapedAscending).unwrap(), size);
+}
+
+fn check_exact_size<T: FixedSize + Serialize>(v: &T) {
+    assert_eq!(calc_size(v, params::AscendingOrder).unwrap(), T::ENCODED_SIZE);
+    check_size(v);
+}
+
+#[derive(FixedSize, Serialize)]
+struct Unit;
+
+#[derive(FixedSize, Serialize)]
+struct Newtype(u64);
+
+#[derive(FixedSize, Serialize)]
+struct Key {
+    id: u32,
+    ts: Desc<i64>,
+    tag: [u8; 3],
+    pair: (bool, char),
+    unit: Unit,
+}
+
+#[derive(FixedSize, Serialize)]
+struct Generic<T> { items: [T; 2], last: Newtype }
+
+#[derive(FixedSize, Serialize)]
+enum Same { A(u16), B { x: i8, y: u8 }, C([bool; 2]) }
+
+#[derive(FixedSize, Serialize)]
+enum Different { A, B(Option<Key>), C { key: Key } }
+
+fn key() -> Key {
+    Key { id: 1, ts: Desc(-2), tag: *b"abc", pair: (true, 'x'), unit: Unit }
+}
+
+#[test]
+fn test_fixed_size() {
+    check_exact_size(&Unit);
+    check_exact_size(&Newtype(1));
+    check_exact_size(&key());
+    assert_eq!(Key::ENCODED_SIZE, 20);
+    check_exact_size(&Generic { items: [1_u16, 2], last: Newtype(3) });
+    assert_eq!(Generic::<u16>::ENCODED_SIZE, 12);
+    check_exact_size(&(1_u8, 2_u128, -3_f32, 4_f64, [(); 5]));
+
+    for v in &[Same::A(1), Same::B { x: 1, y: 2 }, Same::C([true, false])] {
+        check_exact_size(v);
+    }
+    assert_eq!(Same::ENCODED_SIZE, 3);
+
+    for v in &[Different::A, Different::B(None), Different::B(Some(key())), Different::C { key: key() }] {
+        check_size(v);
+    }
+    assert_eq!((Different::MIN_ENCODED_SIZE, Different::MAX_ENCODED_SIZE), (1, 22));
+    assert_eq!((Option::<Key>::MIN_ENCODED_SIZE, Option::<Key>::MAX_ENCODED_SIZE), (1, 21));
+
+    // buffer of exact size on stack
+    let mut buf = [0_u8; Key::ENCODED_SIZE];
+    ordcode::ser_to_buf_asc_exact(&mut buf, &key()).unwrap();
+}
+
+#[test]
+fn test_discriminant_size() {
+    for i in (0..70000_u32).chain([u32::MAX >> 8, u32::MAX >> 7, u32::MAX - 1, u32::MAX]) {
+        assert_eq!(ordcode::fixed_size::discriminant_size(i), OrdVarIntDiscrEncoder::calc_size(i), "{}", i);
+    }
+}
@@ -0,0 +1,153 @@
+use std::io::{ self, Read };
+use ordcode::{ Error, params, de_from_bytes_asc, frame::{ self, FrameWriter, FrameReader, IoFrameReader } };
+
+type Record = (u64, String, Vec<Vec<u8>>);
+
+fn records() -> Vec<Record> {
+    vec![
+        (0, String::new(), vec![]),
+        (1, "abc".to_string(), vec![vec![1, 2], vec![]]),
+        (u64::MAX, "x".repeat(1000), vec![vec![0xFF; 300]; 3]),
+        (7, "last".to_string(), vec![vec![0]]),
+    ]
+}
+
+fn write_log(checksum: bool) -> (Vec<u8>, Vec<usize>) {
+    let mut writer = FrameWriter::new(Vec::new());
+    if checksum {
+        writer = writer.with_checksum();
+    }
+    let mut ends = Vec::new();
+    for record in records() {
+        writer.write_value(&record, params::AscendingOrder).unwrap();
+        ends.push(writer.get_mut().len());
+    }
+    (writer.into_inner(), ends)
+}
+
+// reader which returns at most 3 bytes per read
+struct Slow<'a>(&'a [u8]);
+
+impl Read for Slow<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = buf.len().min(self.0.len()).min(3);
+        buf[..len].copy_from_slice(&self.0[..len]);
+        self.0 = &self.0[len..];
+        Ok(len)
+    }
+}
+
+fn read_slice(log: &[u8], checksum: bool) -> (Vec<Vec<u8>>, Option<Error>, usize) {
+    let mut reader = FrameReader::new(log);
+    if checksum {
+        reader = reader.with_checksum();
+    }
+    let mut frames = Vec::new();
+    let mut error = None;
+    for frame in &mut reader {
+        match frame {
+            Ok(frame) => frames.push(frame.to_vec()),
+            Err(e) => error = Some(e),
+        }
+    }
+    (frames, error, reader.position())
+}
+
+fn read_io(log: &[u8], checksum: bool) -> (Vec<Vec<u8>>, Option<Error>, usize) {
+    let mut reader = IoFrameReader::new(Slow(log));
+    if checksum {
+        reader = reader.with_checksum();
+    }
+    let mut frames = Vec::new();
+    let mut error = None;
+    for frame in &mut reader {
+        match frame {
+            Ok(frame) => frames.push(frame),
+            Err(e) => error = Some(e),
+        }
+    }
+    (frames, error, reader.position() as usize)
+}
+
+#[test]
+fn test_crc32c() {
+    assert_eq!(frame::crc32c(b""), 0);
+    assert_eq!(frame::crc32c(b"123456789"), 0xE306_9283);
+    assert_eq!(frame::crc32c_append(frame::crc32c(b"1234"), b"56789"), 0xE306_9283);
+}
+
+#[test]
+fn test_frame_roundtrip() {
+    for &checksum in &[false, true] {
+        let (log, ends) = write_log(checksum);
+        assert_eq!(ends.last(), Some(&log.len()));
+        for read in &[read_slice, read_io] {
+            let (frames, error, position) = read(&log, checksum);
+            assert!(error.is_none());
+            assert_eq!(position, log.len());
+            let decoded: Vec<Record> = frames.iter().map(|f| de_from_bytes_asc(f).unwrap()).collect();
+            assert_eq!(decoded, records());
+        }
+    }
+    // large payload has multi-byte length prefix
+    let mut writer = FrameWriter::new(Vec::new());
+    writer.write_frame(&[]).unwrap();
+    writer.write_frame(&[5; 100_000]).unwrap();
+    let log = writer.into_inner();
+    assert_eq!(log.len(), 100_000 + 1 + 3);
+    assert_eq!(read_slice(&log, false).0, [vec![], vec![5; 100_000]]);
+}
+
+#[test]
+fn test_frame_truncated() {
+    for &checksum in &[false, true] {
+        let (log, ends) = write_log(checksum);
+        for cut in 0..log.len() {
+            let complete = ends.iter().filter(|end| **end <= cut).count();
+            let valid_end = if complete == 0 { 0 } else { ends[complete - 1] };
+            for read in &[read_slice, read_io] {
+                let (frames, error, position) = read(&log[..cut], checksum);
+                assert_eq!(frames.len(), complete);
+                assert_eq!(position, valid_end);
+                if cut == valid_end {
+                    assert!(error.is_none());
+                } else {
+                    assert!(matches!(error, Some(Error::TruncatedFrame)), "{} {:?}", cut, error);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_frame_corrupted() {
+    let (mut log, ends) = write_log(true);
+    log[ends[1] + 5] ^= 1;
+    for read in &[read_slice, read_io] {
+        let (frames, error, position) = read(&log, true);
+        assert_eq!(frames.len(), 2);
+        assert!(matches!(error, Some(Error::FrameChecksumMismatch)));
+        assert_eq!(position, ends[1]);
+    }
+
+    // length prefix in the middle of the log claims more than the rest of input
+    let (mut log, ends) = write_log(true);
+    log[ends[0]..ends[0] + 2].copy_from_slice(&[0xFE, 0xFF]);
+    for read in &[read_slice, read_io] {
+        let (frames, error, position) = read(&log, true);
+        assert_eq!(frames.len(), 1);
+        assert!(matches!(error, Some(Error::CorruptFrame)), "{:?}", error);
+        assert_eq!(position, ends[0]);
+    }
+    // without checksums, it can't be told from torn write
+    let (mut log, ends) = write_log(false);
+    log[ends[0]..ends[0] + 2].copy_from_slice(&[0xFE, 0xFF]);
+    assert!(matches!(read_slice(&log, false).1, Some(Error::TruncatedFrame)));
+
+    let (log, _) = write_log(false);
+    let mut reader = IoFrameReader::new(log.as_slice()).with_max_len(500);
+    assert_eq!(reader.by_ref().take_while(Result::is_ok).count(), 2);
+    assert!(reader.next().is_none());
+    let mut reader = IoFrameReader::new(log.as_slice()).with_max_len(500);
+    assert!(matches!(reader.nth(2), Some(Err(Error::LengthLimitExceeded))));
+}
@@ -0,0 +1,121 @@
+use std::io::{ self, BufReader, Write };
+use ordcode::{ *, buf::{ IoReader, IoWriter, ReadBytes } };
+use serde::{ Serialize, de::DeserializeOwned };
+use serde_derive::{ Serialize, Deserialize };
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+struct Record {
+    id: u64,
+    name: String,
+    #[serde(with = "serde_bytes")]
+    data: Vec<u8>,
+    items: Vec<(i16, Option<String>)>,
+}
+
+fn records() -> Vec<Record> {
+    (0..10_u8).map(|i| Record {
+        id: u64::from(i) << 40,
+        name: "x".repeat(usize::from(i) * 7),
+        data: vec![i; usize::from(i) * 100],
+        items: (0..i16::from(i)).map(|j| (-j, if j % 2 == 0 { Some(j.to_string()) } else { None })).collect(),
+    }).collect()
+}
+
+fn ser_to_vec<T: Serialize, P: params::SerializerParams>(value: &T, params: P) -> Vec<u8> {
+    let mut buf = vec![0_u8; calc_size(value, params).unwrap()];
+    value.serialize(&mut Serializer::new(DeBytesWriter::new(&mut buf), params)).unwrap();
+    buf
+}
+
+fn write_all<T: Serialize, P: params::SerializerParams>(values: &[T], params: P) -> Result<Vec<u8>> {
+    let mut ser = Serializer::new(IoWriter::new(Vec::new()), params);
+    for value in values {
+        value.serialize(&mut ser)?;
+    }
+    Ok(ser.into_writer().into_inner())
+}
+
+// read with small stream buffer, so that values span its boundaries
+fn read_all<T: DeserializeOwned, P: params::SerializerParams>(stream: &[u8], params: P, limits: Limits) -> Result<Vec<T>> {
+    let mut reader = IoReader::new(BufReader::with_capacity(5, stream));
+    let mut values = Vec::new();
+    while reader.is_complete().is_err() {
+        let mut de = Deserializer::new(&mut reader, params).with_limits(limits);
+        values.push(T::deserialize(&mut de)?);
+    }
+    Ok(values)
+}
+
+fn check_stream<P: params::SerializerParams>(params: P) {
+    let records = records();
+    let stream = write_all(&records, params).unwrap();
+    let expected: Vec<u8> = records.iter().flat_map(|r| ser_to_vec(r, params)).collect();
+    assert_eq!(stream, expected);
+    assert_eq!(read_all::<Record, _>(&stream, params, Limits::new()).unwrap(), records);
+}
+
+#[test]
+fn test_io_stream() {
+    check_stream(params::PortableBinary);
+    check_stream(params::NativeBinary);
+    // escaped strings are longer than stream buffer
+    check_stream(params::EscapedAscending);
+
+    let values = vec![("a\0b".to_string(), vec![1_u8, 2]), (String::new(), vec![])];
+    let stream = write_all(&values, params::EscapedAscending).unwrap();
+    let mut reader = IoReader::new(BufReader::with_capacity(64, stream.as_slice()));
+    for value in &values {
+        let decoded: (String, Vec<u8>) = serde::Deserialize::deserialize(&mut Deserializer::new(&mut reader, params::EscapedAscending)).unwrap();
+        assert_eq!(&decoded, value);
+    }
+    reader.is_complete().unwrap();
+}
+
+#[test]
+fn test_io_limits() {
+    let records = records();
+    let stream = write_all(&records, params::PortableBinary).unwrap();
+    // lengths are not limited by stream buffer size
+    assert_eq!(read_all::<Record, _>(&stream, params::PortableBinary, Limits::new().max_seq_len(1000)).unwrap(), records);
+    assert!(matches!(read_all::<Record, _>(&stream, params::PortableBinary, Limits::new().max_seq_len(100)),
+                     Err(Error::LengthLimitExceeded)));
+}
+
+#[test]
+fn test_io_errors() {
+    // tail is not supported
+    assert!(matches!(write_all(&["abc"], params::AscendingOrder), Err(Error::TailNotSupported)));
+    let stream = write_all(&[(1_u32, 2_u16)], params::AscendingOrder).unwrap();
+    assert_eq!(read_all::<(u32, u16), _>(&stream, params::AscendingOrder, Limits::new()).unwrap(), [(1, 2)]);
+    let stream = ser_to_vec(&"abc", params::AscendingOrder);
+    assert!(matches!(read_all::<String, _>(&stream, params::AscendingOrder, Limits::new()), Err(Error::TailNotSupported)));
+
+    // truncated stream
+    let stream = write_all(&records()[3..4], params::PortableBinary).unwrap();
+    assert!(matches!(read_all::<Record, _>(&stream[..stream.len() - 1], params::PortableBinary, Limits::new()),
+                     Err(Error::PrematureEndOfInput)));
+
+    // errors of underlying reader after a value are not mistaken for the end of stream
+    struct Broken;
+    impl io::Read for Broken {
+        fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> { Err(io::ErrorKind::BrokenPipe.into()) }
+    }
+    let stream = write_all(&[7_u32], params::PortableBinary).unwrap();
+    let mut reader = IoReader::new(BufReader::new(io::Read::chain(stream.as_slice(), Broken)));
+    let value: u32 = serde::Deserialize::deserialize(&mut Deserializer::new(&mut reader, params::PortableBinary)).unwrap();
+    assert_eq!(value, 7);
+    assert!(matches!(reader.is_complete(), Err(Error::Io(e)) if e.kind() == io::ErrorKind::BrokenPipe));
+
+    // errors of underlying writer
+    struct Full;
+    impl Write for Full {
+        fn write(&mut self, _buf: &[u8]) -> io::Result<usize> { Err(io::ErrorKind::WriteZero.into()) }
+        fn flush(&mut self) -> io::Result<()> { Ok(()) }
+    }
+    let err = 1_u8.serialize(&mut Serializer::new(IoWriter::new(Full), params::PortableBinary)).unwrap_err();
+    match &err {
+        Error::Io(e) => assert_eq!(e.kind(), io::ErrorKind::WriteZero),
+        _ => panic!("unexpected error {:?}", err),
+    }
+    assert!(std::error::Error::source(&err).is_some());
+}
@@ -0,0 +1,89 @@
+use ordcode::{ *, key::{ KeyBuilder, KeyReader } };
+use serde::ser::Serialize;
+
+fn build(id: u32, name: &str, ts: i64, n: u64) -> Vec<u8> {
+    KeyBuilder::new(Vec::new()).u32(id).str_escaped(name).i64_desc(ts).u64_compact_desc(n).finish().unwrap()
+}
+
+#[test]
+fn test_key_roundtrip() {
+    let buf = KeyBuilder::new(Vec::new())
+        .bool(true).u8_desc(1).u16(2).u32_desc(3).u64(4).i8(-1).i16_desc(-2).i32(-3).i64_desc(-4)
+        .u128(5).i128_desc(-5).f32(1.5).f64_desc(-2.5).char('x')
+        .u32_compact(6).u64_compact_desc(70000).i32_compact_desc(-7).i64_compact(-80000)
+        .str_escaped("a\0b").str_escaped_desc("").bytes_escaped(&[0, 0xF8]).bytes_escaped_desc(b"xyz")
+        .value(&(1_u8, Some(2_u16)), Order::Descending)
+        .finish().unwrap();
+
+    let mut r = KeyReader::new(DeBytesReader::new(&buf));
+    assert!(r.bool().unwrap());
+    assert_eq!(r.u8_desc().unwrap(), 1);
+    assert_eq!(r.u16().unwrap(), 2);
+    assert_eq!(r.u32_desc().unwrap(), 3);
+    assert_eq!(r.u64().unwrap(), 4);
+    assert_eq!(r.i8().unwrap(), -1);
+    assert_eq!(r.i16_desc().unwrap(), -2);
+    assert_eq!(r.i32().unwrap(), -3);
+    assert_eq!(r.i64_desc().unwrap(), -4);
+    assert_eq!(r.u128().unwrap(), 5);
+    assert_eq!(r.i128_desc().unwrap(), -5);
+    assert!((r.f32().unwrap() - 1.5).abs() < f32::EPSILON);
+    assert!((r.f64_desc().unwrap() + 2.5).abs() < f64::EPSILON);
+    assert_eq!(r.char().unwrap(), 'x');
+    assert_eq!(r.u32_compact().unwrap(), 6);
+    assert_eq!(r.u64_compact_desc().unwrap(), 70000);
+    assert_eq!(r.i32_compact_desc().unwrap(), -7);
+    assert_eq!(r.i64_compact().unwrap(), -80000);
+    assert_eq!(r.str_escaped().unwrap(), "a\0b");
+    assert_eq!(r.str_escaped_desc().unwrap(), "");
+    let mut bytes = Vec::new();
+    r.bytes_escaped_to_writer(&mut bytes).unwrap();
+    assert_eq!(bytes, vec![0, 0xF8]);
+    assert_eq!(r.bytes_escaped_desc().unwrap(), b"xyz");
+    assert_eq!(r.value::<(u8, Option<u16>)>(Order::Descending).unwrap(), (1, Some(2)));
+    r.finish().unwrap();
+}
+
+#[test]
+fn test_key_ordering() {
+    let keys = [
+        build(1, "", 100, 5),
+        build(1, "", -100, 5),
+        build(1, "\0", 0, 1000),
+        build(1, "\0", 0, 5),
+        build(1, "a", i64::MAX, 0),
+        build(2, "", 0, 0),
+    ];
+    for pair in keys.windows(2) {
+        assert!(pair[0] < pair[1]);
+    }
+}
+
+fn serialize<T: Serialize, P: params::SerializerParams>(value: &T, params: P) -> Vec<u8> {
+    let mut buf = vec![0_u8; calc_size(value, params).unwrap()];
+    let mut writer = DeBytesWriter::new(&mut buf);
+    value.serialize(&mut Serializer::new(&mut writer, params)).unwrap();
+    writer.is_complete().unwrap();
+    buf
+}
+
+#[test]
+fn test_key_serde_layout() {
+    let key = build(7, "na\0me", -5, 300);
+    let escaped = serialize(&(7_u32, "na\0me", Desc(-5_i64)), params::EscapedAscending);
+    let compact = serialize(&Desc(300_u64), params::CompactAscending);
+    assert_eq!(key, [escaped, compact].concat());
+}
+
+#[test]
+fn test_key_fixed_buffer() {
+    let mut buf = [0_u8; 6];
+    let mut writer = DeBytesWriter::new(&mut buf);
+    let res = KeyBuilder::new(&mut writer).u32(1).u32(2).u8(3).finish();
+    assert!(matches!(res, Err(Error::BufferOverflow)));
+    assert_eq!(writer.finalize().unwrap(), 4);
+
+    let mut r = KeyReader::new(DeBytesReader::new(&buf[..4]));
+    assert_eq!(r.u16().unwrap(), 0);
+    assert!(matches!(r.finish(), Err(Error::BufferUnderflow)));
+}
@@ -0,0 +1,127 @@
+use std::collections::BTreeMap;
+use ordcode::{ *, layout::Layout };
+use serde::Serialize;
+use serde_derive::Serialize;
+
+#[derive(Serialize)]
+enum Kind {
+    Name(String),
+    Pair { a: u8, b: Vec<u8> },
+}
+
+// sequence of unknown length
+struct Odd(Vec<u16>);
+
+impl Serialize for Odd {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.0.iter().filter(|v| *v % 2 == 1))
+    }
+}
+
+#[derive(Serialize)]
+struct Record {
+    id: u32,
+    name: String,
+    tags: Vec<String>,
+    kinds: Vec<Kind>,
+    opt: Option<(u8, String)>,
+    odd: Odd,
+    desc: Desc<(i64, String)>,
+}
+
+fn record() -> Record {
+    Record {
+        id: 7,
+        name: "ab".to_string(),
+        tags: vec!["x".to_string(), "a\0b".to_string()],
+        kinds: vec![Kind::Name("n".to_string()), Kind::Pair { a: 1, b: vec![0xFF; 200] }],
+        opt: Some((3, "abc".to_string())),
+        odd: Odd(vec![1, 2, 3, 4, 5]),
+        desc: Desc((-1, "d".to_string())),
+    }
+}
+
+fn ser_to_vec<T: ?Sized + Serialize, P: params::SerializerParams>(value: &T, params: P) -> Vec<u8> {
+    let mut buf = vec![0_u8; calc_size(value, params).unwrap()];
+    let mut writer = DeBytesWriter::new(&mut buf);
+    value.serialize(&mut Serializer::new(&mut writer, params)).unwrap();
+    writer.is_complete().unwrap();
+    buf
+}
+
+// bytes of the entry at `path` are the same as of `value` serialized alone
+fn check_entry<T, P>(layout: &Layout, bytes: &[u8], path: &str, value: &T, params: P)
+    where T: ?Sized + Serialize,
+          P: params::SerializerParams,
+{
+    let entry = layout.iter().find(|e| e.path() == path).unwrap();
+    let alone = ser_to_vec(value, params);
+    assert_eq!(&bytes[entry.head()], &alone[..entry.head().len()], "{}", path);
+    assert_eq!(&bytes[entry.tail()], &alone[entry.head().len()..], "{}", path);
+}
+
+fn check_record<P: params::SerializerParams>(params: P) {
+    let record = record();
+    let layout = calc_layout(&record, params).unwrap();
+    let bytes = ser_to_vec(&record, params);
+    assert_eq!(layout.size(), bytes.len());
+    let root = &layout.entries()[0];
+    assert_eq!((root.path(), root.head(), root.tail()), ("", 0..layout.head_size(), layout.head_size()..bytes.len()));
+
+    check_entry(&layout, &bytes, "id", &record.id, params);
+    check_entry(&layout, &bytes, "name", &record.name, params);
+    check_entry(&layout, &bytes, "tags", &record.tags, params);
+    check_entry(&layout, &bytes, "tags[1]", &record.tags[1], params);
+    check_entry(&layout, &bytes, "kinds", &record.kinds, params);
+    check_entry(&layout, &bytes, "kinds[1]", &record.kinds[1], params);
+    check_entry(&layout, &bytes, "kinds[1].b", &vec![0xFF_u8; 200], params);
+    check_entry(&layout, &bytes, "opt", &record.opt, params);
+    check_entry(&layout, &bytes, "opt[1]", "abc", params);
+    check_entry(&layout, &bytes, "odd", &record.odd, params);
+    check_entry(&layout, &bytes, "odd[1]", &3_u16, params);
+    check_entry(&layout, &bytes, "desc[1]", &Desc("d"), params);
+}
+
+#[test]
+fn test_layout_same_as_serialized() {
+    check_record(params::AscendingOrder);
+    check_record(params::CompactAscending);
+    check_record(params::PortableBinary);
+    check_record(params::NativeBinary);
+
+    // fields of escaped sequences have markers, only sizes are the same
+    let record = record();
+    let layout = calc_layout(&record, params::EscapedAscending).unwrap();
+    let fields_size: usize = layout.iter().skip(1).filter(|e| !e.path().contains(&['.', '['][..])).map(|e| e.size()).sum();
+    assert_eq!(fields_size, layout.size());
+    assert_eq!(layout.size(), calc_size(&record, params::EscapedAscending).unwrap());
+    assert_eq!(calc_layout(&record, params::SelfDescribing).unwrap().size(), calc_size(&record, params::SelfDescribing).unwrap());
+}
+
+#[test]
+fn test_layout_len_overhead() {
+    let value = (1_u32, "ab", vec![vec![1_u8, 2], vec![]]);
+    let layout = calc_layout(&value, params::AscendingOrder).unwrap();
+    let overhead: Vec<_> = layout.iter().map(|e| (e.path(), e.len_overhead())).collect();
+    assert_eq!(overhead, [("", 4), ("[0]", 0), ("[1]", 1), ("[2]", 3), ("[2][0]", 1), ("[2][0][0]", 0), ("[2][0][1]", 0), ("[2][1]", 1)]);
+
+    // escaped: item markers, end marker, escaping and terminator of strings
+    let layout = calc_layout(&("a\0b", vec![1_u8, 2]), params::EscapedAscending).unwrap();
+    let overhead: Vec<_> = layout.iter().map(|e| (e.path(), e.size(), e.len_overhead())).collect();
+    assert_eq!(overhead, [("", 11, 6), ("[0]", 6, 3), ("[1]", 5, 3), ("[1][0]", 2, 1), ("[1][1]", 2, 1)]);
+    assert_eq!(layout.tail_size(), 0);
+}
+
+#[test]
+fn test_layout_map_and_display() {
+    let map: BTreeMap<_, _> = vec![("a", vec![1_u8]), ("b", vec![])].into_iter().collect();
+    let layout = calc_layout(&map, params::AscendingOrder).unwrap();
+    let ranges: Vec<_> = layout.iter().map(|e| (e.path(), e.head(), e.tail())).collect();
+    assert_eq!(ranges, [("", 0..3, 3..8), ("[0]", 0..2, 5..7), ("[0][0]", 1..2, 5..5), ("[1]", 2..3, 3..5)]);
+
+    let table = layout.to_string();
+    let lines: Vec<Vec<_>> = table.lines().map(|l| l.split_whitespace().collect()).collect();
+    assert_eq!(lines[0], ["path", "head", "tail", "size", "lengths"]);
+    assert_eq!(lines[1], ["<root>", "0..3", "3..8", "8", "5"]);
+    assert_eq!(lines[3], ["[0][0]", "1..2", "-", "1", "0"]);
+}
@@ -1,6 +1,7 @@
 #![cfg(feature="serde")]
+#![allow(clippy::owned_cow)]
 
-#[macro_use] extern crate serde;
+extern crate serde;
 #[macro_use] extern crate serde_derive;
 
 extern crate ordcode;
@@ -12,7 +13,8 @@ use std::fmt::Debug;
 
 use ordcode::*;
 
-use serde::de::{ DeserializeOwned };
+use serde::de::{ Deserialize, DeserializeOwned };
+use ordcode::buf::ReadBytes;
 use serde::ser::Serialize;
 
 fn serialize_asc<T: Serialize + ?Sized>(v: &T) -> Result<Vec<u8>> {
@@ -38,6 +40,10 @@ fn the_same<V>(element: V)
     let test_same = |element, order: Order| {
         let mut buf = [0_u8; 1<<16];
         let len =  ser_to_buf_ordered(&mut buf, &element, order).unwrap();
+        if matches!(order, Order::Descending) {
+            let decoded: V = de_from_bytes_desc(&buf[..len]).unwrap();
+            assert_eq!(element, &decoded);
+        }
         let decoded: V = de_from_bytes_ordered(&mut buf[..len], order).unwrap();
 
         //if element != decoded { println!("MISMATCH {:#?}: {:#?} {:#?}", order, &buf[..len], decoded); }
@@ -75,19 +81,17 @@ fn test_numbers() {
     the_same(5f64);
 }
 
-serde_if_integer128! {
-    #[test]
-    fn test_numbers_128bit() {
-        // unsigned positive
-        the_same(5u128);
-        the_same(u128::max_value());
-        // signed positive
-        the_same(5i128);
-        the_same(i128::max_value());
-        // signed negative
-        the_same(-5i128);
-        the_same(i128::min_value());
-    }
+#[test]
+fn test_numbers_128bit() {
+    // unsigned positive
+    the_same(5u128);
+    the_same(u128::MAX);
+    // signed positive
+    the_same(5i128);
+    the_same(i128::MAX);
+    // signed negative
+    the_same(-5i128);
+    the_same(i128::MIN);
 }
 
 #[test]
@@ -195,6 +199,48 @@ fn test_enum() {
     ]);
 }
 
+// Unit variant of an enum with up to `u32::MAX` variants
+#[derive(PartialEq, Debug)]
+struct ManyVariants(u32);
+
+impl Serialize for ManyVariants {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_unit_variant("ManyVariants", self.0, "V")
+    }
+}
+
+impl<'de> Deserialize<'de> for ManyVariants {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        struct Visitor;
+        impl<'de> serde::de::Visitor<'de> for Visitor {
+            type Value = ManyVariants;
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str("enum ManyVariants")
+            }
+            fn visit_enum<A: serde::de::EnumAccess<'de>>(self, data: A) -> std::result::Result<Self::Value, A::Error> {
+                let (idx, variant) = data.variant::<u32>()?;
+                serde::de::VariantAccess::unit_variant(variant)?;
+                Ok(ManyVariants(idx))
+            }
+        }
+        deserializer.deserialize_enum("ManyVariants", &[], Visitor)
+    }
+}
+
+#[test]
+fn test_enum_discriminant_ordering() {
+    let idxs = [0_u32, 1, 127, 128, 240, 241, 2287, 2288, 67823, 67824, 1 << 24, u32::MAX];
+    for w in idxs.windows(2) {
+        let (a, b) = (ManyVariants(w[0]), ManyVariants(w[1]));
+        assert!(serialize_asc(&a).unwrap() < serialize_asc(&b).unwrap());
+        assert!(serialize_desc(&a).unwrap() > serialize_desc(&b).unwrap());
+        assert_eq!(calc_size_asc(&a).unwrap(), serialize_asc(&a).unwrap().len());
+    }
+    for i in idxs {
+        the_same(ManyVariants(i));
+    }
+}
+
 #[test]
 fn test_vec() {
     let v: Vec<u8> = vec![];
@@ -378,4 +424,730 @@ fn test_byteseq() {
         }
     }
     the_same(v);
-}
\ No newline at end of file
+}
+#[test]
+fn test_borrowed() {
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Key<'a> {
+        id:   u32,
+        name: &'a str,
+        #[serde(with = "serde_bytes")]
+        blob: &'a [u8],
+    }
+    let key = Key { id: 5, name: "foo", blob: &[1, 2, 3] };
+    let encoded = serialize_asc(&key).unwrap();
+    let decoded: Key = de_from_bytes_borrowed(&encoded).unwrap();
+    assert_eq!(decoded, key);
+    // check that data is not copied
+    let range = encoded.as_ptr_range();
+    assert!(range.contains(&decoded.name.as_ptr()));
+    assert!(range.contains(&decoded.blob.as_ptr()));
+}
+
+#[test]
+fn test_desc_with_buffer() {
+    let value = (7_u16, "a string longer than sixteen bytes".to_string());
+    let encoded = serialize_desc(&value).unwrap();
+    let mut tmp = [0_u8; 64];
+    let mut reader = buf::InvertingReader::with_buffer(DeBytesReader::new(&encoded), &mut tmp[..]);
+    let decoded: (u16, String) = Deserialize::deserialize(&mut new_de_asc(&mut reader)).unwrap();
+    assert_eq!(decoded, value);
+
+    let mut tmp = [0_u8; 20];
+    let mut reader = buf::InvertingReader::with_buffer(DeBytesReader::new(&encoded), &mut tmp[..]);
+    let res: Result<(u16, String)> = Deserialize::deserialize(&mut new_de_asc(&mut reader));
+    assert!(matches!(res, Err(Error::BufferOverflow)));
+}
+
+fn serialize_esc<T: Serialize + ?Sized>(v: &T) -> Vec<u8> {
+    let mut buf = vec![0_u8; calc_size(v, params::EscapedAscending).unwrap()];
+    let mut writer = DeBytesWriter::new(&mut buf);
+    let mut ser = Serializer::new(&mut writer, params::EscapedAscending);
+    v.serialize(&mut ser).unwrap();
+    writer.is_complete().unwrap();
+    buf
+}
+
+fn deserialize_esc<T: DeserializeOwned>(b: &[u8]) -> T {
+    let mut reader = DeBytesReader::new(b);
+    let mut deser = Deserializer::new(&mut reader, params::EscapedAscending);
+    let v = T::deserialize(&mut deser).unwrap();
+    reader.is_complete().unwrap();
+    v
+}
+
+#[test]
+fn test_escaped_roundtrip() {
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Foo {
+        s: String,
+        b: serde_bytes::ByteBuf,
+        v: Vec<u16>,
+        m: std::collections::BTreeMap<String, Option<u8>>,
+    }
+    let mut m = std::collections::BTreeMap::new();
+    m.insert("a\0b".to_string(), Some(1));
+    m.insert("".to_string(), None);
+    let foo = Foo {
+        s: "\0hello\0".to_string(),
+        b: serde_bytes::ByteBuf::from(vec![0, 0xFF, 0, 1, 0xF8]),
+        v: vec![0, 1, 0xFFFF],
+        m,
+    };
+    let encoded = serialize_esc(&foo);
+    assert_eq!(deserialize_esc::<Foo>(&encoded), foo);
+    assert_eq!(serialize_esc("ab"), b"ab\x00\x01");
+    assert_eq!(serialize_esc("\0"), b"\x00\xFF\x00\x01");
+}
+
+#[test]
+fn test_escaped_with_buffer() {
+    let value = ("a\0b".to_string(), serde_bytes::ByteBuf::from(vec![0, 0, 7]), 5_u8);
+    let encoded = serialize_esc(&value);
+    let mut tmp = [0_u8; 3];
+    let mut deser = Deserializer::new(DeBytesReader::new(&encoded), params::EscapedAscending)
+        .with_buffer(&mut tmp[..]);
+    let decoded: (String, serde_bytes::ByteBuf, u8) = Deserialize::deserialize(&mut deser).unwrap();
+    assert_eq!(decoded, value);
+
+    let mut tmp = [0_u8; 2];
+    let mut deser = Deserializer::new(DeBytesReader::new(&encoded), params::EscapedAscending)
+        .with_buffer(&mut tmp[..]);
+    let res: Result<(String, serde_bytes::ByteBuf, u8)> = Deserialize::deserialize(&mut deser);
+    assert!(matches!(res, Err(Error::BufferOverflow)));
+}
+
+#[test]
+fn test_escaped_borrowed() {
+    let encoded = serialize_esc(&("foo", 1_u8));
+    let mut reader = DeBytesReader::new(&encoded);
+    let mut deser = Deserializer::new(&mut reader, params::EscapedAscending);
+    let (s, v): (&str, u8) = Deserialize::deserialize(&mut deser).unwrap();
+    assert_eq!((s, v), ("foo", 1));
+}
+
+#[test]
+fn test_escaped_desc_immutable() {
+    type Key = (String, u8, Desc<String>, Vec<u16>);
+    let value: Key = ("a\0b".to_string(), 5, Desc("a string longer than sixteen\0bytes".to_string()), vec![0, 0xF8F8]);
+    let inverted: Vec<u8> = serialize_esc(&value).iter().map(|b| !b).collect();
+    let mut deser = Deserializer::new(buf::InvertingReader::new(DeBytesReader::new(&inverted)), params::EscapedAscending);
+    let decoded: Key = Deserialize::deserialize(&mut deser).unwrap();
+    assert_eq!(decoded, value);
+    deser.into_reader().is_complete().unwrap();
+}
+
+#[test]
+fn test_escaped_ordering() {
+    fn check<T: Serialize + Ord + Debug>(mut values: Vec<T>) {
+        values.sort();
+        for w in values.windows(2) {
+            assert!(serialize_esc(&w[0]) < serialize_esc(&w[1]), "{:?} < {:?}", w[0], w[1]);
+        }
+    }
+    check(vec![("a", 2_u32), ("ab", 1), ("", 5), ("a\0", 0), ("b", 0), ("a\u{10000}", 0)]);
+    check(vec![("a", "b"), ("ab", ""), ("a", ""), ("a\0", "z"), ("", "")]);
+    check(vec![vec![1_u32], vec![], vec![1, 2], vec![0, 5], vec![2]]);
+    check(vec![(vec![1_u8, 0], 1_u8), (vec![1], 2), (vec![1, 0, 0], 0), (vec![0xFF], 0)]);
+}
+
+#[test]
+fn test_desc() {
+    the_same(Desc(5_u32));
+    the_same((Desc("abc".to_string()), 7_u8, Desc(vec![Desc(1_i16), Desc(-2)])));
+    the_same(Some(Desc((1_u64, "foo".to_string()))));
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Key {
+        device_id: u32,
+        #[serde(with = "ordcode::desc")]
+        timestamp: std::cmp::Reverse<u64>,
+        name: Desc<String>,
+    }
+    let key = Key { device_id: 3, timestamp: std::cmp::Reverse(1000), name: Desc("dev".to_string()) };
+    assert_eq!(calc_size_asc(&key).unwrap(), calc_size_asc(&(3_u32, 1000_u64, "dev")).unwrap());
+    the_same(key);
+
+    let esc = (1_u8, Desc("a\0b".to_string()), Desc(vec![2_u16, 3]));
+    assert_eq!(calc_size(&esc, params::EscapedAscending).unwrap(), serialize_esc(&esc).len());
+    assert_eq!(deserialize_esc::<(u8, Desc<String>, Desc<Vec<u16>>)>(&serialize_esc(&esc)), esc);
+
+    // strings after `Desc` value are borrowed from input again
+    let encoded = serialize_asc(&(Desc("ab"), "cd")).unwrap();
+    let (a, b): (Desc<String>, &str) = de_from_bytes_borrowed(&encoded).unwrap();
+    assert_eq!((a.0.as_str(), b), ("ab", "cd"));
+    assert!(encoded.as_ptr_range().contains(&b.as_ptr()));
+}
+
+#[test]
+fn test_desc_ordering() {
+    fn check<T: Serialize + Ord + Debug>(mut values: Vec<T>) {
+        values.sort();
+        for w in values.windows(2) {
+            assert!(serialize_asc(&w[0]).unwrap() < serialize_asc(&w[1]).unwrap(), "{:?} < {:?}", w[0], w[1]);
+            assert!(serialize_esc(&w[0]) < serialize_esc(&w[1]), "{:?} < {:?}", w[0], w[1]);
+        }
+    }
+    check(vec![(1_u32, Desc(100_u64)), (1, Desc(200)), (2, Desc(0)), (0, Desc(u64::MAX))]);
+    check(vec![(Desc(-1_i32), 1_u8), (Desc(5), 0), (Desc(5), 1), (Desc(i32::MIN), 0)]);
+    check(vec![Desc(Desc(3_u8)), Desc(Desc(1)), Desc(Desc(2))]);
+    // variable-length values preserve ordering only with `EscapedAscending`
+    let mut values = [(Desc("a"), 2_u32), (Desc("ab"), 1), (Desc(""), 5), (Desc("b"), 0)];
+    values.sort();
+    for w in values.windows(2) {
+        assert!(serialize_esc(&w[0]) < serialize_esc(&w[1]), "{:?} < {:?}", w[0], w[1]);
+    }
+}
+
+fn serialize_compact<T: Serialize + ?Sized>(v: &T) -> Vec<u8> {
+    let mut buf = vec![0_u8; calc_size(v, params::CompactAscending).unwrap()];
+    let mut writer = DeBytesWriter::new(&mut buf);
+    let mut ser = Serializer::new(&mut writer, params::CompactAscending);
+    v.serialize(&mut ser).unwrap();
+    writer.is_complete().unwrap();
+    buf
+}
+
+fn deserialize_compact<T: DeserializeOwned>(b: &[u8]) -> T {
+    let mut reader = DeBytesReader::new(b);
+    let mut deser = Deserializer::new(&mut reader, params::CompactAscending);
+    let v = T::deserialize(&mut deser).unwrap();
+    reader.is_complete().unwrap();
+    v
+}
+
+#[test]
+fn test_compact_ints() {
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Rec {
+        id: u64,
+        count: u32,
+        delta: i64,
+        small: i32,
+        fixed: u16,
+        name: String,
+        items: Vec<i64>,
+    }
+    let rec = Rec { id: 5, count: 300, delta: -7, small: i32::MIN, fixed: 1,
+        name: "abc".to_string(), items: vec![0, -1, i64::MAX, i64::MIN] };
+    let buf = serialize_compact(&rec);
+    assert_eq!(deserialize_compact::<Rec>(&buf), rec);
+    assert_eq!(serialize_compact(&(5_u64, -7_i64, 200_usize)).len(), 3);
+
+    let signed = [i64::MIN, -1_000_000, -121, -120, -1, 0, 1, 119, 120, 1_000_000, i64::MAX];
+    for w in signed.windows(2) {
+        assert!(serialize_compact(&(w[0], 1_u8)) < serialize_compact(&(w[1], 0_u8)));
+    }
+    let unsigned = [0_u64, 1, 240, 241, 2288, 1_000_000, u64::MAX];
+    for w in unsigned.windows(2) {
+        assert!(serialize_compact(&(w[0], 1_u8)) < serialize_compact(&(w[1], 0_u8)));
+    }
+}
+
+#[test]
+fn test_de_vec_writer() {
+    fn check<T: Serialize + DeserializeOwned + PartialEq + Debug>(value: &T) {
+        let mut writer = buf::DeVecWriter::new();
+        value.serialize(&mut new_ser_asc(&mut writer)).unwrap();
+        assert_eq!(writer.len(), calc_size_asc(value).unwrap());
+        let buf = writer.into_vec();
+        assert_eq!(buf, serialize_asc(value).unwrap());
+        assert_eq!(&deserialize_asc::<T>(&buf).unwrap(), value);
+    }
+    check(&(1_u8, "abc".to_string(), vec!["a".to_string(); 300]));
+    check(&vec![vec![0_u8; 1000]; 3]);
+    check(&HashMap::<String, String>::new());
+
+    // size of values serialized with `collect_str` is not known in advance
+    struct Display(u64);
+    impl Serialize for Display {
+        fn serialize<S: serde::Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+            serializer.collect_str(&format_args!("#{}", self.0))
+        }
+    }
+    let mut writer = buf::DeVecWriter::with_capacity(16, 4);
+    (Display(42), Display(7)).serialize(&mut new_ser_asc(&mut writer)).unwrap();
+    assert_eq!(deserialize_asc::<(String, String)>(&writer.into_vec()).unwrap(), ("#42".to_string(), "#7".to_string()));
+}
+
+#[test]
+fn test_unknown_length_seq() {
+    // `collect_seq` and `collect_map` over filtered iterators don't know sequence length
+    struct Filtered<'a>(&'a [Vec<String>]);
+    impl Serialize for Filtered<'_> {
+        fn serialize<S: serde::Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+            serializer.collect_seq(self.0.iter().filter(|v| !v.is_empty()))
+        }
+    }
+    struct FilteredMap<'a>(&'a [(u8, &'a str)]);
+    impl Serialize for FilteredMap<'_> {
+        fn serialize<S: serde::Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+            serializer.collect_map(self.0.iter().filter(|(k, _)| *k > 0).map(|(k, v)| (k, v)))
+        }
+    }
+    fn check<P: params::SerializerParams, T: Serialize, E: Serialize>(params: P, value: &T, expected: &E) {
+        let size = calc_size(value, params).unwrap();
+        assert_eq!(size, calc_size(expected, params).unwrap());
+        let mut buf = vec![0_u8; size];
+        let mut writer = DeBytesWriter::new(&mut buf);
+        value.serialize(&mut Serializer::new(&mut writer, params)).unwrap();
+        writer.is_complete().unwrap();
+        let mut expected_buf = vec![0_u8; size];
+        let mut writer = DeBytesWriter::new(&mut expected_buf);
+        expected.serialize(&mut Serializer::new(&mut writer, params)).unwrap();
+        assert_eq!(buf, expected_buf);
+    }
+    let data = vec![vec!["a".to_string(), "bc".to_string()], vec![], vec!["def".to_string()]];
+    let value = ("x", Filtered(&data), Filtered(&[]), 5_u8);
+    let expected = ("x", vec![data[0].clone(), data[2].clone()], Vec::<Vec<String>>::new(), 5_u8);
+    check(params::AscendingOrder, &value, &expected);
+    check(params::PortableBinary, &value, &expected);
+    check(params::NativeBinary, &value, &expected);
+    check(params::EscapedAscending, &value, &expected);
+    check(params::AscendingOrder, &Filtered(&[data.clone(), vec![]].concat()), &expected.1);
+    check(params::AscendingOrder, &(Desc(Filtered(&data)), "y"), &(Desc(expected.1.clone()), "y"));
+    check(params::CompactAscending, &value, &expected);
+    // nested sequences of unknown length
+    struct Nested<'a>(&'a [Vec<Vec<String>>]);
+    impl Serialize for Nested<'_> {
+        fn serialize<S: serde::Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+            serializer.collect_seq(self.0.iter().filter(|_| true).map(|v| Filtered(v)))
+        }
+    }
+    let nested = [data.clone(), vec![], data.clone()];
+    let expected_nested = vec![expected.1.clone(), vec![], expected.1.clone()];
+    check(params::AscendingOrder, &Nested(&nested), &expected_nested);
+    check(params::PortableBinary, &Nested(&nested), &expected_nested);
+
+    let map = FilteredMap(&[(1, "a"), (0, "b"), (2, "c")]);
+    let mut expected_map = std::collections::BTreeMap::new();
+    expected_map.insert(1_u8, "a");
+    expected_map.insert(2_u8, "c");
+    check(params::AscendingOrder, &map, &expected_map);
+    check(params::PortableBinary, &map, &expected_map);
+
+    let buf = serialize_asc(&value).unwrap();
+    let decoded: (String, Vec<Vec<String>>, Vec<Vec<String>>, u8) = deserialize_asc(&buf).unwrap();
+    assert_eq!((decoded.0.as_str(), decoded.1, decoded.2, decoded.3), expected);
+}
+
+#[test]
+fn test_flatten_serialize() {
+    use serde::ser::SerializeMap;
+
+    #[derive(Serialize)]
+    struct Inner { b: u16, c: String }
+    #[derive(Serialize)]
+    struct Outer { a: u8, #[serde(flatten)] inner: Inner }
+
+    struct Expected;
+    impl Serialize for Expected {
+        fn serialize<S: serde::Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+            let mut map = serializer.serialize_map(Some(3))?;
+            map.serialize_entry("a", &1_u8)?;
+            map.serialize_entry("b", &2_u16)?;
+            map.serialize_entry("c", "c")?;
+            map.end()
+        }
+    }
+    let value = Outer { a: 1, inner: Inner { b: 2, c: "c".to_string() } };
+    let buf = serialize_asc(&value).unwrap();
+    assert_eq!(buf.len(), calc_size_asc(&value).unwrap());
+    assert_eq!(buf, serialize_asc(&Expected).unwrap());
+}
+
+fn serialize_tagged<T: Serialize + ?Sized>(v: &T) -> Vec<u8> {
+    let mut buf = vec![0_u8; calc_size(v, params::SelfDescribing).unwrap()];
+    let mut writer = DeBytesWriter::new(&mut buf);
+    let mut ser = Serializer::new(&mut writer, params::SelfDescribing);
+    v.serialize(&mut ser).unwrap();
+    writer.is_complete().unwrap();
+    buf
+}
+
+fn deserialize_tagged<T: DeserializeOwned>(b: &[u8]) -> Result<T> {
+    let mut reader = DeBytesReader::new(b);
+    let mut deser = Deserializer::new(&mut reader, params::SelfDescribing);
+    let v = T::deserialize(&mut deser)?;
+    reader.is_complete()?;
+    Ok(v)
+}
+
+#[test]
+fn test_self_describing() {
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    enum E { Unit, Newtype(u32), Tuple(i64, String), Struct { a: Option<u8>, b: Vec<u16> } }
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Rec {
+        id: u64,
+        name: String,
+        #[serde(with = "serde_bytes")]
+        data: Vec<u8>,
+        items: Vec<E>,
+        map: HashMap<String, (bool, char)>,
+        nested: Option<Desc<(f32, i128)>>,
+        unit: (),
+    }
+    let mut map = HashMap::new();
+    map.insert("k".to_string(), (true, 'x'));
+    let value = Rec {
+        id: 1_000_000,
+        name: "name".to_string(),
+        data: vec![0, 1, 2],
+        items: vec![E::Unit, E::Newtype(7), E::Tuple(-5, "t".to_string()), E::Struct { a: None, b: vec![3] }],
+        map,
+        nested: Some(Desc((1.5, -1))),
+        unit: (),
+    };
+    let buf = serialize_tagged(&value);
+    assert_eq!(deserialize_tagged::<Rec>(&buf).unwrap(), value);
+
+    // primitives are prefixed with type tag, strings identify enum variants and struct fields
+    assert_eq!(serialize_tagged(&1_u8), [params::TypeTag::U8 as u8, 1]);
+    assert_eq!(serialize_tagged(&E::Unit), [params::TypeTag::Enum as u8, params::TypeTag::Str as u8, b'U', b'n',
+        b'i', b't', params::TypeTag::Unit as u8, 9 /* varint length 4 */]);
+
+    // type mismatch
+    assert!(matches!(deserialize_tagged::<u16>(&serialize_tagged("1")), Err(Error::Custom(_))));
+    // unknown tag
+    assert!(matches!(deserialize_tagged::<u8>(&[0xFF, 1]), Err(Error::InvalidTagEncoding)));
+}
+
+#[test]
+fn test_self_describing_skip_fields() {
+    #[derive(Serialize)]
+    struct V2 { a: u32, extra: Vec<(String, Option<i8>)>, b: String, more: HashMap<u8, ()> }
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct V1 { a: u32, b: String }
+
+    let mut more = HashMap::new();
+    more.insert(1, ());
+    let v2 = V2 { a: 5, extra: vec![("x".to_string(), Some(-1)), ("y".to_string(), None)], b: "b".to_string(), more };
+    let buf = serialize_tagged(&v2);
+    assert_eq!(deserialize_tagged::<V1>(&buf).unwrap(), V1 { a: 5, b: "b".to_string() });
+    // ignore whole value
+    assert!(deserialize_tagged::<serde::de::IgnoredAny>(&buf).is_ok());
+    // not supported without type tags
+    let buf = serialize_asc(&5_u32).unwrap();
+    assert!(matches!(deserialize_asc::<serde::de::IgnoredAny>(&buf), Err(Error::DeserializeIgnoredAny)));
+}
+
+#[test]
+fn test_self_describing_enums() {
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    #[serde(untagged)]
+    enum Untagged { Int(u32), Str(String), Pair(u8, bool) }
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    #[serde(tag = "type")]
+    enum Internal { A { x: i32 }, B { y: String, z: Option<u64> } }
+
+    for v in &[Untagged::Int(10), Untagged::Str("s".to_string()), Untagged::Pair(1, true)] {
+        assert_eq!(&deserialize_tagged::<Untagged>(&serialize_tagged(v)).unwrap(), v);
+    }
+    for v in &[Internal::A { x: -3 }, Internal::B { y: "y".to_string(), z: Some(1) }] {
+        assert_eq!(&deserialize_tagged::<Internal>(&serialize_tagged(v)).unwrap(), v);
+    }
+}
+
+#[test]
+fn test_versioned_structs() {
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct InnerV1 { a: u8 }
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct InnerV2 { a: u8, #[serde(default)] b: Vec<String> }
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct RecV1 { id: u32, name: String, inner: InnerV1 }
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct RecV2 {
+        id: u32,
+        name: String,
+        inner: InnerV2,
+        #[serde(default)] tags: Vec<String>,
+        #[serde(default)] desc: Option<Desc<String>>,
+    }
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct RecV3 { id: u32, name: String, inner: InnerV2, extra: u8 }
+
+    fn ser_to_vec<P: params::SerializerParams, T: Serialize>(value: &T, params: P) -> Vec<u8> {
+        let mut ser = Serializer::new(buf::DeVecWriter::new(), params);
+        value.serialize(&mut ser).unwrap();
+        ser.into_writer().into_vec()
+    }
+    // serialize with `P`, read with `Versioned<P>`, as is and inside `Desc`
+    fn check<P, T, R>(params: P, value: &T) -> Result<R>
+        where P: params::SerializerParams, T: Serialize, R: DeserializeOwned + PartialEq + Debug,
+    {
+        let buf = ser_to_vec(value, params);
+        assert_eq!(buf, ser_to_vec(value, params::Versioned(params)));
+        let mut reader = DeBytesReader::new(&buf);
+        let v = R::deserialize(&mut Deserializer::new(&mut reader, params::Versioned(params)))?;
+        reader.is_complete()?;
+        let buf = ser_to_vec(&Desc(value), params);
+        let d = <Desc<R>>::deserialize(&mut Deserializer::new(DeBytesReader::new(&buf), params::Versioned(params)))?;
+        assert_eq!(d.0, v);
+        Ok(v)
+    }
+    fn check_all<T: Serialize, R: DeserializeOwned + PartialEq + Debug>(value: &T, expected: &R) {
+        assert_eq!(&check::<_, _, R>(params::AscendingOrder, value).unwrap(), expected);
+        assert_eq!(&check::<_, _, R>(params::CompactAscending, value).unwrap(), expected);
+        assert_eq!(&check::<_, _, R>(params::PortableBinary, value).unwrap(), expected);
+        assert_eq!(&check::<_, _, R>(params::EscapedAscending, value).unwrap(), expected);
+        assert_eq!(&check::<_, _, R>(params::SelfDescribing, value).unwrap(), expected);
+    }
+
+    // old data is read by new readers
+    let v1 = RecV1 { id: 1, name: "name".to_string(), inner: InnerV1 { a: 2 } };
+    let v1_as_v2 = RecV2 { id: 1, name: "name".to_string(), inner: InnerV2 { a: 2, b: Vec::new() },
+        tags: Vec::new(), desc: None };
+    check_all(&v1, &v1_as_v2);
+    // field without default value
+    assert!(check::<_, _, RecV3>(params::AscendingOrder, &v1).is_err());
+    // missing fields are not absent without versioned structs
+    assert!(deserialize_asc::<RecV2>(&serialize_asc(&v1).unwrap()).is_err());
+
+    // new data is read by new and old readers, old readers leave extra fields unread
+    let v2 = RecV2 { id: 3, name: "n".to_string(), inner: InnerV2 { a: 4, b: vec!["b".to_string()] },
+        tags: vec!["t1".to_string(), "t2".to_string()], desc: Some(Desc("d".to_string())) };
+    check_all(&v2, &v2);
+    let buf = serialize_asc(&v2).unwrap();
+    let mut reader = DeBytesReader::new(&buf);
+    let old = RecV1::deserialize(&mut Deserializer::new(&mut reader, params::Versioned(params::AscendingOrder))).unwrap();
+    assert_eq!(old, RecV1 { id: 3, name: "n".to_string(), inner: InnerV1 { a: 4 } });
+    assert!(reader.is_complete().is_err());
+
+    // end of stream is not mistaken for the end of stream buffer
+    let params = params::Versioned(params::PortableBinary);
+    for (value, expected) in [(ser_to_vec(&v2, params), &v2), (ser_to_vec(&v1, params), &v1_as_v2)] {
+        let mut reader = buf::IoReader::new(std::io::BufReader::with_capacity(1, value.as_slice()));
+        assert_eq!(&RecV2::deserialize(&mut Deserializer::new(&mut reader, params)).unwrap(), expected);
+    }
+}
+
+#[test]
+fn test_error_context() {
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Item { id: u16, name: String }
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Rec { items: Vec<Item>, pair: (u8, Desc<Item>) }
+
+    let value = Rec {
+        items: vec![Item { id: 1, name: "a".to_string() }, Item { id: 2, name: "b".to_string() }],
+        pair: (3, Desc(Item { id: 4, name: "c".to_string() })),
+    };
+    let buf = serialize_asc(&value).unwrap();
+
+    // serialization into too small buffer
+    let mut short = vec![0_u8; buf.len() - 2];
+    let mut writer = DeBytesWriter::new(&mut short);
+    let mut ser = Serializer::new(&mut writer, params::AscendingOrder);
+    assert!(matches!(value.serialize(&mut ser), Err(Error::BufferOverflow)));
+    let context = ser.error_context().unwrap();
+    assert_eq!(context.path(), "Rec.pair[1].name");
+    assert_eq!((context.head_offset(), context.tail_offset()), (9, 3));
+    assert_eq!(context.to_string(), "at Rec.pair[1].name, head offset 9, tail offset 3");
+
+    // deserialization of invalid data: second item name is not valid UTF-8
+    let mut bad = buf.clone();
+    bad[5] = 0xFF;
+    let mut reader = DeBytesReader::new(&bad);
+    let mut deser = Deserializer::new(&mut reader, params::AscendingOrder);
+    assert!(matches!(Rec::deserialize(&mut deser), Err(Error::InvalidUtf8Encoding)));
+    assert_eq!(deser.error_context().unwrap().path(), "Rec.items[1].name");
+
+    // map entries and sequences of unknown length
+    let mut map = HashMap::new();
+    map.insert(1_u8, vec![Item { id: 5, name: "x".to_string() }]);
+    let buf = serialize_asc(&map).unwrap();
+    let mut reader = DeBytesReader::new(&buf[..buf.len() - 1]);
+    let mut deser = Deserializer::new(&mut reader, params::AscendingOrder);
+    assert!(HashMap::<u8, Vec<Item>>::deserialize(&mut deser).is_err());
+    assert!(deser.error_context().unwrap().path().starts_with("[0]"));
+
+    struct Unknown;
+    impl Serialize for Unknown {
+        fn serialize<S: serde::Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+            serializer.collect_seq(std::iter::repeat_n("item", 3).filter(|_| true))
+        }
+    }
+    let mut short = [0_u8; 10];
+    let mut writer = DeBytesWriter::new(&mut short);
+    let mut ser = Serializer::new(&mut writer, params::AscendingOrder);
+    assert!(matches!((7_u8, Unknown).serialize(&mut ser), Err(Error::BufferOverflow)));
+    // items are written as they come, so the failed one is known
+    assert_eq!(ser.error_context().unwrap().path(), "[1][2]");
+}
+
+#[test]
+fn test_custom_error_message() {
+    #[derive(Serialize, Deserialize, Debug)]
+    #[serde(try_from = "u8")]
+    struct Percent(u8);
+    impl std::convert::TryFrom<u8> for Percent {
+        type Error = String;
+        fn try_from(v: u8) -> std::result::Result<Self, String> {
+            if v <= 100 { Ok(Percent(v)) } else { Err(format!("{v} is not a percentage")) }
+        }
+    }
+    let buf = serialize_asc(&200_u8).unwrap();
+    match deserialize_asc::<Percent>(&buf) {
+        Err(Error::Custom(msg)) => assert_eq!(msg.as_str(), "200 is not a percentage"),
+        r => panic!("unexpected result {:?}", r),
+    }
+
+    // unknown variant in self-describing format
+    #[derive(Serialize)]
+    enum New { Added }
+    #[derive(Deserialize, Debug)]
+    enum Old { Existing }
+    let err = deserialize_tagged::<Old>(&serialize_tagged(&New::Added)).unwrap_err();
+    assert_eq!(err.to_string(), "unknown variant `Added`, expected `Existing`");
+}
+
+#[test]
+fn test_limits() {
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Node { value: u8, next: Option<Box<Node>> }
+
+    let mut list = None;
+    for i in 0..50 {
+        list = Some(Box::new(Node { value: i, next: list }));
+    }
+    let buf = serialize_asc(&list).unwrap();
+    assert_eq!(de_from_bytes_with_limits::<_, Option<Box<Node>>>(&buf, Limits::new()).unwrap(), list);
+    // each node adds struct and option levels
+    assert!(de_from_bytes_with_limits::<_, Option<Box<Node>>>(&buf, Limits::new().max_depth(100)).is_ok());
+    assert!(matches!(de_from_bytes_with_limits::<_, Option<Box<Node>>>(&buf, Limits::new().max_depth(99)),
+                     Err(Error::DepthLimitExceeded)));
+
+    let value = (vec!["abc".to_string(), "de".to_string()], Desc("fgh".to_string()));
+    let buf = serialize_asc(&value).unwrap();
+    let limits = Limits::new().max_seq_len(3);
+    assert_eq!(de_from_bytes_with_limits::<_, (Vec<String>, Desc<String>)>(&buf, limits).unwrap(), value);
+    let limits = Limits::new().max_seq_len(2);
+    assert!(matches!(de_from_bytes_with_limits::<_, (Vec<String>, Desc<String>)>(&buf, limits),
+                     Err(Error::LengthLimitExceeded)));
+    // 2 items, 5 string bytes, then 3 bytes of string in `Desc`
+    let limits = Limits::new().max_alloc(10);
+    assert!(de_from_bytes_with_limits::<_, (Vec<String>, Desc<String>)>(&buf, limits).is_ok());
+    let limits = Limits::new().max_alloc(9);
+    assert!(matches!(de_from_bytes_with_limits::<_, (Vec<String>, Desc<String>)>(&buf, limits),
+                     Err(Error::AllocationLimitExceeded)));
+
+    // byte strings with escaped encoding
+    let buf = serialize_esc("abcd");
+    let mut reader = DeBytesReader::new(&buf);
+    let mut deser = Deserializer::new(&mut reader, params::EscapedAscending).with_limits(Limits::new().max_seq_len(3));
+    assert!(matches!(String::deserialize(&mut deser), Err(Error::LengthLimitExceeded)));
+
+    // hostile sequence length
+    let mut buf = serialize_asc(&vec![1_u64, 2]).unwrap();
+    *buf.last_mut().unwrap() = 0xFF;
+    assert!(matches!(de_from_bytes_with_limits::<_, Vec<u64>>(&buf, Limits::new()), Err(Error::LengthLimitExceeded)));
+}
+
+#[test]
+fn test_strict_decoding() {
+    fn de_strict<T: DeserializeOwned, P: params::SerializerParams>(b: &[u8], params: P) -> Result<T> {
+        let mut reader = DeBytesReader::new(b);
+        let mut deser = Deserializer::new(&mut reader, params).with_strict_decoding();
+        let v = T::deserialize(&mut deser)?;
+        reader.is_complete()?;
+        Ok(v)
+    }
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    enum E { A, B(Desc<bool>) }
+
+    let value = (true, "abc".to_string(), vec![E::A, E::B(Desc(false))], 1000_u32);
+    let buf = serialize_asc(&value).unwrap();
+    assert_eq!(de_from_bytes_strict::<_, (bool, String, Vec<E>, u32)>(&buf).unwrap(), value);
+
+    // bool must be 0 or 1, also when inverted
+    let mut bad = buf.clone();
+    bad[0] = 2;
+    assert!(deserialize_asc::<(bool, String, Vec<E>, u32)>(&bad).unwrap().0);
+    assert!(matches!(de_from_bytes_strict::<_, (bool, String, Vec<E>, u32)>(&bad), Err(Error::InvalidBoolEncoding)));
+    assert!(matches!(de_from_bytes_strict::<_, Desc<bool>>(&[0xFD]), Err(Error::InvalidBoolEncoding)));
+    // trailing bytes
+    let mut long = buf.clone();
+    long.insert(10, 0);
+    assert!(matches!(de_from_bytes_strict::<_, (bool, String, Vec<E>, u32)>(&long), Err(Error::TrailingBytes)));
+
+    // string length 3 in 1 byte and in 2 bytes
+    assert_eq!(de_strict::<String, _>(&[7, b'a', b'b', b'c'], params::PortableBinary).unwrap(), "abc");
+    assert!(matches!(de_strict::<String, _>(&[14, 0, b'a', b'b', b'c'], params::PortableBinary),
+                     Err(Error::InvalidVarintEncoding)));
+    // enum discriminant 1 in 2 bytes
+    assert!(matches!(de_strict::<E, _>(&[6, 0, 1], params::PortableBinary), Err(Error::InvalidVarintEncoding)));
+    // compact integer 5 in 4 bytes
+    assert_eq!(de_strict::<u32, _>(&[5], params::CompactAscending).unwrap(), 5);
+    assert!(matches!(de_strict::<u32, _>(&[250, 0, 0, 5], params::CompactAscending), Err(Error::InvalidVarintEncoding)));
+}
+
+#[test]
+fn test_validate() {
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    enum E { A, B(String), C { bytes: Cow<'static, [u8]> } }
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Rec { id: u32, name: String, tags: Vec<(String, Desc<String>)>, items: HashMap<String, E>, opt: Option<Box<str>> }
+
+    // values are checked by `Deserialize` implementations
+    #[derive(Deserialize)]
+    #[serde(try_from = "String")]
+    #[allow(dead_code)]
+    struct NonEmpty(String);
+    impl std::convert::TryFrom<String> for NonEmpty {
+        type Error = &'static str;
+        fn try_from(s: String) -> std::result::Result<Self, Self::Error> {
+            if s.is_empty() { Err("empty") } else { Ok(Self(s)) }
+        }
+    }
+    #[derive(Deserialize)]
+    #[allow(dead_code)]
+    struct Id(#[serde(with = "serde_bytes")] [u8; 4]);
+
+    let mut items = HashMap::new();
+    items.insert("a".to_string(), E::A);
+    items.insert("b\0".to_string(), E::B("x\0y".to_string()));
+    items.insert("c".to_string(), E::C { bytes: Cow::Owned(vec![0, 1, 255]) });
+    let rec = Rec { id: 7, name: "rec".to_string(), tags: vec![("t".to_string(), Desc("d\0".to_string()))],
+                    items, opt: Some("opt".into()) };
+
+    let buf = serialize_asc(&rec).unwrap();
+    validate::<Rec>(&buf, params::AscendingOrder).unwrap();
+    validate::<Vec<NonEmpty>>(&serialize_asc(&vec!["abc", "de"]).unwrap(), params::AscendingOrder).unwrap();
+    assert!(matches!(validate::<Vec<NonEmpty>>(&serialize_asc(&vec!["abc", ""]).unwrap(), params::AscendingOrder),
+        Err(Error::Custom(msg)) if msg.as_str() == "empty"));
+    let id = serialize_asc(&serde_bytes::Bytes::new(&[1, 2, 3, 4])).unwrap();
+    validate::<Id>(&id, params::AscendingOrder).unwrap();
+    validate::<(&str, Vec<&str>)>(&serialize_asc(&("a", vec!["b", "c"])).unwrap(), params::AscendingOrder).unwrap();
+    validate::<Rec>(&serialize_esc(&rec), params::EscapedAscending).unwrap();
+    validate::<Rec>(&serialize_tagged(&rec), params::SelfDescribing).unwrap();
+
+    // corrupted input
+    let mut bad = buf.clone();
+    bad[4] = 0xFF;
+    assert!(matches!(validate::<Rec>(&bad, params::AscendingOrder), Err(Error::InvalidUtf8Encoding)));
+    assert!(validate::<Rec>(&buf[1..], params::AscendingOrder).is_err());
+    let long = serialize_asc(&(1_u32, "a", 5_u8)).unwrap();
+    assert!(matches!(validate::<(u32, String)>(&long, params::AscendingOrder), Err(Error::TrailingBytes)));
+    let mut esc = serialize_esc(&"a\0b");
+    esc[2] = 3;
+    assert!(matches!(validate::<String>(&esc, params::EscapedAscending), Err(Error::InvalidByteSequenceEscape)));
+
+    // self-describing input with enums which need `deserialize_any()`
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    #[serde(tag = "type")]
+    enum Internal { A { x: i32 }, B { y: String, z: Option<u64> } }
+    let v = vec![Internal::A { x: -3 }, Internal::B { y: "y".to_string(), z: Some(1) }];
+    validate::<Vec<Internal>>(&serialize_tagged(&v), params::SelfDescribing).unwrap();
+    let mut bad = serialize_tagged(&v);
+    bad[0] = 0xFF;
+    assert!(matches!(validate::<Vec<Internal>>(&bad, params::SelfDescribing), Err(Error::InvalidTagEncoding)));
+
+    // deserializer with options
+    let mut reader = DeBytesReader::new(&[1_u8, 2]);
+    let mut deser = Deserializer::new(&mut reader, params::AscendingOrder).with_strict_decoding();
+    assert!(matches!(deser.validate::<(u8, bool)>(), Err(Error::InvalidBoolEncoding)));
+}
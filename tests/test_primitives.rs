@@ -1,28 +1,28 @@
 #![allow(clippy::float_cmp)]
 
-use ordcode::{*, params::*, primitives::SerializableValue, bytes_esc  };
+use ordcode::{*, params::*, primitives::SerializableValue, bytes_esc, buf::ReadBytes };
 use std::{f32, f64};
 
 // test values, few normal ones plus corner cases
-const V_U8:  &[u8]  = &[u8::min_value(), 0, 1, 10, 130, u8::max_value()];
-const V_U16: &[u16] = &[u16::min_value(), 0, 1, 10, 1000, 65000, u16::max_value()];
-const V_U32: &[u32] = &[u32::min_value(), 0, 1, 10, 65000, 999999, u32::max_value()];
-const V_U64: &[u64] = &[u64::min_value(), 0, 1, 65000, 999999,
-    (2<<40) + 999, u64::max_value()];
-
-const V_I8:  &[i8]  = &[i8::min_value(), 0, 1, 10, 99, -1, -10, -99, i8::max_value()];
-const V_I16: &[i16] = &[i16::min_value(), 0, 1, 10, 1000, 32700,
-    -1, -10, -1000, -32700, i16::max_value()];
-const V_I32: &[i32] = &[i32::min_value(), 0, 1, 10, 65000, 999999,
-    -1, -10, -65000, -999999, i32::max_value()];
-const V_I64: &[i64] = &[i64::min_value(), 0, 1, 65000, 999999, (2<<40) + 999,
-    -1, -65000, -999999, -((2<<40) + 999), i64::max_value()];
+const V_U8:  &[u8]  = &[u8::MIN, 0, 1, 10, 130, u8::MAX];
+const V_U16: &[u16] = &[u16::MIN, 0, 1, 10, 1000, 65000, u16::MAX];
+const V_U32: &[u32] = &[u32::MIN, 0, 1, 10, 65000, 999999, u32::MAX];
+const V_U64: &[u64] = &[u64::MIN, 0, 1, 65000, 999999,
+    (2<<40) + 999, u64::MAX];
+
+const V_I8:  &[i8]  = &[i8::MIN, 0, 1, 10, 99, -1, -10, -99, i8::MAX];
+const V_I16: &[i16] = &[i16::MIN, 0, 1, 10, 1000, 32700,
+    -1, -10, -1000, -32700, i16::MAX];
+const V_I32: &[i32] = &[i32::MIN, 0, 1, 10, 65000, 999999,
+    -1, -10, -65000, -999999, i32::MAX];
+const V_I64: &[i64] = &[i64::MIN, 0, 1, 65000, 999999, (2<<40) + 999,
+    -1, -65000, -999999, -((2<<40) + 999), i64::MAX];
 #[cfg(not(no_i128))]
-const V_U128: &[u128] = &[u128::min_value(), 0, 1, 65000, 999999,
-                                (2<<90) + 999, u128::max_value()];
+const V_U128: &[u128] = &[u128::MIN, 0, 1, 65000, 999999,
+                                (2<<90) + 999, u128::MAX];
 #[cfg(not(no_i128))]
-const V_I128: &[i128] = &[i128::min_value(), 0, 1, 65000, 999999, (2<<90) + 999,
-        -1, -65000, -999999, -((2<<90) + 999), i128::max_value()];
+const V_I128: &[i128] = &[i128::MIN, 0, 1, 65000, 999999, (2<<90) + 999,
+        -1, -65000, -999999, -((2<<90) + 999), i128::MAX];
 const V_BOOL: &[bool] = &[true, false];
 
 const V_F32: &[f32] = &[f32::NEG_INFINITY, f32::MIN, 0.0, f32::MIN_POSITIVE,
@@ -35,7 +35,7 @@ macro_rules! test_ser {
         #[test]
         fn $sfn() {
             for val in $tvs {
-                let buf = &mut vec![0_u8; 128];
+                let buf = &mut [0_u8; 128];
                 let mut bb  = DeBytesWriter::new(buf);
                 val.to_writer(&mut bb, AscendingOrder).unwrap();
                 val.to_writer(WriteToTail(&mut bb), DescendingOrder).unwrap();
@@ -190,6 +190,22 @@ fn test_esc_enclen_desc() {
     assert!(v.len() == len);
 }
 
+fn esc_bytes_followed_by_data(param: impl EncodingParams) {
+    let data = vec![1u8, 0xF8, 2, 0xFF, 3];
+    let mut s = vec![];
+    bytes_esc::serialize_bytes(&mut s, data.as_slice(), param).unwrap();
+    s.extend_from_slice(&[0xAA, 0xBB]);
+    let mut r = DeBytesReader::new(&s);
+    assert_eq!(bytes_esc::deserialize_bytes_to_vec(&mut r, param).unwrap(), data);
+    assert_eq!(r.remaining_buffer(), &[0xAA, 0xBB]);
+}
+
+#[test]
+fn bytes_esc_followed_by_data() {
+    esc_bytes_followed_by_data(AscendingOrder);
+    esc_bytes_followed_by_data(DescendingOrder);
+}
+
 fn cmp_esc_bytes_nested(param: impl EncodingParams) {
     let data = vec![0, 0u8,0,0xFF,0xF8,7, 3,1,0,0xFF,0,0xFE,1,2,3,0,1,
                               0xF1, 0xF1, 0xFF, 0xF1, 0x01, 0x0E, 0x00, 0x0E, 0xFE ];
@@ -215,4 +231,88 @@ fn bytes_esc_nested_asc() {
 #[test]
 fn bytes_esc_nested_desc() {
     cmp_esc_bytes_nested(DescendingOrder);
-}
\ No newline at end of file
+}
+fn cmp_esc_bytes_ordered(param: impl EncodingParams) {
+    let data = vec![0, 0u8,0,0xFF,0xF8,7, 3,1,0,0xFF,0,0xFE,1,2,3,0,1, 0xF1, 0x00, 0x0E, 0xFE ];
+    let mut s = vec![];
+    bytes_esc::serialize_bytes_ordered(&mut s, data.as_slice(), param).unwrap();
+    assert_eq!(s.len(), bytes_esc::escaped_length_ordered(&data));
+    let mut r = DeBytesReader::new(&s);
+    assert_eq!(bytes_esc::bytes_length_ordered(&mut r, param).unwrap(), data.len());
+    let dv = bytes_esc::deserialize_bytes_ordered_to_vec(&mut r, param).unwrap();
+    assert_eq!(data, dv);
+    r.is_complete().unwrap();
+}
+
+#[test]
+fn bytes_esc_ordered_asc() {
+    cmp_esc_bytes_ordered(AscendingOrder);
+    let mut s = vec![];
+    bytes_esc::serialize_bytes_ordered(&mut s, &[1, 0, 2], AscendingOrder).unwrap();
+    assert_eq!(bytes_esc::scan_escaped_ordered(&s, AscendingOrder).unwrap(), (4, 1));
+    let mut s = vec![];
+    bytes_esc::serialize_bytes_ordered(&mut s, &[1, 0, 2], DescendingOrder).unwrap();
+    assert_eq!(bytes_esc::scan_escaped_ordered(&s, DescendingOrder).unwrap(), (4, 1));
+}
+
+#[test]
+fn bytes_esc_ordered_desc() {
+    cmp_esc_bytes_ordered(DescendingOrder);
+}
+
+type Key = (u32, Option<i16>, [u8; 3], String, Vec<u8>, (bool, f32));
+
+fn composite_keys() -> Vec<Key> {
+    vec![
+        (1, None, *b"abc", String::new(), vec![], (false, 0.0)),
+        (1, None, *b"abc", "a".to_string(), vec![0, 1], (true, -1.5)),
+        (1, None, *b"abc", "a\0".to_string(), vec![], (false, 0.0)),
+        (1, Some(-5), *b"abc", "x".to_string(), vec![0xF8], (false, 0.0)),
+        (1, Some(5), *b"\0\0\0", "x".to_string(), vec![0xF8], (false, 0.0)),
+        (2, None, *b"\xFF\xFF\xFF", String::new(), vec![], (false, f32::MIN)),
+    ]
+}
+
+#[test]
+fn test_composite_keys() {
+    let keys = composite_keys();
+    let mut prev: Option<(Vec<u8>, Vec<u8>)> = None;
+    for key in &keys {
+        let mut asc = Vec::new();
+        key.to_writer(&mut asc, AscendingOrder).unwrap();
+        let mut desc = Vec::new();
+        key.to_writer(&mut desc, DescendingOrder).unwrap();
+
+        // same layout as serde serializer with `EscapedAscending`
+        let serde_key = (key.0, key.1, key.2, &key.3, serde_bytes::Bytes::new(&key.4), key.5);
+        let mut esc = vec![0_u8; calc_size(&serde_key, EscapedAscending).unwrap()];
+        let mut writer = DeBytesWriter::new(&mut esc);
+        serde::Serialize::serialize(&serde_key, &mut Serializer::new(&mut writer, EscapedAscending)).unwrap();
+        assert_eq!(asc, esc);
+        primitives::invert_buffer(&mut esc);
+        assert_eq!(desc, esc);
+
+        let mut reader = DeBytesReader::new(&asc);
+        assert_eq!(&Key::from_reader(&mut reader, AscendingOrder).unwrap(), key);
+        reader.is_complete().unwrap();
+        let mut reader = DeBytesReader::new(&desc);
+        assert_eq!(&Key::from_reader(&mut reader, DescendingOrder).unwrap(), key);
+        reader.is_complete().unwrap();
+
+        if let Some((prev_asc, prev_desc)) = prev {
+            assert!(prev_asc < asc);
+            assert!(prev_desc > desc);
+        }
+        prev = Some((asc, desc));
+    }
+
+    let mut buf = Vec::new();
+    "abc".to_writer(&mut buf, AscendingOrder).unwrap();
+    b"abc"[..].to_writer(&mut buf, AscendingOrder).unwrap();
+    let mut reader = DeBytesReader::new(&buf);
+    assert_eq!(<(String, Vec<u8>)>::from_reader(&mut reader, AscendingOrder).unwrap(), ("abc".to_string(), b"abc".to_vec()));
+
+    assert!(matches!(<Option<u8>>::from_reader(DeBytesReader::new(&[2, 0]), AscendingOrder), Err(Error::InvalidTagEncoding)));
+    assert!(matches!(<[u16; 3]>::from_reader(DeBytesReader::new(&[0, 1, 0, 2, 0]), AscendingOrder), Err(Error::PrematureEndOfInput)));
+    assert!(matches!(<String>::from_reader(DeBytesReader::new(&[0xFF, 0, 1]), AscendingOrder), Err(Error::InvalidUtf8Encoding)));
+}
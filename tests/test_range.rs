@@ -0,0 +1,76 @@
+use std::collections::BTreeMap;
+use std::ops::Bound;
+use ordcode::{ *, key::KeyBuilder, range::{ prefix_range, prefix_successor, PrefixRange } };
+use serde::ser::Serialize;
+
+fn key<T: Serialize + ?Sized>(value: &T, order: Order) -> Vec<u8> {
+    let mut buf = vec![0_u8; calc_size(value, params::EscapedAscending).unwrap()];
+    value.serialize(&mut Serializer::new(DeBytesWriter::new(&mut buf), params::EscapedAscending)).unwrap();
+    if matches!(order, Order::Descending) {
+        primitives::invert_buffer(&mut buf);
+    }
+    buf
+}
+
+const KEYS: &[(u32, &str, u64)] = &[
+    (0, "a", 1), (1, "", 2), (1, "a", 3), (1, "a\0", 4), (1, "a\u{ff}", 5), (1, "ab", 6),
+    (1, "a", 7), (1, "b", 8), (2, "a", 9), (u32::MAX, "a", 10), (u32::MAX, "\u{ff}", 11),
+];
+
+fn scan<P: Serialize + ?Sized>(prefix: &P, order: Order) -> Vec<u64> {
+    let map: BTreeMap<_, _> = KEYS.iter().map(|k| (key(k, order), k.2)).collect();
+    let range = prefix_range(prefix, order).unwrap();
+    let mut ids: Vec<_> = map.range(range.clone()).map(|(k, id)| {
+        assert!(range.contains(k));
+        *id
+    }).collect();
+    let expected = map.iter().filter(|(k, _)| range.contains(k)).count();
+    assert_eq!(ids.len(), expected);
+    ids.sort_unstable();
+    ids
+}
+
+#[test]
+fn test_prefix_range() {
+    for order in &[Order::Ascending, Order::Descending] {
+        let order = *order;
+        assert_eq!(scan(&(1_u32, "a"), order), vec![3, 7]);
+        assert_eq!(scan(&(1_u32, "a\0"), order), vec![4]);
+        assert_eq!(scan(&(1_u32, ""), order), vec![2]);
+        assert_eq!(scan(&(1_u32,), order), vec![2, 3, 4, 5, 6, 7, 8]);
+        assert_eq!(scan(&(u32::MAX,), order), vec![10, 11]);
+        assert_eq!(scan(&(u32::MAX, "\u{ff}"), order), vec![11]);
+        assert_eq!(scan(&(3_u32,), order), Vec::<u64>::new());
+        assert_eq!(scan(&(1_u32, "a", 7_u64), order), vec![7]);
+        assert_eq!(scan(&(), order), (1..=11).collect::<Vec<_>>());
+    }
+}
+
+#[test]
+fn test_prefix_successor() {
+    assert_eq!(prefix_successor(&[]), None);
+    assert_eq!(prefix_successor(&[0xFF, 0xFF]), None);
+    assert_eq!(prefix_successor(&[0]), Some(vec![1]));
+    assert_eq!(prefix_successor(&[1, 0xFE]), Some(vec![1, 0xFF]));
+    assert_eq!(prefix_successor(&[1, 0xFF, 0xFF]), Some(vec![2]));
+
+    let range = PrefixRange::new(vec![0xFF]);
+    assert_eq!(range.end(), None);
+    assert_eq!(range.as_slices(), (Bound::Included(&[0xFF_u8][..]), Bound::Unbounded));
+    assert_eq!(range.into_bounds(), (Bound::Included(vec![0xFF]), Bound::Unbounded));
+
+    let range = PrefixRange::new(vec![1, 0xFF]);
+    assert_eq!(range.start(), &[1, 0xFF]);
+    assert_eq!(range.end(), Some(&[2_u8][..]));
+    assert!(range.contains(&[1, 0xFF, 0]));
+    assert!(!range.contains(&[1, 0xFE, 0xFF]));
+}
+
+#[test]
+fn test_prefix_range_key_builder() {
+    let prefix = KeyBuilder::new(Vec::new()).u32(1).str_escaped("a").finish().unwrap();
+    assert_eq!(PrefixRange::new(prefix), prefix_range(&(1_u32, "a"), Order::Ascending).unwrap());
+
+    let prefix = KeyBuilder::new(Vec::new()).u32_desc(1).str_escaped_desc("a").finish().unwrap();
+    assert_eq!(PrefixRange::new(prefix), prefix_range(&(1_u32, "a"), Order::Descending).unwrap());
+}
@@ -0,0 +1,190 @@
+use std::collections::BTreeMap;
+use ordcode::{ *, value::{ Schema, Value } };
+use serde::{ Serialize, de::DeserializeOwned };
+use serde_derive::{ Serialize, Deserialize };
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+enum Kind {
+    Empty,
+    Name(String),
+    Pair(u16, i64),
+    Range { start: u32, end: Option<u32> },
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+struct Record {
+    id: u64,
+    flag: bool,
+    kinds: Vec<Kind>,
+    attrs: BTreeMap<String, i32>,
+    #[serde(with = "serde_bytes")]
+    data: Vec<u8>,
+    extra: (char, f64, u128, i8),
+    unit: (),
+    recent: Desc<(u32, String)>,
+}
+
+fn s(name: &str, schema: Schema) -> (String, Schema) {
+    (name.to_string(), schema)
+}
+
+fn record_schema() -> Schema {
+    let kind = Schema::Enum(vec![
+        s("Empty", Schema::Unit),
+        s("Name", Schema::Str),
+        s("Pair", Schema::Tuple(vec![Schema::U16, Schema::I64])),
+        s("Range", Schema::Struct(vec![s("start", Schema::U32), s("end", Schema::Option(Box::new(Schema::U32)))])),
+    ]);
+    Schema::Struct(vec![
+        s("id", Schema::U64),
+        s("flag", Schema::Bool),
+        s("kinds", Schema::Seq(Box::new(kind))),
+        s("attrs", Schema::Map(Box::new(Schema::Str), Box::new(Schema::I32))),
+        s("data", Schema::Bytes),
+        s("extra", Schema::Tuple(vec![Schema::Char, Schema::F64, Schema::U128, Schema::I8])),
+        s("unit", Schema::Unit),
+        s("recent", Schema::Desc(Box::new(Schema::Tuple(vec![Schema::U32, Schema::Str])))),
+    ])
+}
+
+fn record() -> (Record, Value) {
+    let record = Record {
+        id: 1 << 40,
+        flag: true,
+        kinds: vec![
+            Kind::Empty,
+            Kind::Name("abc".to_string()),
+            Kind::Pair(7, -1),
+            Kind::Range { start: 1, end: None },
+            Kind::Range { start: 2, end: Some(300) },
+        ],
+        attrs: vec![("a".to_string(), -5), ("b\0".to_string(), 1 << 20)].into_iter().collect(),
+        data: vec![0, 1, 0xFF],
+        extra: ('ы', -2.5, u128::MAX, -128),
+        unit: (),
+        recent: Desc((5, "xy".to_string())),
+    };
+    let value = Value::Tuple(vec![
+        Value::U64(1 << 40),
+        Value::Bool(true),
+        Value::Seq(vec![
+            Value::Enum(0, Box::new(Value::Unit)),
+            Value::Enum(1, Box::new(Value::Str("abc".to_string()))),
+            Value::Enum(2, Box::new(Value::Tuple(vec![Value::U16(7), Value::I64(-1)]))),
+            Value::Enum(3, Box::new(Value::Tuple(vec![Value::U32(1), Value::Option(None)]))),
+            Value::Enum(3, Box::new(Value::Tuple(vec![Value::U32(2), Value::Option(Some(Box::new(Value::U32(300))))]))),
+        ]),
+        Value::Map(vec![
+            (Value::Str("a".to_string()), Value::I32(-5)),
+            (Value::Str("b\0".to_string()), Value::I32(1 << 20)),
+        ]),
+        Value::Bytes(vec![0, 1, 0xFF]),
+        Value::Tuple(vec![Value::Char('ы'), Value::F64(-2.5), Value::U128(u128::MAX), Value::I8(-128)]),
+        Value::Unit,
+        Value::Tuple(vec![Value::U32(5), Value::Str("xy".to_string())]),
+    ]);
+    (record, value)
+}
+
+fn check<T, P>(typed: &T, value: &Value, schema: &Schema, params: P)
+    where T: Serialize + DeserializeOwned + PartialEq + std::fmt::Debug,
+          P: params::SerializerParams,
+{
+    let size = calc_size(typed, params).unwrap();
+    assert_eq!(value.calc_size(schema, params).unwrap(), size);
+    let mut buf = vec![0_u8; size];
+    let mut writer = DeBytesWriter::new(&mut buf);
+    typed.serialize(&mut Serializer::new(&mut writer, params)).unwrap();
+    writer.is_complete().unwrap();
+
+    let bytes = value.to_vec(schema, params).unwrap();
+    assert_eq!(bytes, buf);
+    assert_eq!(&Value::from_bytes(&bytes, schema, params).unwrap(), value);
+
+    let mut reader = DeBytesReader::new(&bytes);
+    let decoded = T::deserialize(&mut Deserializer::new(&mut reader, params)).unwrap();
+    assert_eq!(&decoded, typed);
+}
+
+#[test]
+fn test_value_same_as_typed() {
+    let (record, value) = record();
+    let schema = record_schema();
+    check(&record, &value, &schema, params::AscendingOrder);
+    check(&record, &value, &schema, params::EscapedAscending);
+    check(&record, &value, &schema, params::CompactAscending);
+    check(&record, &value, &schema, params::SelfDescribing);
+    check(&record, &value, &schema, params::PortableBinary);
+    check(&record, &value, &schema, params::NativeBinary);
+    check(&record, &value, &schema, params::Versioned(params::AscendingOrder));
+    check(&record, &value, &schema, params::Versioned(params::EscapedAscending));
+    check(&record, &value, &schema, params::Versioned(params::SelfDescribing));
+}
+
+#[test]
+fn test_value_order() {
+    let schema = Schema::Tuple(vec![Schema::U32, Schema::Str, Schema::Desc(Box::new(Schema::I64))]);
+    let keys = [(1_u32, "a", 0_i64), (1, "a", -1), (1, "ab", -5), (2, "", 0)];
+    let encoded: Vec<_> = keys.iter().map(|(a, b, c)| {
+        Value::Tuple(vec![Value::U32(*a), Value::Str((*b).to_string()), Value::I64(*c)])
+            .to_vec(&schema, params::EscapedAscending).unwrap()
+    }).collect();
+    let mut sorted = encoded.clone();
+    sorted.sort();
+    assert_eq!(encoded, sorted);
+}
+
+#[test]
+fn test_value_schema_mismatch() {
+    let schema = Schema::Tuple(vec![Schema::U32, Schema::Str]);
+    let short = Value::Tuple(vec![Value::U32(1)]);
+    assert!(short.to_vec(&schema, params::AscendingOrder).is_err());
+    let wrong = Value::Tuple(vec![Value::U32(1), Value::U32(2)]);
+    assert!(wrong.calc_size(&schema, params::AscendingOrder).is_err());
+    let variant = Value::Enum(2, Box::new(Value::Unit));
+    assert!(variant.to_vec(&Schema::Enum(vec![s("A", Schema::Unit)]), params::AscendingOrder).is_err());
+
+    // unknown variant index
+    let bytes = Value::Enum(1, Box::new(Value::Unit))
+        .to_vec(&Schema::Enum(vec![s("A", Schema::Unit), s("B", Schema::Unit)]), params::AscendingOrder).unwrap();
+    assert!(Value::from_bytes(&bytes, &Schema::Enum(vec![s("A", Schema::Unit)]), params::AscendingOrder).is_err());
+    // trailing bytes
+    assert!(Value::from_bytes(&[0, 0, 0, 1, 0], &Schema::U32, params::AscendingOrder).is_err());
+}
+
+#[test]
+fn test_value_self_describing_struct() {
+    #[derive(Serialize)]
+    struct V2 { b: u8, extra: String, a: u16 }
+
+    let bytes = ser_to_vec(&V2 { b: 1, extra: "x".to_string(), a: 2 }, params::SelfDescribing);
+    let schema = Schema::Struct(vec![s("a", Schema::U16), s("b", Schema::U8)]);
+    assert_eq!(Value::from_bytes(&bytes, &schema, params::SelfDescribing).unwrap(),
+               Value::Tuple(vec![Value::U16(2), Value::U8(1)]));
+    let schema = Schema::Struct(vec![s("a", Schema::U16), s("c", Schema::U8)]);
+    assert!(Value::from_bytes(&bytes, &schema, params::SelfDescribing).is_err());
+}
+
+fn ser_to_vec<T: Serialize, P: params::SerializerParams>(value: &T, params: P) -> Vec<u8> {
+    let mut buf = vec![0_u8; calc_size(value, params).unwrap()];
+    value.serialize(&mut Serializer::new(DeBytesWriter::new(&mut buf), params)).unwrap();
+    buf
+}
+
+#[test]
+fn test_schema_text() {
+    let text = "{id: u64, flag: bool, kinds: seq<enum{Empty, Name(str), Pair(u16, i64), \
+                Range{start: u32, end: option<u32>}}>, attrs: map<str, i32>, data: bytes, \
+                extra: (char, f64, u128, i8), unit: (), recent: desc<(u32, str)>}";
+    let schema: Schema = text.parse().unwrap();
+    assert_eq!(schema, record_schema());
+    assert_eq!(schema.to_string().parse::<Schema>().unwrap(), schema);
+
+    let single: Schema = "enum{A((u8)), B(u8)}".parse().unwrap();
+    assert_eq!(single, Schema::Enum(vec![s("A", Schema::Tuple(vec![Schema::U8])), s("B", Schema::U8)]));
+    assert_eq!(single.to_string().parse::<Schema>().unwrap(), single);
+
+    for bad in &["", "u7", "(u8", "map<str>", "{a u8}", "enum{A(}", "u8 u8"] {
+        assert!(bad.parse::<Schema>().is_err(), "{}", bad);
+    }
+}
@@ -1,3 +1,4 @@
+use std::convert::TryFrom;
 use ordcode::{ *, varint::*  };
 
 // Varint tests are adopted and modified from VInt implementation, github.com/iqlusioninc/veriform
@@ -51,11 +52,11 @@ fn encode_bit_pattern_examples() {
 #[test]
 fn encode_maxint() {
     assert_eq!(
-        encode64(core::u64::MAX).as_ref(),
+        encode64(u64::MAX).as_ref(),
         &[0x00, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]
     );
     assert_eq!(
-        encode32(core::u32::MAX).as_ref(),
+        encode32(u32::MAX).as_ref(),
         &[0xF0, 0xff, 0xff, 0xff, 0xff]
     );
 }
@@ -63,33 +64,33 @@ fn encode_maxint() {
 #[test]
 fn decode_zero() {
     let slice = [1].as_ref();
-    assert_eq!(decode64(&slice).unwrap(), (0, 1));
-    assert_eq!(decode32(&slice).unwrap(), (0, 1));
+    assert_eq!(decode64(slice).unwrap(), (0, 1));
+    assert_eq!(decode32(slice).unwrap(), (0, 1));
 }
 
 #[test]
 fn decode_bit_pattern_examples() {
     let slice = [0x3e, 0x3c].as_ref();
-    assert_eq!(decode64(&slice).unwrap(), (0x0f0f, 2));
-    assert_eq!(decode32(&slice).unwrap(), (0x0f0f, 2));
+    assert_eq!(decode64(slice).unwrap(), (0x0f0f, 2));
+    assert_eq!(decode32(slice).unwrap(), (0x0f0f, 2));
 
     let slice = [0x08, 0x0f, 0xff, 0xf0].as_ref();
-    assert_eq!(decode64(&slice).unwrap(), (0x0f0f_f0f0, 4));
-    assert_eq!(decode32(&slice).unwrap(), (0x0f0f_f0f0, 4));
+    assert_eq!(decode64(slice).unwrap(), (0x0f0f_f0f0, 4));
+    assert_eq!(decode32(slice).unwrap(), (0x0f0f_f0f0, 4));
 
     let slice = [0xc0, 0x87, 0x07, 0x78, 0xf8, 0x87, 0x07].as_ref();
-    assert_eq!(decode64(&slice).unwrap(), (0x0f0f_f0f0_0f0f, 7));
+    assert_eq!(decode64(slice).unwrap(), (0x0f0f_f0f0_0f0f, 7));
 
     let slice = [0x00, 0xf0, 0xf0, 0x0f, 0x0f, 0xf0, 0xf0, 0x0f, 0x0f].as_ref();
-    assert_eq!(decode64(&slice).unwrap(), (0x0f0f_f0f0_0f0f_f0f0, 9));
+    assert_eq!(decode64(slice).unwrap(), (0x0f0f_f0f0_0f0f_f0f0, 9));
 }
 
 #[test]
 fn decode_maxint() {
     let slice64 = [0x00, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff].as_ref();
     let slice32 = [0xf0, 0xff, 0xff, 0xff, 0xff].as_ref();
-    assert_eq!(decode64(slice64).unwrap(), (core::u64::MAX, 9));
-    assert_eq!(decode32(slice32).unwrap(), (core::u32::MAX, 5));
+    assert_eq!(decode64(slice64).unwrap(), (u64::MAX, 9));
+    assert_eq!(decode32(slice32).unwrap(), (u32::MAX, 5));
 }
 
 #[test]
@@ -128,4 +129,119 @@ fn with_buffer() {
     let mut r = DeBytesReader::new(&buf);
     assert_eq!(<u64>::varu_from_reader(ReadFromTail(&mut r)).unwrap(), 12);
     assert_eq!(<u64>::varu_from_reader(&mut r).unwrap(), 11);
-}
\ No newline at end of file
+}
+
+fn encode_ord64(value: u64) -> Box<[u8]> {
+    let mut s = Vec::<u8>::new();
+    value.ord_varu_to_writer(&mut s).unwrap();
+    assert_eq!(s.len(), value.ord_varu_encoded_len() as usize);
+    s.into_boxed_slice()
+}
+
+const ORD_BOUNDARIES: [u64; 20] = [ 0, 1, 127, 128, 240, 241, 2287, 2288, 67823, 67824, 0xff_ffff,
+    0x100_0000, 0xffff_ffff, 0x1_0000_0000, 0xff_ffff_ffff, 0x100_0000_0000, 0xffff_ffff_ffff,
+    0x1_0000_0000_0000, 0x100_0000_0000_0000, u64::MAX ];
+
+#[test]
+fn ord_encode_examples() {
+    assert_eq!(encode_ord64(0).as_ref(), &[0]);
+    assert_eq!(encode_ord64(240).as_ref(), &[240]);
+    assert_eq!(encode_ord64(241).as_ref(), &[241, 1]);
+    assert_eq!(encode_ord64(2287).as_ref(), &[248, 255]);
+    assert_eq!(encode_ord64(2288).as_ref(), &[249, 0, 0]);
+    assert_eq!(encode_ord64(67823).as_ref(), &[249, 255, 255]);
+    assert_eq!(encode_ord64(67824).as_ref(), &[250, 0x01, 0x08, 0xf0]);
+    assert_eq!(encode_ord64(u64::from(u32::MAX)).as_ref(), &[251, 0xff, 0xff, 0xff, 0xff]);
+    assert_eq!(encode_ord64(u64::MAX).as_ref(), &[255, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]);
+}
+
+#[test]
+fn ord_roundtrip() {
+    for v in ORD_BOUNDARIES {
+        let encoded = encode_ord64(v);
+        assert_eq!(<u64>::ord_varu_from_slice(&encoded).unwrap(), (v, encoded.len() as u8));
+        if let Ok(v32) = u32::try_from(v) {
+            let mut s = Vec::<u8>::new();
+            v32.ord_varu_to_writer(&mut s).unwrap();
+            assert_eq!(s.as_slice(), encoded.as_ref());
+            assert_eq!(<u32>::ord_varu_from_reader(&mut DeBytesReader::new(&s)).unwrap(), v32);
+        } else {
+            assert!(<u32>::ord_varu_from_slice(&encoded).is_err());
+        }
+    }
+}
+
+#[test]
+fn ord_preserves_ordering() {
+    let mut values = ORD_BOUNDARIES.to_vec();
+    values.extend((0..70000).step_by(7));
+    values.sort_unstable();
+    values.dedup();
+    for w in values.windows(2) {
+        assert!(encode_ord64(w[0]) < encode_ord64(w[1]), "{} {}", w[0], w[1]);
+    }
+}
+
+#[test]
+fn ord_decode_truncated() {
+    assert!(<u64>::ord_varu_from_slice(&[]).is_err());
+    assert!(<u64>::ord_varu_from_slice(&[241]).is_err());
+    assert!(<u64>::ord_varu_from_slice(&[251, 1, 2, 3]).is_err());
+}
+
+#[test]
+fn ord_decode_non_canonical() {
+    assert!(<u64>::ord_varu_from_slice(&[250, 0, 0, 1]).is_err());
+    assert!(<u64>::ord_varu_from_slice(&[251, 0, 0xff, 0xff, 0xff]).is_err());
+}
+
+fn encode_ord_i64(value: i64) -> Vec<u8> {
+    let mut s = Vec::<u8>::new();
+    value.ord_vari_to_writer(&mut s).unwrap();
+    assert_eq!(s.len(), value.ord_vari_encoded_len() as usize);
+    assert_eq!(s.len(), <i64>::ord_vari_decoded_len(s[0]) as usize);
+    s
+}
+
+const ORD_SIGNED_BOUNDARIES: [i64; 16] = [ 0, 1, 119, 120, 375, 376, 65911, 65912, 16_843_127, 16_843_128,
+    0xffff_ffff, 0x1_0000_0000, 72_340_172_838_076_791, 72_340_172_838_076_792, i64::MAX - 1, i64::MAX ];
+
+#[test]
+fn ord_signed_examples() {
+    assert_eq!(encode_ord_i64(0), &[0x80]);
+    assert_eq!(encode_ord_i64(119), &[0xF7]);
+    assert_eq!(encode_ord_i64(120), &[0xF8, 0]);
+    assert_eq!(encode_ord_i64(-1), &[0x7F]);
+    assert_eq!(encode_ord_i64(-120), &[0x08]);
+    assert_eq!(encode_ord_i64(-121), &[0x07, 0xFF]);
+    assert_eq!(encode_ord_i64(i64::MIN).len(), 9);
+}
+
+#[test]
+fn ord_signed_roundtrip_and_ordering() {
+    let mut values: Vec<i64> = ORD_SIGNED_BOUNDARIES.iter().flat_map(|v| vec![*v, !*v, -*v]).collect();
+    values.extend((-70000..70000).step_by(13));
+    values.sort_unstable();
+    values.dedup();
+    for v in &values {
+        let encoded = encode_ord_i64(*v);
+        assert_eq!(<i64>::ord_vari_from_reader(&mut DeBytesReader::new(&encoded)).unwrap(), *v);
+        if let Ok(v32) = i32::try_from(*v) {
+            let mut s = Vec::<u8>::new();
+            v32.ord_vari_to_writer(&mut s).unwrap();
+            assert_eq!(s, encoded);
+            assert_eq!(<i32>::ord_vari_from_reader(&mut DeBytesReader::new(&s)).unwrap(), v32);
+        } else {
+            assert!(<i32>::ord_vari_from_reader(&mut DeBytesReader::new(&encoded)).is_err());
+        }
+    }
+    for w in values.windows(2) {
+        assert!(encode_ord_i64(w[0]) < encode_ord_i64(w[1]), "{} {}", w[0], w[1]);
+    }
+}
+
+#[test]
+fn ord_signed_invalid() {
+    assert!(<i64>::ord_vari_from_reader(&mut DeBytesReader::new(&[0xF9, 0])).is_err());
+    assert!(<i64>::ord_vari_from_reader(&mut DeBytesReader::new(&[0xFF; 9])).is_err());
+}